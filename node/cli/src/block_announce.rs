@@ -0,0 +1,261 @@
+//! The block announce validator of full nodes: announcements sync would only download to throw
+//! away are rejected before it asks the peer for anything.
+//!
+//! An announced header must carry one BABE pre-digest and end with a BABE seal, have a slot after
+//! that of its parent, if known, and not too far in the future, and not be on a fork that left
+//! the chain below the last finalized block. Headers with broken BABE digests are junk no honest
+//! node sends, and their peer is disconnected. Honest peers can be behind and announce stale
+//! forks, so those are only refused: each refusal costs the peer reputation in sync, which bans
+//! peers that keep announcing them.
+
+use futures::future;
+use node_primitives::{Block, BlockNumber};
+use sc_consensus_babe::find_pre_digest;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::block_validation::{BlockAnnounceValidator, Validation};
+use sp_consensus_babe::digests::CompatibleDigestItem;
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, Header as _},
+};
+use std::{
+    error::Error,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// How many slots ahead of the clock an announced block may be, for peers with a fast clock.
+const MAX_FUTURE_SLOTS: u64 = 10;
+
+type Header = <Block as BlockT>::Header;
+
+/// Why an announcement is refused.
+enum Refusal {
+    /// The BABE digests are broken.
+    Junk(String),
+    /// The header is on a fork below the last finalized block.
+    Stale(String),
+}
+
+/// Validates block announcements against the BABE digests and the finalized chain.
+pub struct CoinnetBlockAnnounceValidator<Client> {
+    client: Arc<Client>,
+    slot_duration: u64,
+}
+
+impl<Client: HeaderBackend<Block>> CoinnetBlockAnnounceValidator<Client> {
+    /// Create a validator for a chain with slots of `slot_duration` milliseconds.
+    pub fn new(client: Arc<Client>, slot_duration: u64) -> Self {
+        CoinnetBlockAnnounceValidator { client, slot_duration }
+    }
+
+    fn check(&self, header: &Header) -> Result<(), Refusal> {
+        if *header.number() == 0 {
+            return Err(Refusal::Junk("announcement of a genesis block".into()));
+        }
+        let slot =
+            *find_pre_digest::<Block>(header).map_err(|e| Refusal::Junk(e.to_string()))?.slot();
+        let sealed = header.digest().logs().last().and_then(|log| log.as_babe_seal()).is_some();
+        if !sealed {
+            return Err(Refusal::Junk("no BABE seal".into()));
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let current_slot = now.as_millis() as u64 / self.slot_duration;
+        if slot > current_slot + MAX_FUTURE_SLOTS {
+            return Err(Refusal::Junk(format!("slot {} is ahead of slot {}", slot, current_slot)));
+        }
+
+        let info = self.client.info();
+        if *header.number() <= info.finalized_number {
+            return self.check_finalized(header.hash(), *header.number());
+        }
+        let parent = match self.client.header(BlockId::Hash(*header.parent_hash())) {
+            Ok(Some(parent)) => parent,
+            // the parent is not known yet, sync will download the fork.
+            _ => return Ok(()),
+        };
+        if *parent.number() <= info.finalized_number {
+            self.check_finalized(parent.hash(), *parent.number())?;
+        }
+        if *parent.number() > 0 {
+            let parent_slot = *find_pre_digest::<Block>(&parent)
+                .map_err(|e| Refusal::Junk(e.to_string()))?
+                .slot();
+            if slot <= parent_slot {
+                return Err(Refusal::Junk(format!(
+                    "slot {} is not after slot {} of the parent",
+                    slot, parent_slot
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a block at or below the last finalized block is on the finalized chain.
+    fn check_finalized(
+        &self,
+        hash: <Block as BlockT>::Hash,
+        number: BlockNumber,
+    ) -> Result<(), Refusal> {
+        match self.client.hash(number) {
+            Ok(Some(finalized)) if finalized != hash => Err(Refusal::Stale(format!(
+                "block #{} ({}) is on a fork below the last finalized block",
+                number, hash
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<Client: HeaderBackend<Block>> BlockAnnounceValidator<Block>
+    for CoinnetBlockAnnounceValidator<Client>
+{
+    fn validate(
+        &mut self,
+        header: &Header,
+        _data: &[u8],
+    ) -> Pin<Box<dyn Future<Output = Result<Validation, Box<dyn Error + Send>>> + Send>> {
+        let validation = match self.check(header) {
+            Ok(()) => Validation::Success { is_new_best: false },
+            Err(Refusal::Junk(reason)) => {
+                log::debug!(target: "sync", "Refused announcement of #{}: {}", header.number(), reason);
+                Validation::Failure { disconnect: true }
+            }
+            Err(Refusal::Stale(reason)) => {
+                log::debug!(target: "sync", "Refused announcement: {}", reason);
+                Validation::Failure { disconnect: false }
+            }
+        };
+        Box::pin(future::ready(Ok(validation)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use node_primitives::{DigestItem, Hash};
+    use sc_client_api::{in_mem::Blockchain, NewBlockState};
+    use sp_consensus_babe::{
+        digests::{PreDigest, SecondaryPlainPreDigest},
+        AuthoritySignature,
+    };
+    use sp_runtime::generic::Digest;
+
+    const SLOT_DURATION: u64 = 3000;
+
+    fn pre_digest(slot: u64) -> DigestItem {
+        DigestItem::babe_pre_digest(PreDigest::SecondaryPlain(SecondaryPlainPreDigest {
+            authority_index: 0,
+            slot: slot.into(),
+        }))
+    }
+
+    fn seal() -> DigestItem {
+        DigestItem::babe_seal(AuthoritySignature::from(sp_core::sr25519::Signature::from_raw(
+            [0; 64],
+        )))
+    }
+
+    fn header(number: BlockNumber, parent: &Header, logs: Vec<DigestItem>) -> Header {
+        Header::new(number, Default::default(), Default::default(), parent.hash(), Digest { logs })
+    }
+
+    fn current_slot() -> u64 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        now.as_millis() as u64 / SLOT_DURATION
+    }
+
+    /// A chain of a genesis block and a block #1 at slot 10, both finalized, and a block #2 at
+    /// slot 11 on top of them.
+    fn chain() -> (Arc<Blockchain<Block>>, Header, Header, Header) {
+        let chain = Blockchain::new();
+        let genesis = Header::new(
+            0,
+            Default::default(),
+            Default::default(),
+            Hash::default(),
+            Default::default(),
+        );
+        let first = header(1, &genesis, vec![pre_digest(10), seal()]);
+        let second = header(2, &first, vec![pre_digest(11), seal()]);
+        chain.insert(genesis.hash(), genesis.clone(), None, None, NewBlockState::Final).unwrap();
+        chain.insert(first.hash(), first.clone(), None, None, NewBlockState::Final).unwrap();
+        chain.insert(second.hash(), second.clone(), None, None, NewBlockState::Best).unwrap();
+        (Arc::new(chain), genesis, first, second)
+    }
+
+    fn validate(chain: &Arc<Blockchain<Block>>, header: &Header) -> Validation {
+        let mut validator = CoinnetBlockAnnounceValidator::new(chain.clone(), SLOT_DURATION);
+        futures::executor::block_on(validator.validate(header, &[])).unwrap()
+    }
+
+    fn is_accepted(validation: Validation) -> bool {
+        matches!(validation, Validation::Success { .. })
+    }
+
+    fn is_junk(validation: Validation) -> bool {
+        matches!(validation, Validation::Failure { disconnect: true })
+    }
+
+    fn is_stale(validation: Validation) -> bool {
+        matches!(validation, Validation::Failure { disconnect: false })
+    }
+
+    #[test]
+    fn accepts_blocks_building_on_the_chain() {
+        let (chain, _, _, second) = chain();
+        assert!(is_accepted(validate(&chain, &header(3, &second, vec![pre_digest(12), seal()]))));
+        assert!(is_accepted(validate(
+            &chain,
+            &header(3, &second, vec![pre_digest(current_slot()), seal()])
+        )));
+        // the parent of the block is not known yet.
+        let unknown = header(3, &second, vec![pre_digest(12), seal()]);
+        assert!(is_accepted(validate(&chain, &header(4, &unknown, vec![pre_digest(13), seal()]))));
+    }
+
+    #[test]
+    fn refuses_missing_or_duplicate_pre_digests() {
+        let (chain, _, _, second) = chain();
+        assert!(is_junk(validate(&chain, &header(3, &second, vec![seal()]))));
+        assert!(is_junk(validate(
+            &chain,
+            &header(3, &second, vec![pre_digest(12), pre_digest(12), seal()])
+        )));
+    }
+
+    #[test]
+    fn refuses_missing_seals() {
+        let (chain, _, _, second) = chain();
+        assert!(is_junk(validate(&chain, &header(3, &second, vec![pre_digest(12)]))));
+        assert!(is_junk(validate(&chain, &header(3, &second, vec![seal(), pre_digest(12)]))));
+    }
+
+    #[test]
+    fn refuses_future_slots() {
+        let (chain, _, _, second) = chain();
+        let slot = current_slot() + MAX_FUTURE_SLOTS + 2;
+        assert!(is_junk(validate(&chain, &header(3, &second, vec![pre_digest(slot), seal()]))));
+    }
+
+    #[test]
+    fn refuses_slots_not_after_the_parent() {
+        let (chain, _, _, second) = chain();
+        assert!(is_junk(validate(&chain, &header(3, &second, vec![pre_digest(11), seal()]))));
+        assert!(is_junk(validate(&chain, &header(3, &second, vec![pre_digest(5), seal()]))));
+    }
+
+    #[test]
+    fn refuses_forks_below_finality_without_disconnecting() {
+        let (chain, genesis, first, _) = chain();
+        // another block #1, which the finalized block #1 rules out.
+        let fork = header(1, &genesis, vec![pre_digest(9), seal()]);
+        assert!(is_stale(validate(&chain, &fork)));
+        chain.insert(fork.hash(), fork.clone(), None, None, NewBlockState::Normal).unwrap();
+        assert!(is_stale(validate(&chain, &header(2, &fork, vec![pre_digest(12), seal()]))));
+        // the finalized blocks themselves are fine.
+        assert!(is_accepted(validate(&chain, &first)));
+    }
+}
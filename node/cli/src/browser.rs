@@ -16,15 +16,24 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::chain::{asset_account_key, AccountInfo, AssetBalance};
 use crate::chain_spec::ChainSpec;
+use codec::{Decode, Encode};
 use log::info;
-use wasm_bindgen::prelude::*;
+use node_primitives::{AccountId, Balance, Header, Index};
+use node_runtime::UncheckedExtrinsic;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sp_core::{bytes, crypto::Ss58Codec, hashing::{blake2_128, twox_128}};
+use sp_runtime::traits::Header as _;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
 use browser_utils::{
 	Client,
 	browser_configuration, init_logging, set_console_error_panic_hook,
 };
 
-/// Starts the client.
+/// Starts the client, on the coinnet testnet without a chain spec.
 #[wasm_bindgen]
 pub async fn start_client(chain_spec: Option<String>, log_level: String) -> Result<Client, JsValue> {
 	start_inner(chain_spec, log_level)
@@ -41,7 +50,7 @@ async fn start_inner(
 	let chain_spec = match chain_spec {
 		Some(chain_spec) => ChainSpec::from_json_bytes(chain_spec.as_bytes().to_vec())
 			.map_err(|e| format!("{:?}", e))?,
-		None => crate::chain_spec::development_config(),
+		None => crate::chain_spec::coinnet_test_config()?,
 	};
 
 	let config = browser_configuration(chain_spec).await?;
@@ -61,3 +70,206 @@ async fn start_inner(
 
 	Ok(browser_utils::start_client(task_manager, rpc_handlers))
 }
+
+/// The native balances of an account. Balances are decimal strings, as they do not fit in a
+/// JavaScript number.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountBalance {
+	nonce: Index,
+	free: String,
+	reserved: String,
+	misc_frozen: String,
+	fee_frozen: String,
+}
+
+/// The balance of an account in an asset of `pallet_assets`, with the metadata of the asset.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetAccountBalance {
+	id: u32,
+	balance: String,
+	is_frozen: bool,
+	name: Option<String>,
+	symbol: Option<String>,
+	decimals: Option<u8>,
+}
+
+/// A finalized block.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FinalizedHead {
+	number: u32,
+	hash: String,
+	parent_hash: String,
+}
+
+/// The metadata of an asset, as `pallet_assets` stores it.
+#[derive(Decode)]
+struct AssetMetadata {
+	_deposit: Balance,
+	name: Vec<u8>,
+	symbol: Vec<u8>,
+	decimals: u8,
+	_is_frozen: bool,
+}
+
+/// The balances of the account at `address` on the native token, at the best block.
+#[wasm_bindgen(js_name = getBalance)]
+pub fn get_balance(client: &mut Client, address: String) -> js_sys::Promise {
+	let account = match parse_address(&address) {
+		Ok(account) => account,
+		Err(e) => return js_sys::Promise::reject(&e),
+	};
+	let request = storage_request(client, map_key(b"System", b"Account", &[&account.encode()]));
+	future_to_promise(async move {
+		let info = storage::<AccountInfo>(request).await?.unwrap_or_default();
+		to_js(&AccountBalance {
+			nonce: info.nonce,
+			free: info.data.free.to_string(),
+			reserved: info.data.reserved.to_string(),
+			misc_frozen: info.data.misc_frozen.to_string(),
+			fee_frozen: info.data.fee_frozen.to_string(),
+		})
+	})
+}
+
+/// The balances of the account at `address` in the assets `asset_ids`, at the best block. The
+/// metadata of assets without any is left out.
+#[wasm_bindgen(js_name = getAssetBalances)]
+pub fn get_asset_balances(
+	client: &mut Client,
+	address: String,
+	asset_ids: Vec<u32>,
+) -> js_sys::Promise {
+	let account = match parse_address(&address) {
+		Ok(account) => account,
+		Err(e) => return js_sys::Promise::reject(&e),
+	};
+	// the requests are all sent before waiting for any, the client cannot be borrowed across.
+	let requests = asset_ids.into_iter().map(|id| {
+		let balance = storage_request(client, asset_account_key(id, &account));
+		let metadata = storage_request(client, map_key(b"Assets", b"Metadata", &[&id.encode()]));
+		(id, balance, metadata)
+	}).collect::<Vec<_>>();
+	future_to_promise(async move {
+		let mut balances = Vec::with_capacity(requests.len());
+		for (id, balance, metadata) in requests {
+			let balance = storage::<AssetBalance>(balance).await?.unwrap_or_default();
+			let metadata = storage::<AssetMetadata>(metadata).await?;
+			let text = |text: &[u8]| String::from_utf8_lossy(text).into_owned();
+			balances.push(AssetAccountBalance {
+				id,
+				balance: balance.balance.to_string(),
+				is_frozen: balance.is_frozen,
+				name: metadata.as_ref().map(|metadata| text(&metadata.name)),
+				symbol: metadata.as_ref().map(|metadata| text(&metadata.symbol)),
+				decimals: metadata.as_ref().map(|metadata| metadata.decimals),
+			});
+		}
+		to_js(&balances)
+	})
+}
+
+/// Call `callback` with the number, hash and parent hash of every newly finalized block.
+#[wasm_bindgen(js_name = subscribeFinalizedHeads)]
+pub fn subscribe_finalized_heads(client: &mut Client, callback: js_sys::Function) {
+	let on_message = Closure::wrap(Box::new(move |message: JsValue| {
+		// the first message answers the subscription, the others are notifications.
+		let header = message.as_string()
+			.and_then(|message| serde_json::from_str::<Value>(&message).ok())
+			.and_then(|mut message| {
+				serde_json::from_value::<Header>(message["params"]["result"].take()).ok()
+			});
+		if let Some(header) = header {
+			let head = FinalizedHead {
+				number: *header.number(),
+				hash: format!("{:?}", header.hash()),
+				parent_hash: format!("{:?}", header.parent_hash()),
+			};
+			if let Ok(head) = to_js(&head) {
+				let _ = callback.call1(&JsValue::NULL, &head);
+			}
+		}
+	}) as Box<dyn FnMut(JsValue)>);
+	client.rpc_subscribe(
+		&json!({"jsonrpc": "2.0", "id": 1, "method": "chain_subscribeFinalizedHeads", "params": []})
+			.to_string(),
+		on_message.into_js_value().unchecked_into(),
+	);
+}
+
+/// Submit a signed extrinsic, SCALE-encoded in hex, and resolve with its hash.
+#[wasm_bindgen(js_name = submitExtrinsic)]
+pub fn submit_extrinsic(client: &mut Client, extrinsic: String) -> js_sys::Promise {
+	let checked = bytes::from_hex(&extrinsic)
+		.map_err(|e| format!("invalid hex: {}", e))
+		.and_then(|encoded| UncheckedExtrinsic::decode(&mut &encoded[..])
+			.map_err(|e| format!("invalid extrinsic: {}", e)))
+		.and_then(|decoded| match decoded.signature {
+			Some(_) => Ok(()),
+			None => Err("the extrinsic is not signed".into()),
+		});
+	if let Err(e) = checked {
+		return js_sys::Promise::reject(&JsValue::from_str(&e));
+	}
+	let request = rpc_request(client, "author_submitExtrinsic", json!([extrinsic]));
+	future_to_promise(async move {
+		let hash = rpc_result(request).await?;
+		Ok(JsValue::from_str(hash.as_str().unwrap_or_default()))
+	})
+}
+
+fn parse_address(address: &str) -> Result<AccountId, JsValue> {
+	AccountId::from_ss58check(address)
+		.map_err(|e| JsValue::from_str(&format!("invalid address {}: {:?}", address, e)))
+}
+
+/// The key of an entry of a storage map whose keys are all hashed with `Blake2_128Concat`.
+fn map_key(pallet: &[u8], item: &[u8], keys: &[&[u8]]) -> Vec<u8> {
+	let mut key = [twox_128(pallet), twox_128(item)].concat();
+	for map_key in keys {
+		key.extend(blake2_128(map_key));
+		key.extend(*map_key);
+	}
+	key
+}
+
+fn rpc_request(client: &mut Client, method: &str, params: Value) -> js_sys::Promise {
+	let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+	client.rpc_send(&request.to_string())
+}
+
+fn storage_request(client: &mut Client, key: Vec<u8>) -> js_sys::Promise {
+	rpc_request(client, "state_getStorage", json!([bytes::to_hex(&key, false)]))
+}
+
+/// The result of an RPC request, or its error message.
+async fn rpc_result(request: js_sys::Promise) -> Result<Value, JsValue> {
+	let response = JsFuture::from(request).await?
+		.as_string()
+		.ok_or_else(|| JsValue::from_str("the RPC response is not a string"))?;
+	let mut response: Value = serde_json::from_str(&response)
+		.map_err(|e| JsValue::from_str(&format!("invalid RPC response: {}", e)))?;
+	match response.get("error") {
+		Some(error) => Err(JsValue::from_str(error["message"].as_str().unwrap_or("RPC error"))),
+		None => Ok(response["result"].take()),
+	}
+}
+
+/// The decoded storage entry a `state_getStorage` request reads.
+async fn storage<T: Decode>(request: js_sys::Promise) -> Result<Option<T>, JsValue> {
+	let value = match rpc_result(request).await? {
+		Value::String(value) => value,
+		_ => return Ok(None),
+	};
+	let encoded = bytes::from_hex(&value)
+		.map_err(|e| JsValue::from_str(&format!("invalid storage value: {}", e)))?;
+	T::decode(&mut &encoded[..])
+		.map(Some)
+		.map_err(|e| JsValue::from_str(&format!("cannot decode the storage: {}", e)))
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+	JsValue::from_serde(value).map_err(|e| JsValue::from_str(&e.to_string()))
+}
@@ -0,0 +1,192 @@
+//! Blocks and state of the native runtime, read from the client, and the properties of the
+//! network: what the subcommands, the indexer, the chain metrics and the RPCs have in common.
+//! The browser light client shares the storage layouts.
+
+#![cfg_attr(not(feature = "cli"), allow(dead_code))]
+
+use crate::service::FullClient;
+use codec::{Compact, Decode, Encode};
+use frame_support::weights::{DispatchInfo, Pays};
+use frame_system::{EventRecord, Phase};
+use node_primitives::{AccountId, Balance, Block, Hash, Index, Moment};
+use node_runtime::{Event, Multiplier, SignedExtra, UncheckedExtrinsic};
+use pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi;
+use sc_client_api::StorageProvider;
+use sc_service::ChainSpec;
+use serde_json::Value;
+use sp_api::ProvideRuntimeApi;
+use sp_core::{
+    crypto::Ss58AddressFormat,
+    hashing::{blake2_128, twox_128},
+    storage::StorageKey,
+};
+use sp_runtime::{
+    generic::{BlockId, Era},
+    traits::Block as BlockT,
+    FixedPointNumber,
+};
+
+/// The native token of the network, for chain specs without a `tokenSymbol` property.
+const TOKEN: (&str, u8) = ("1CN", 12);
+
+/// The SS58 format of the network, for chain specs without a `ss58Format` property.
+const SS58_FORMAT: u16 = 85;
+
+/// The nonce and balances of an account, as `System::Account` holds them.
+pub(crate) type AccountInfo =
+    frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// The balance of an account in an asset, as `pallet_assets` stores it under `Assets::Account`.
+#[derive(Decode, Default)]
+pub(crate) struct AssetBalance {
+    pub(crate) balance: u64,
+    pub(crate) is_frozen: bool,
+    #[allow(dead_code)]
+    pub(crate) sufficient: bool,
+    /// The `Extra` of the runtime's `pallet_assets::Config`, which is `()`.
+    #[allow(dead_code)]
+    pub(crate) extra: (),
+}
+
+/// The symbol and decimals of the native token.
+pub(crate) fn token(chain_spec: &dyn ChainSpec) -> (String, u8) {
+    let properties = chain_spec.properties();
+    let symbol = properties.get("tokenSymbol").and_then(Value::as_str);
+    let decimals = properties.get("tokenDecimals").and_then(Value::as_u64);
+    match (symbol, decimals) {
+        (Some(symbol), Some(decimals)) => (symbol.into(), decimals as u8),
+        _ => (TOKEN.0.into(), TOKEN.1),
+    }
+}
+
+/// The SS58 format of the accounts of the network.
+pub(crate) fn ss58_format(chain_spec: &dyn ChainSpec) -> Ss58AddressFormat {
+    let format = chain_spec.properties().get("ss58Format").and_then(Value::as_u64);
+    Ss58AddressFormat::Custom(format.map_or(SS58_FORMAT, |format| format as u16))
+}
+
+/// The key of a storage value, or the prefix of the keys of a storage map.
+pub(crate) fn storage_key(pallet: &[u8], item: &[u8]) -> Vec<u8> {
+    [twox_128(pallet), twox_128(item)].concat()
+}
+
+/// The key of the balance of `account` in the asset `id`, under `Assets::Account`.
+pub(crate) fn asset_account_key(id: u32, account: &AccountId) -> Vec<u8> {
+    let mut key = storage_key(b"Assets", b"Account");
+    key.extend(blake2_128(&id.encode()));
+    key.extend(id.encode());
+    key.extend(blake2_128(&account.encode()));
+    key.extend(account.encode());
+    key
+}
+
+/// Read and decode the storage entry at `key`.
+pub(crate) fn storage<T: Decode>(
+    client: &FullClient,
+    at: &BlockId<Block>,
+    key: Vec<u8>,
+) -> Result<Option<T>, String> {
+    client
+        .storage(at, &StorageKey(key))
+        .map_err(|e| format!("cannot read the storage: {}", e))?
+        .map(|value| T::decode(&mut &value.0[..]))
+        .transpose()
+        .map_err(|e| format!("cannot decode the storage: {}", e))
+}
+
+/// The nonce and balances of `account`, the default for accounts that do not exist.
+pub(crate) fn account(
+    client: &FullClient,
+    at: &BlockId<Block>,
+    account: &AccountId,
+) -> Result<AccountInfo, String> {
+    let mut key = storage_key(b"System", b"Account");
+    key.extend(blake2_128(&account.encode()));
+    key.extend(account.encode());
+    storage(client, at, key).map(Option::unwrap_or_default)
+}
+
+/// The time `Timestamp` set in a block, zero for the genesis block.
+pub(crate) fn timestamp(client: &FullClient, at: &BlockId<Block>) -> Result<Moment, String> {
+    storage(client, at, storage_key(b"Timestamp", b"Now")).map(Option::unwrap_or_default)
+}
+
+/// The extrinsics of a block, which the client only knows as opaque bytes.
+pub(crate) fn extrinsics(block: &Block) -> Result<Vec<UncheckedExtrinsic>, String> {
+    block
+        .extrinsics
+        .iter()
+        .map(|extrinsic| UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("cannot decode the extrinsics: {}", e))
+}
+
+/// The events a block emitted, as `System::Events` holds them in its state.
+pub(crate) fn block_events(
+    client: &FullClient,
+    at: &BlockId<Block>,
+) -> Result<Vec<EventRecord<Event, Hash>>, String> {
+    storage(client, at, storage_key(b"System", b"Events")).map(Option::unwrap_or_default)
+}
+
+/// The era, nonce and tip of a signed extrinsic.
+pub(crate) fn era_nonce_tip(extra: &SignedExtra) -> (Era, Index, Balance) {
+    let (era, nonce, tip) =
+        <(Era, Compact<Index>, Compact<Balance>)>::decode(&mut &extra.encode()[..])
+            .expect("the signed extra of the runtime encodes these three; qed");
+    (era, nonce.0, tip.0)
+}
+
+/// Whether the extrinsic at `index` succeeded and the weight it used, from the event `System`
+/// emits for every extrinsic.
+pub(crate) fn outcome(
+    events: &[EventRecord<Event, Hash>],
+    index: usize,
+) -> Option<(bool, DispatchInfo)> {
+    let phase = Phase::ApplyExtrinsic(index as u32);
+    events.iter().filter(|record| record.phase == phase).find_map(|record| match &record.event {
+        Event::frame_system(frame_system::Event::ExtrinsicSuccess(info)) => Some((true, *info)),
+        Event::frame_system(frame_system::Event::ExtrinsicFailed(_, info)) => Some((false, *info)),
+        _ => None,
+    })
+}
+
+/// The fee `TransactionPayment` charged for a signed extrinsic, tip included.
+///
+/// The base and length fees are what the runtime computes for the extrinsic on the state of the
+/// parent block; the weight fee is for the weight the extrinsic actually used, which the runtime
+/// converts to fee one to one (`IdentityFee`) and scales by the fee multiplier of the parent.
+pub(crate) fn actual_fee(
+    client: &FullClient,
+    parent: &BlockId<Block>,
+    extrinsic: &<Block as BlockT>::Extrinsic,
+    tip: Balance,
+    info: &DispatchInfo,
+) -> Result<Balance, String> {
+    if info.pays_fee == Pays::No {
+        return Ok(tip);
+    }
+    let details = client
+        .runtime_api()
+        .query_fee_details(parent, extrinsic.clone(), extrinsic.encode().len() as u32)
+        .map_err(|e| format!("cannot compute the fee: {}", e))?;
+    let inclusion_fee = match details.inclusion_fee {
+        Some(inclusion_fee) => inclusion_fee,
+        None => return Ok(tip),
+    };
+    let multiplier = fee_multiplier(client, parent)?;
+    Ok(inclusion_fee
+        .base_fee
+        .saturating_add(inclusion_fee.len_fee)
+        .saturating_add(multiplier.saturating_mul_int(info.weight as Balance))
+        .saturating_add(tip))
+}
+
+/// The multiplier `TransactionPayment` scales weight fees with in the block after `at`.
+pub(crate) fn fee_multiplier(
+    client: &FullClient,
+    at: &BlockId<Block>,
+) -> Result<Multiplier, String> {
+    storage(client, at, storage_key(b"TransactionPayment", b"NextFeeMultiplier"))
+        .map(|multiplier| multiplier.unwrap_or_else(|| Multiplier::saturating_from_integer(1)))
+}
@@ -23,10 +23,10 @@ use hex_literal::hex;
 use node_runtime::constants::currency::*;
 use node_runtime::Block;
 use node_runtime::{
-    wasm_binary_unwrap, AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, ContractsConfig,
-    CouncilConfig, DemocracyConfig, ElectionsConfig, GrandpaConfig, ImOnlineConfig, IndicesConfig,
-    SessionConfig, SessionKeys, StakerStatus, StakingConfig, SudoConfig, SystemConfig,
-    TechnicalCommitteeConfig, MAX_NOMINATIONS,
+    wasm_binary_unwrap, AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, ClaimsConfig,
+    ContractsConfig, CouncilConfig, DemocracyConfig, ElectionsConfig, EthereumAddress,
+    GrandpaConfig, ImOnlineConfig, IndicesConfig, SessionConfig, SessionKeys, StakerStatus,
+    StakingConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, MAX_NOMINATIONS,
 };
 use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use sc_chain_spec::ChainSpecExtension;
@@ -34,8 +34,8 @@ use sc_service::ChainType;
 use sc_telemetry::TelemetryEndpoints;
 use serde::{Deserialize, Serialize};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
-use sp_consensus_babe::AuthorityId as BabeId;
-use sp_core::{crypto::UncheckedInto, sr25519, Pair, Public};
+use sp_consensus_babe::{AuthorityId as BabeId, BabeBlockWeight};
+use sp_core::{crypto::UncheckedInto, sr25519, Bytes, Pair, Public};
 use sp_runtime::{
     traits::{IdentifyAccount, Verify},
     Perbill,
@@ -59,15 +59,37 @@ pub struct Extensions {
     pub fork_blocks: sc_client_api::ForkBlocks<Block>,
     /// Known bad block hashes.
     pub bad_blocks: sc_client_api::BadBlocks<Block>,
+    /// A finalized block for light clients to start syncing from. The `lightSyncState` key
+    /// belongs to the chain spec itself, which only the `sync_state` RPC fills.
+    #[serde(rename = "lightSyncCheckpoint")]
+    pub light_sync_state: LightSyncStateExtension,
 }
 
+/// What a light client needs to start syncing from a finalized block instead of genesis, as the
+/// `export-checkpoint` subcommand writes it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LightSyncState {
+    /// The SCALE-encoded header of the finalized block.
+    pub finalized_block_header: Bytes,
+    /// The SCALE-encoded BABE epoch changes at the block.
+    pub babe_epoch_changes: Bytes,
+    /// The BABE weight of the block.
+    pub babe_finalized_block_weight: BabeBlockWeight,
+    /// The SCALE-encoded GRANDPA authority set at the block.
+    pub grandpa_authority_set: Bytes,
+}
+
+/// The light sync state of a chain spec, if it has one.
+pub type LightSyncStateExtension = Option<LightSyncState>;
+
 /// Specialized `ChainSpec`.
 pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig, Extensions>;
 
-// /// 1COIN testnet generator
-// pub fn coinnet_test_config() -> Result<ChainSpec, String> {
-//     ChainSpec::from_json_bytes(&include_bytes!("../res/coinnet.json")[..])
-// }
+/// 1COIN testnet generator
+pub fn coinnet_test_config() -> Result<ChainSpec, String> {
+    ChainSpec::from_json_bytes(&include_bytes!("../res/coinnet.json")[..])
+}
 
 /// SWIVEL generator
 pub fn swivel_config() -> Result<ChainSpec, String> {
@@ -375,17 +397,29 @@ pub fn testnet_genesis(
         pallet_membership_Instance1: Default::default(),
         pallet_treasury: Default::default(),
         pallet_vesting: Default::default(),
+        pallet_claims: ClaimsConfig {
+            claims: vec![],
+            vesting: vec![],
+        },
+        pallet_genesis_assets: Default::default(),
     }
 }
 
 fn development_config_genesis() -> GenesisConfig {
-    testnet_genesis(
+    let mut genesis = testnet_genesis(
         vec![authority_keys_from_seed("Alice")],
         vec![],
         get_account_id_from_seed::<sr25519::Public>("Alice"),
         None,
         true,
-    )
+    );
+    // The first well-known development account of Ethereum tooling (Hardhat, Ganache, ...),
+    // whose key is public, so that claims can be tried out on a dev chain.
+    genesis.pallet_claims.claims = vec![(
+        EthereumAddress(hex!["f39fd6e51aad88f6f4ce6ab8827279cfffb92266"]),
+        1_000 * DOLLARS,
+    )];
+    genesis
 }
 
 /// Development config (single validator Alice)
@@ -490,7 +524,7 @@ pub(crate) mod tests {
                     network,
                     transaction_pool,
                     ..
-                } = new_full_base(config, |_, _| ())?;
+                } = new_full_base(config, Default::default(), |_, _| ())?;
                 Ok(sc_service_test::TestNetComponents::new(
                     task_manager,
                     client,
@@ -0,0 +1,74 @@
+//! The `export-checkpoint` subcommand: the chain spec with a light sync state at the last
+//! finalized block of the local database.
+//!
+//! Light clients started with the spec import the finalized header and the consensus state
+//! (BABE epoch changes, GRANDPA authority set) from it and sync from there instead of genesis,
+//! without the headers before the finalized block.
+//! The database has to be that of a node that follows the chain, which must not be running.
+
+use crate::chain_spec::LightSyncState;
+use crate::cli::ExportCheckpointCmd;
+use crate::service::new_partial;
+use codec::Encode;
+use sc_cli::{CliConfiguration, Error, ImportParams, SharedParams};
+use sc_service::{Configuration, PartialComponents};
+use serde_json::Value;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+use std::fs;
+
+impl ExportCheckpointCmd {
+    /// Run the command.
+    pub fn run(&self, config: Configuration) -> Result<(), Error> {
+        let PartialComponents { client, other: (_, (_, grandpa_link, babe_link), _, _), .. } =
+            new_partial(&config)?;
+
+        let info = client.info();
+        let header = client.header(BlockId::Hash(info.finalized_hash))?.ok_or_else(|| {
+            Error::Input(format!("no header of the finalized block {}", info.finalized_hash))
+        })?;
+        // the genesis block has no weight.
+        let weight =
+            sc_consensus_babe::aux_schema::load_block_weight(&*client, info.finalized_hash)?
+                .unwrap_or_default();
+        let state = LightSyncState {
+            finalized_block_header: header.encode().into(),
+            babe_epoch_changes: babe_link.epoch_changes().lock().encode().into(),
+            babe_finalized_block_weight: weight,
+            grandpa_authority_set: grandpa_link
+                .shared_authority_set()
+                .clone_inner()
+                .encode()
+                .into(),
+        };
+
+        let spec = config.chain_spec.as_json(self.raw).map_err(Error::Input)?;
+        let mut spec: Value = serde_json::from_str(&spec)
+            .map_err(|e| Error::Input(format!("the chain spec is not valid JSON: {}", e)))?;
+        spec["lightSyncCheckpoint"] = serde_json::to_value(&state)
+            .map_err(|e| Error::Input(format!("cannot serialize the light sync state: {}", e)))?;
+        let spec = serde_json::to_string_pretty(&spec)
+            .map_err(|e| Error::Input(format!("cannot serialize the chain spec: {}", e)))?;
+
+        match &self.output {
+            Some(path) => fs::write(path, spec)?,
+            None => println!("{}", spec),
+        }
+        log::info!(
+            "Light sync state at finalized block #{} ({})",
+            info.finalized_number,
+            info.finalized_hash
+        );
+        Ok(())
+    }
+}
+
+impl CliConfiguration for ExportCheckpointCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn import_params(&self) -> Option<&ImportParams> {
+        Some(&self.import_params)
+    }
+}
@@ -16,7 +16,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use sc_cli::{RunCmd, KeySubcommand, SignCmd, VanityCmd, VerifyCmd};
+use sc_cli::{
+	CryptoSchemeFlag, ImportParams, KeySubcommand, KeystoreParams, OutputTypeFlag, RunCmd,
+	SharedParams, SignCmd, VanityCmd, VerifyCmd,
+};
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 /// An overarching CLI command definition.
@@ -28,6 +32,43 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Index blocks, extrinsics, events and transfers into an SQLite database at this path.
+	///
+	/// A new index is backfilled from genesis. Full nodes only.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub index_db: Option<PathBuf>,
+
+	/// Monitor the validator of the session keys in the keystore.
+	///
+	/// Tracks BABE slots claimed and authored, GRANDPA votes, ImOnline heartbeats and the next
+	/// session keys on chain, logs what risks slashing, and exports it as metrics and through the
+	/// unsafe `validator_health` RPC. Full nodes only.
+	#[structopt(long)]
+	pub validator_health: bool,
+
+	/// The number of blocks between GRANDPA justifications the voter keeps.
+	#[structopt(long, value_name = "BLOCKS", default_value = "512")]
+	pub grandpa_justification_period: u32,
+
+	/// A rule restricting the block GRANDPA votes for, repeated to compose several.
+	///
+	/// `before-best:<N>` votes at least N blocks below the best block, `three-quarters` at most
+	/// three quarters of the way from the last finalized block to the best block, and
+	/// `runtime-upgrade-delay:<N>` not for a block that changed the runtime until N blocks were
+	/// built on it. Defaults to `before-best:2` and `three-quarters`.
+	#[structopt(long, value_name = "RULE")]
+	pub grandpa_voting_rule: Vec<String>,
+
+	/// Pay out the staking rewards of the stashes whose `stsh` key is in the keystore.
+	///
+	/// `true` enables the staking payout offchain worker, which submits `payout_stakers` for the
+	/// eras whose rewards were not claimed, signed with the stash key, and `false` disables it.
+	/// The node writes the setting to its offchain storage, where it stays for the next starts;
+	/// without the flag, the stored setting, or one set with `offchain_localStorageSet`, is left
+	/// as it is. Nodes running offchain workers only.
+	#[structopt(long, value_name = "BOOL", possible_values = &["true", "false"])]
+	pub staking_payouts: Option<bool>,
 }
 
 /// Possible subcommands of the main binary.
@@ -43,6 +84,9 @@ pub enum Subcommand {
 	)]
 	Inspect(node_inspect::cli::InspectCmd),
 
+	/// Decode a block of the local database with its events, for humans or as JSON.
+	InspectBlock(InspectBlockCmd),
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
@@ -64,6 +108,21 @@ pub enum Subcommand {
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Generate a chain specification from a network config file.
+	GenerateSpec(GenerateSpecCmd),
+
+	/// Session key management cli utilities.
+	SessionKeys(SessionKeysSubcommand),
+
+	/// Build and sign transactions offline, and submit them to a node.
+	Tx(TxSubcommand),
+
+	/// Check a new runtime against the current one and print the calls that upgrade to it.
+	RuntimeUpgrade(RuntimeUpgradeCmd),
+
+	/// Export and import the slashing protection database of a validator.
+	SlashingProtection(SlashingProtectionSubcommand),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -73,6 +132,10 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Write the chain spec with a light sync state at the last finalized block, for light
+	/// clients to start syncing from.
+	ExportCheckpoint(ExportCheckpointCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -82,3 +145,260 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 }
+
+/// The `generate-spec` command used to generate chain specifications from a network config file.
+#[derive(Debug, StructOpt)]
+pub struct GenerateSpecCmd {
+	/// The TOML or JSON file describing the network.
+	#[structopt(long, parse(from_os_str))]
+	pub config: PathBuf,
+
+	/// The directory to write `<id>.json` and its raw version `<id>-raw.json` to.
+	#[structopt(long, parse(from_os_str), default_value = ".")]
+	pub output: PathBuf,
+}
+
+/// Session key management cli utilities.
+#[derive(Debug, StructOpt)]
+pub enum SessionKeysSubcommand {
+	/// Generate new session keys into a keystore and print them, like `author_rotateKeys` does.
+	Generate(GenerateSessionKeysCmd),
+
+	/// Decode session keys into the public key of each key type.
+	Inspect(InspectSessionKeysCmd),
+
+	/// Print the unsigned `Session::set_keys` call of the given session keys, for offline signing.
+	SetKeysPayload(SetKeysPayloadCmd),
+}
+
+/// The `session-keys generate` command.
+#[derive(Debug, StructOpt)]
+pub struct GenerateSessionKeysCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+/// The `session-keys inspect` command.
+#[derive(Debug, StructOpt)]
+pub struct InspectSessionKeysCmd {
+	/// The hex encoded session keys, as returned by `author_rotateKeys`.
+	pub keys: String,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub output_type: OutputTypeFlag,
+}
+
+/// The `session-keys set-keys-payload` command.
+#[derive(Debug, StructOpt)]
+pub struct SetKeysPayloadCmd {
+	/// The hex encoded session keys, as returned by `author_rotateKeys`.
+	pub keys: String,
+
+	/// The hex encoded proof of ownership of the keys. None of the current key types need one.
+	#[structopt(long, default_value = "0x")]
+	pub proof: String,
+}
+
+/// Slashing protection database utilities.
+#[derive(Debug, StructOpt)]
+pub enum SlashingProtectionSubcommand {
+	/// Write the database as JSON, to move the validator to another machine.
+	Export(ExportSlashingProtectionCmd),
+
+	/// Merge JSON written by `export` into the database. Run it before the validator starts.
+	Import(ImportSlashingProtectionCmd),
+}
+
+/// The `slashing-protection export` command.
+#[derive(Debug, StructOpt)]
+pub struct ExportSlashingProtectionCmd {
+	/// The file to write the JSON to. Standard output by default.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// The `slashing-protection import` command.
+#[derive(Debug, StructOpt)]
+pub struct ImportSlashingProtectionCmd {
+	/// The JSON written by `slashing-protection export`.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// Offline transaction cli utilities.
+#[derive(Debug, StructOpt)]
+pub enum TxSubcommand {
+	/// Build an unsigned transaction from a JSON description of a call.
+	Build(BuildTxCmd),
+
+	/// Sign a transaction built by `tx build`. Needs no connection to a node.
+	Sign(SignTxCmd),
+
+	/// Submit a transaction signed by `tx sign` to a node.
+	Submit(SubmitTxCmd),
+}
+
+/// The `tx build` command.
+#[derive(Debug, StructOpt)]
+pub struct BuildTxCmd {
+	/// The call, as a JSON object or the path of a file holding one, e.g.
+	/// `{"pallet": "Balances", "call": "transfer", "args": {"dest": "5F...", "value": "1000"}}`.
+	#[structopt(long)]
+	pub call: String,
+
+	/// The nonce of the account that will sign the transaction.
+	#[structopt(long)]
+	pub nonce: u32,
+
+	/// The tip for the block author, in the smallest unit of the native token.
+	#[structopt(long, default_value = "0")]
+	pub tip: u128,
+
+	/// The hash of the genesis block of the chain.
+	#[structopt(long)]
+	pub genesis_hash: String,
+
+	/// Make the transaction mortal, valid for about this many blocks after `--block-number`.
+	/// Transactions are immortal by default.
+	#[structopt(long, requires_all = &["block-number", "block-hash"])]
+	pub era_period: Option<u64>,
+
+	/// The number of the block the era of a mortal transaction starts at.
+	#[structopt(long)]
+	pub block_number: Option<u64>,
+
+	/// The hash of the block the era of a mortal transaction starts at.
+	#[structopt(long)]
+	pub block_hash: Option<String>,
+
+	/// The spec version of the runtime. Defaults to that of the native runtime.
+	#[structopt(long)]
+	pub spec_version: Option<u32>,
+
+	/// The transaction version of the runtime. Defaults to that of the native runtime.
+	#[structopt(long)]
+	pub transaction_version: Option<u32>,
+}
+
+/// The `tx sign` command.
+#[derive(Debug, StructOpt)]
+pub struct SignTxCmd {
+	/// The hex encoded unsigned transaction printed by `tx build`, or the path of a file holding
+	/// it.
+	pub transaction: String,
+
+	/// The secret key URI.
+	/// If the value is a file, the file content is used as URI.
+	/// If not given, you will be prompted for the URI.
+	#[structopt(long)]
+	pub suri: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub crypto_scheme: CryptoSchemeFlag,
+}
+
+/// The `tx submit` command.
+#[derive(Debug, StructOpt)]
+pub struct SubmitTxCmd {
+	/// The hex encoded signed transaction printed by `tx sign`, or the path of a file holding it.
+	pub transaction: String,
+
+	/// The HTTP RPC endpoint of the node to submit the transaction to.
+	#[structopt(long, default_value = "http://localhost:9933")]
+	pub url: String,
+}
+
+/// The `runtime-upgrade` command.
+#[derive(Debug, StructOpt)]
+pub struct RuntimeUpgradeCmd {
+	/// The WASM file of the new runtime, compressed or not.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	/// How the upgrade is enacted: `democracy` prints a `Democracy.note_preimage` call of the
+	/// bare `System.set_code` call and a `Democracy.propose` call of the preimage, `root` prints
+	/// the bare call and `sudo` wraps it in a call of the sudo key, which only chains that never
+	/// upgraded their runtime still have.
+	#[structopt(
+		long,
+		default_value = "democracy",
+		possible_values = &["democracy", "root", "sudo"],
+	)]
+	pub origin: String,
+
+	/// The deposit of the democracy proposal, in the smallest unit of the native token. Defaults
+	/// to the minimum deposit.
+	#[structopt(long)]
+	pub deposit: Option<u128>,
+
+	/// The HTTP RPC endpoint of a node to check the new runtime against the runtime of its chain.
+	/// The new runtime is checked against the native runtime by default.
+	#[structopt(long)]
+	pub url: Option<String>,
+}
+
+/// The `inspect-block` command.
+#[derive(Debug, StructOpt)]
+pub struct InspectBlockCmd {
+	/// The number or hash of the block. The best block by default.
+	pub block: Option<String>,
+
+	/// Only show the extrinsic with this index, with its events.
+	#[structopt(long)]
+	pub extrinsic: Option<u32>,
+
+	/// The SS58 prefix to show accounts with, the one of the chain by default.
+	#[structopt(long)]
+	pub ss58_prefix: Option<u16>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub output_type: OutputTypeFlag,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// The `export-checkpoint` command.
+#[derive(Debug, StructOpt)]
+pub struct ExportCheckpointCmd {
+	/// The file to write the chain spec to. Standard output by default.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Write the genesis storage as raw key-value pairs, like `build-spec --raw`.
+	#[structopt(long)]
+	pub raw: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
@@ -80,10 +80,23 @@ pub fn run() -> Result<()> {
     match &cli.subcommand {
         None => {
             let runner = cli.create_runner(&cli.run)?;
+            let grandpa_voting_rules = if cli.grandpa_voting_rule.is_empty() {
+                None
+            } else {
+                let rules = cli.grandpa_voting_rule.iter().map(|rule| rule.parse());
+                Some(rules.collect::<std::result::Result<_, _>>().map_err(sc_cli::Error::Input)?)
+            };
+            let options = service::NodeOptions {
+                index_db: cli.index_db.clone(),
+                validator_health: cli.validator_health,
+                grandpa_justification_period: cli.grandpa_justification_period,
+                grandpa_voting_rules,
+                staking_payouts: cli.staking_payouts,
+            };
             runner.run_node_until_exit(|config| async move {
                 match config.role {
                     Role::Light => service::new_light(config),
-                    _ => service::new_full(config),
+                    _ => service::new_full(config, options),
                 }
                 .map_err(sc_cli::Error::Service)
             })
@@ -93,6 +106,10 @@ pub fn run() -> Result<()> {
 
             runner.sync_run(|config| cmd.run::<Block, RuntimeApi, Executor>(config))
         }
+        Some(Subcommand::InspectBlock(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.sync_run(|config| cmd.run(config))
+        }
         Some(Subcommand::Benchmark(cmd)) => {
             if cfg!(feature = "runtime-benchmarks") {
                 let runner = cli.create_runner(cmd)?;
@@ -112,6 +129,11 @@ pub fn run() -> Result<()> {
             let runner = cli.create_runner(cmd)?;
             runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
         }
+        Some(Subcommand::GenerateSpec(cmd)) => cmd.run(),
+        Some(Subcommand::SessionKeys(cmd)) => cmd.run(&cli),
+        Some(Subcommand::Tx(cmd)) => cmd.run(),
+        Some(Subcommand::RuntimeUpgrade(cmd)) => cmd.run(),
+        Some(Subcommand::SlashingProtection(cmd)) => cmd.run(&cli),
         Some(Subcommand::CheckBlock(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             runner.async_run(|config| {
@@ -135,6 +157,10 @@ pub fn run() -> Result<()> {
                 Ok((cmd.run(client, config.database), task_manager))
             })
         }
+        Some(Subcommand::ExportCheckpoint(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.sync_run(|config| cmd.run(config))
+        }
         Some(Subcommand::ExportState(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             runner.async_run(|config| {
@@ -0,0 +1,620 @@
+//! The `generate-spec` subcommand: chain specs built from a network config file instead of the
+//! Rust code of `chain_spec.rs`.
+//!
+//! The config is a TOML or JSON file, e.g.:
+//!
+//! ```toml
+//! name = "1COIN Testnet"
+//! id = "coinnet_testnet"
+//! chain_type = "Live"
+//! boot_nodes = ["/dns/node1.example.com/tcp/30333/p2p/12D3KooW..."]
+//! sudo = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! technical_committee = ["5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"]
+//!
+//! [properties]
+//! tokenSymbol = "1CN"
+//! tokenDecimals = 12
+//!
+//! [[validators]]
+//! stash = "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY"
+//! controller = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! bond = "10000000000000000"
+//! grandpa = "0x88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee"
+//! babe = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! im_online = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! authority_discovery = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//!
+//! [[endowments]]
+//! account = "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY"
+//! balance = "10000000000000000000"
+//!
+//! [[council]]
+//! account = "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY"
+//! stake = 1000000000000
+//!
+//! [[assets]]
+//! id = 1
+//! owner = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! is_sufficient = true
+//! min_balance = 1
+//! name = "Dollar"
+//! symbol = "USD"
+//! decimals = 6
+//! balances = [{ account = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", balance = 1000000 }]
+//!
+//! [[vesting]]
+//! account = "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY"
+//! begin = 0
+//! length = 1000
+//! liquid = "9000000000000000000"
+//! ```
+//!
+//! Keys are SS58 addresses or 0x-prefixed hex public keys. Balances of the native token are in
+//! its smallest unit, as integers or, for amounts TOML integers cannot hold, as strings.
+
+use crate::chain_spec::{self, ChainSpec};
+use crate::cli::GenerateSpecCmd;
+use frame_support::traits::Get;
+use grandpa_primitives::AuthorityId as GrandpaId;
+use node_primitives::{AccountId, Balance, BlockNumber};
+use node_runtime::{
+    ExistentialDeposit, GenesisAssetsConfig, GenesisConfig, StakerStatus, StringLimit,
+};
+use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
+use sc_chain_spec::Properties;
+use sc_network::config::MultiaddrWithPeerId;
+use sc_service::ChainType;
+use serde::{Deserialize, Deserializer};
+use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
+use sp_consensus_babe::AuthorityId as BabeId;
+use sp_core::crypto::{Ss58Codec, UncheckedFrom};
+use std::{collections::BTreeSet, fs, path::Path};
+
+/// An amount of the native token in its smallest unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Amount(pub Balance);
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Int(u64),
+            Str(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Int(amount) => Ok(Amount(amount.into())),
+            Raw::Str(amount) => amount
+                .replace('_', "")
+                .parse()
+                .map(Amount)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// A network, as described by the config file of `generate-spec`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// The name of the chain.
+    pub name: String,
+    /// The id of the chain, also the name of the output files.
+    pub id: String,
+    /// `Development`, `Local` or `Live` (the default).
+    #[serde(default = "default_chain_type")]
+    pub chain_type: ChainType,
+    /// The multiaddresses of the boot nodes.
+    #[serde(default)]
+    pub boot_nodes: Vec<String>,
+    /// The network protocol id.
+    #[serde(default)]
+    pub protocol_id: Option<String>,
+    /// Properties such as `tokenSymbol`, `tokenDecimals` and `ss58Format`.
+    #[serde(default)]
+    pub properties: Option<Properties>,
+    /// The sudo key, until the runtime removes it.
+    pub sudo: String,
+    /// The initial validators.
+    pub validators: Vec<ValidatorConfig>,
+    /// The initial balances. Stashes and council members must be endowed here too.
+    #[serde(default)]
+    pub endowments: Vec<EndowmentConfig>,
+    /// The initial council members and the stake each of them backs their seat with.
+    #[serde(default)]
+    pub council: Vec<CouncilMemberConfig>,
+    /// The initial technical committee.
+    #[serde(default)]
+    pub technical_committee: Vec<String>,
+    /// The assets created at genesis.
+    #[serde(default)]
+    pub assets: Vec<AssetConfig>,
+    /// The vesting schedules of endowed accounts.
+    #[serde(default)]
+    pub vesting: Vec<VestingConfig>,
+}
+
+fn default_chain_type() -> ChainType {
+    ChainType::Live
+}
+
+/// An initial validator.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorConfig {
+    /// The stash account.
+    pub stash: String,
+    /// The controller account.
+    pub controller: String,
+    /// How much of the stash is bonded.
+    pub bond: Amount,
+    /// The GRANDPA session key (ed25519).
+    pub grandpa: String,
+    /// The BABE session key (sr25519).
+    pub babe: String,
+    /// The I'm Online session key (sr25519).
+    pub im_online: String,
+    /// The authority discovery session key (sr25519).
+    pub authority_discovery: String,
+}
+
+/// An initial balance.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EndowmentConfig {
+    /// The endowed account.
+    pub account: String,
+    /// Its free balance.
+    pub balance: Amount,
+}
+
+/// An initial council member.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CouncilMemberConfig {
+    /// The member.
+    pub account: String,
+    /// The part of its balance locked as the candidacy stake.
+    pub stake: Amount,
+}
+
+/// An asset created at genesis.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssetConfig {
+    /// The asset id.
+    pub id: u32,
+    /// The owner, who is also the issuer, admin and freezer of the asset.
+    pub owner: String,
+    /// Whether holding the asset is enough for an account to exist.
+    #[serde(default)]
+    pub is_sufficient: bool,
+    /// The minimum balance of an account holding the asset.
+    pub min_balance: u64,
+    /// The name of the asset.
+    #[serde(default)]
+    pub name: String,
+    /// The ticker symbol of the asset.
+    #[serde(default)]
+    pub symbol: String,
+    /// The number of decimals of the asset.
+    #[serde(default)]
+    pub decimals: u8,
+    /// The initial balances of the asset.
+    #[serde(default)]
+    pub balances: Vec<AssetBalanceConfig>,
+}
+
+/// An initial balance of an asset.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssetBalanceConfig {
+    /// The account holding the asset.
+    pub account: String,
+    /// Its balance of the asset.
+    pub balance: u64,
+}
+
+/// A vesting schedule of an endowed account.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VestingConfig {
+    /// The vesting account.
+    pub account: String,
+    /// The block vesting begins at.
+    pub begin: BlockNumber,
+    /// The number of blocks until everything is vested.
+    pub length: BlockNumber,
+    /// The part of the endowment that is not locked at all.
+    pub liquid: Amount,
+}
+
+/// A validated network config, with parsed keys.
+#[derive(Clone)]
+struct Network {
+    boot_nodes: Vec<MultiaddrWithPeerId>,
+    sudo: AccountId,
+    authorities: Vec<(
+        AccountId,
+        AccountId,
+        GrandpaId,
+        BabeId,
+        ImOnlineId,
+        AuthorityDiscoveryId,
+    )>,
+    bonds: Vec<Balance>,
+    endowments: Vec<(AccountId, Balance)>,
+    council: Vec<(AccountId, Balance)>,
+    technical_committee: Vec<AccountId>,
+    assets: Vec<(u32, AccountId, bool, u64)>,
+    asset_metadata: Vec<(u32, Vec<u8>, Vec<u8>, u8)>,
+    asset_balances: Vec<(u32, AccountId, u64)>,
+    vesting: Vec<(AccountId, BlockNumber, BlockNumber, Balance)>,
+}
+
+/// Parse a public key given as an SS58 address or as 0x-prefixed hex.
+pub(crate) fn parse_key<K: Ss58Codec>(value: &str, from_raw: fn([u8; 32]) -> K) -> Result<K, String> {
+    if let Some(hex) = value.strip_prefix("0x") {
+        let bytes =
+            sp_core::bytes::from_hex(hex).map_err(|e| format!("invalid hex `{}`: {}", value, e))?;
+        if bytes.len() != 32 {
+            return Err(format!(
+                "`{}` is {} bytes long, expected 32",
+                value,
+                bytes.len()
+            ));
+        }
+        let mut raw = [0u8; 32];
+        raw.copy_from_slice(&bytes);
+        Ok(from_raw(raw))
+    } else {
+        K::from_ss58check(value).map_err(|e| format!("invalid SS58 address `{}`: {:?}", value, e))
+    }
+}
+
+/// Collects every problem of a config, so that all of them are reported at once.
+#[derive(Default)]
+struct Validator {
+    errors: Vec<String>,
+}
+
+impl Validator {
+    fn error(&mut self, error: String) {
+        self.errors.push(error);
+    }
+
+    fn account(&mut self, field: &str, value: &str) -> Option<AccountId> {
+        self.key(field, value, AccountId::from)
+    }
+
+    fn key<K: Ss58Codec>(
+        &mut self,
+        field: &str,
+        value: &str,
+        from_raw: fn([u8; 32]) -> K,
+    ) -> Option<K> {
+        parse_key(value, from_raw)
+            .map_err(|e| self.error(format!("{}: {}", field, e)))
+            .ok()
+    }
+
+    /// Report the keys that appear more than once in `keys`.
+    fn unique<'a, K: AsRef<[u8]> + Ss58Codec + 'a>(
+        &mut self,
+        what: &str,
+        keys: impl IntoIterator<Item = &'a K>,
+    ) {
+        let mut seen = BTreeSet::new();
+        for key in keys {
+            if !seen.insert(key.as_ref().to_vec()) {
+                self.error(format!("duplicate {}: {}", what, key.to_ss58check()));
+            }
+        }
+    }
+}
+
+fn validate(config: &NetworkConfig) -> Result<Network, Vec<String>> {
+    let mut v = Validator::default();
+    let existential_deposit = ExistentialDeposit::get();
+
+    let boot_nodes = config
+        .boot_nodes
+        .iter()
+        .filter_map(|node| {
+            node.parse::<MultiaddrWithPeerId>()
+                .map_err(|e| v.error(format!("boot_nodes: `{}`: {}", node, e)))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    let sudo = v.account("sudo", &config.sudo);
+
+    let mut endowments = Vec::new();
+    for (i, endowment) in config.endowments.iter().enumerate() {
+        let field = format!("endowments[{}]", i);
+        if let Some(account) = v.account(&format!("{}.account", field), &endowment.account) {
+            if endowment.balance.0 < existential_deposit {
+                v.error(format!(
+                    "{}.balance: {} is below the existential deposit of {}",
+                    field, endowment.balance.0, existential_deposit
+                ));
+            }
+            endowments.push((account, endowment.balance.0));
+        }
+    }
+    v.unique("endowed account", endowments.iter().map(|(account, _)| account));
+    let endowment_of = |account: &AccountId| {
+        endowments
+            .iter()
+            .find(|(endowed, _)| endowed == account)
+            .map(|(_, balance)| *balance)
+    };
+
+    if config.validators.is_empty() {
+        v.error("validators: at least one validator is needed".into());
+    }
+    let mut authorities = Vec::new();
+    let mut bonds = Vec::new();
+    for (i, validator) in config.validators.iter().enumerate() {
+        let field = format!("validators[{}]", i);
+        let keys = (
+            v.account(&format!("{}.stash", field), &validator.stash),
+            v.account(&format!("{}.controller", field), &validator.controller),
+            v.key(&format!("{}.grandpa", field), &validator.grandpa, GrandpaId::unchecked_from),
+            v.key(&format!("{}.babe", field), &validator.babe, BabeId::unchecked_from),
+            v.key(&format!("{}.im_online", field), &validator.im_online, ImOnlineId::unchecked_from),
+            v.key(
+                &format!("{}.authority_discovery", field),
+                &validator.authority_discovery,
+                AuthorityDiscoveryId::unchecked_from,
+            ),
+        );
+        let (stash, controller, grandpa, babe, im_online, authority_discovery) = match keys {
+            (Some(a), Some(b), Some(c), Some(d), Some(e), Some(f)) => (a, b, c, d, e, f),
+            _ => continue,
+        };
+
+        let bond = validator.bond.0;
+        if bond < existential_deposit {
+            v.error(format!(
+                "{}.bond: {} is below the existential deposit of {}",
+                field, bond, existential_deposit
+            ));
+        }
+        match endowment_of(&stash) {
+            None => v.error(format!(
+                "{}.stash: {} is not endowed, so it cannot bond anything",
+                field,
+                stash.to_ss58check()
+            )),
+            Some(balance) if balance < bond => v.error(format!(
+                "{}.bond: the stash is endowed with {}, less than its bond of {}",
+                field, balance, bond
+            )),
+            Some(_) => {}
+        }
+
+        authorities.push((stash, controller, grandpa, babe, im_online, authority_discovery));
+        bonds.push(bond);
+    }
+    // a validator may use its stash as controller, but no account can be used twice otherwise.
+    v.unique(
+        "stash or controller",
+        authorities.iter().flat_map(|a| {
+            if a.0 == a.1 {
+                vec![&a.0]
+            } else {
+                vec![&a.0, &a.1]
+            }
+        }),
+    );
+    v.unique("GRANDPA key", authorities.iter().map(|a| &a.2));
+    v.unique("BABE key", authorities.iter().map(|a| &a.3));
+    v.unique("I'm Online key", authorities.iter().map(|a| &a.4));
+    v.unique("authority discovery key", authorities.iter().map(|a| &a.5));
+
+    let mut council = Vec::new();
+    for (i, member) in config.council.iter().enumerate() {
+        let field = format!("council[{}]", i);
+        if let Some(account) = v.account(&format!("{}.account", field), &member.account) {
+            match endowment_of(&account) {
+                Some(balance) if balance >= member.stake.0 => {}
+                _ => v.error(format!(
+                    "{}.stake: {} is not endowed with its stake of {}",
+                    field,
+                    account.to_ss58check(),
+                    member.stake.0
+                )),
+            }
+            council.push((account, member.stake.0));
+        }
+    }
+    v.unique("council member", council.iter().map(|(account, _)| account));
+
+    let technical_committee = config
+        .technical_committee
+        .iter()
+        .enumerate()
+        .filter_map(|(i, member)| v.account(&format!("technical_committee[{}]", i), member))
+        .collect::<Vec<_>>();
+    v.unique("technical committee member", technical_committee.iter());
+
+    let mut assets = Vec::new();
+    let mut asset_metadata = Vec::new();
+    let mut asset_balances = Vec::new();
+    let mut asset_ids = BTreeSet::new();
+    for (i, asset) in config.assets.iter().enumerate() {
+        let field = format!("assets[{}]", i);
+        if !asset_ids.insert(asset.id) {
+            v.error(format!("{}.id: duplicate asset id {}", field, asset.id));
+        }
+        if asset.min_balance == 0 {
+            v.error(format!("{}.min_balance: must not be zero", field));
+        }
+        for (name, value) in &[("name", &asset.name), ("symbol", &asset.symbol)] {
+            if value.len() > StringLimit::get() as usize {
+                v.error(format!(
+                    "{}.{}: longer than {} bytes",
+                    field,
+                    name,
+                    StringLimit::get()
+                ));
+            }
+        }
+        if let Some(owner) = v.account(&format!("{}.owner", field), &asset.owner) {
+            assets.push((asset.id, owner, asset.is_sufficient, asset.min_balance));
+        }
+        if !asset.name.is_empty() || !asset.symbol.is_empty() {
+            asset_metadata.push((
+                asset.id,
+                asset.name.as_bytes().to_vec(),
+                asset.symbol.as_bytes().to_vec(),
+                asset.decimals,
+            ));
+        }
+
+        let mut holders = Vec::new();
+        for (j, holding) in asset.balances.iter().enumerate() {
+            let field = format!("{}.balances[{}]", field, j);
+            let account = match v.account(&format!("{}.account", field), &holding.account) {
+                Some(account) => account,
+                None => continue,
+            };
+            if holding.balance < asset.min_balance {
+                v.error(format!(
+                    "{}.balance: {} is below the min balance of {}",
+                    field, holding.balance, asset.min_balance
+                ));
+            }
+            if !asset.is_sufficient && endowment_of(&account).is_none() {
+                v.error(format!(
+                    "{}.account: {} holds an insufficient asset, so it must be endowed",
+                    field,
+                    account.to_ss58check()
+                ));
+            }
+            holders.push(account.clone());
+            asset_balances.push((asset.id, account, holding.balance));
+        }
+        v.unique(&format!("holder of asset {}", asset.id), holders.iter());
+    }
+
+    let mut vesting = Vec::new();
+    for (i, schedule) in config.vesting.iter().enumerate() {
+        let field = format!("vesting[{}]", i);
+        let account = match v.account(&format!("{}.account", field), &schedule.account) {
+            Some(account) => account,
+            None => continue,
+        };
+        if schedule.length == 0 {
+            v.error(format!("{}.length: must not be zero", field));
+        }
+        match endowment_of(&account) {
+            None => v.error(format!(
+                "{}.account: {} is not endowed, so nothing can vest",
+                field,
+                account.to_ss58check()
+            )),
+            Some(balance) if balance < schedule.liquid.0 => v.error(format!(
+                "{}.liquid: {} is more than the endowment of {}",
+                field, schedule.liquid.0, balance
+            )),
+            Some(_) => {}
+        }
+        vesting.push((account, schedule.begin, schedule.length, schedule.liquid.0));
+    }
+    v.unique("vesting account", vesting.iter().map(|schedule| &schedule.0));
+
+    match sudo {
+        Some(sudo) if v.errors.is_empty() => Ok(Network {
+            boot_nodes,
+            sudo,
+            authorities,
+            bonds,
+            endowments,
+            council,
+            technical_committee,
+            assets,
+            asset_metadata,
+            asset_balances,
+            vesting,
+        }),
+        _ => Err(v.errors),
+    }
+}
+
+/// The genesis of a validated network. Starts from `testnet_genesis`, so that pallets without
+/// anything to configure get the same genesis as the hand-written networks.
+fn genesis(network: &Network) -> GenesisConfig {
+    let mut genesis = chain_spec::testnet_genesis(
+        network.authorities.clone(),
+        vec![],
+        network.sudo.clone(),
+        Some(vec![]),
+        false,
+    );
+
+    genesis.pallet_balances.balances = network.endowments.clone();
+    genesis.pallet_staking.stakers = network
+        .authorities
+        .iter()
+        .zip(&network.bonds)
+        .map(|(a, bond)| (a.0.clone(), a.1.clone(), *bond, StakerStatus::Validator))
+        .collect();
+    genesis.pallet_elections_phragmen.members = network.council.clone();
+    genesis.pallet_collective_Instance2.members = network.technical_committee.clone();
+    genesis.pallet_vesting.vesting = network.vesting.clone();
+    genesis.pallet_genesis_assets = GenesisAssetsConfig {
+        assets: network.assets.clone(),
+        metadata: network.asset_metadata.clone(),
+        accounts: network.asset_balances.clone(),
+    };
+    genesis
+}
+
+fn read_config(path: &Path) -> Result<NetworkConfig, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| e.to_string()),
+        Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        _ => Err("the config file must end with .toml or .json".into()),
+    }
+    .map_err(|e| format!("cannot parse {}: {}", path.display(), e))
+}
+
+impl GenerateSpecCmd {
+    /// Run the command.
+    pub fn run(&self) -> sc_cli::Result<()> {
+        let config = read_config(&self.config).map_err(sc_cli::Error::Input)?;
+        let network = validate(&config).map_err(|errors| {
+            sc_cli::Error::Input(format!(
+                "invalid network config {}:\n  {}",
+                self.config.display(),
+                errors.join("\n  ")
+            ))
+        })?;
+
+        let boot_nodes = network.boot_nodes.clone();
+        let spec = ChainSpec::from_genesis(
+            &config.name,
+            &config.id,
+            config.chain_type.clone(),
+            move || genesis(&network),
+            boot_nodes,
+            None,
+            config.protocol_id.as_deref(),
+            config.properties.clone(),
+            Default::default(),
+        );
+
+        fs::create_dir_all(&self.output)?;
+        for &(raw, suffix) in &[(false, ""), (true, "-raw")] {
+            let path = self.output.join(format!("{}{}.json", config.id, suffix));
+            let json = spec.as_json(raw).map_err(sc_cli::Error::Input)?;
+            fs::write(&path, json)?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,490 @@
+//! The health of the validator this node runs, for the session keys of its keystore.
+//!
+//! At every new best block the monitor looks at the slots of the current BABE epoch the keys
+//! could claim and the blocks they authored, the GRANDPA rounds the keys voted in, the `ImOnline`
+//! heartbeat of the current session and whether the next session keys registered on chain are
+//! all in the keystore. Slots are counted along the best chain as it is imported, and GRANDPA
+//! rounds since the node started. What puts the validator at risk of being slashed or reported
+//! offline is logged as an alert, exported to Prometheus and served by `validator_health`.
+
+use crate::chain::{ss58_format, storage, storage_key};
+use crate::service::FullClient;
+use crate::session_keys::key_type_name;
+use codec::Encode;
+use futures::StreamExt;
+use grandpa::SharedVoterState;
+use grandpa_primitives::GrandpaApi;
+use node_primitives::{AccountId, Block, BlockNumber};
+use node_runtime::SessionKeys;
+use parking_lot::RwLock;
+use prometheus_endpoint::{register, Gauge, GaugeVec, Opts, PrometheusError, Registry, U64};
+use sc_client_api::BlockchainEvents;
+use sc_consensus_babe::{authorship::claim_slot, find_pre_digest, Epoch};
+use sc_service::ChainSpec;
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_consensus::BlockOrigin;
+use sp_consensus_babe::BabeApi;
+use sp_core::{
+    crypto::{key_types, KeyTypeId, Public, Ss58AddressFormat, Ss58Codec},
+    hashing::twox_64,
+};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, Header as _},
+};
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The key types of the session keys of the runtime.
+const SESSION_KEY_TYPES: [KeyTypeId; 4] =
+    [key_types::GRANDPA, key_types::BABE, key_types::IM_ONLINE, key_types::AUTHORITY_DISCOVERY];
+
+/// The least slots or rounds a rate is alerted on.
+const MIN_SAMPLES: u64 = 5;
+
+/// How far into the session a missing heartbeat is alerted on, `ImOnline` sending it halfway.
+const HEARTBEAT_DEADLINE: f64 = 0.75;
+
+/// The report the monitor keeps up to date, shared with the RPC.
+pub type SharedHealthReport = Arc<RwLock<HealthReport>>;
+
+/// The slots and blocks of the keys of the node in the current BABE epoch.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BabeHealth {
+    /// The index of the epoch.
+    pub epoch: u64,
+    /// The slots up to the best block the keys could author a block in.
+    pub claimed_slots: u64,
+    /// The blocks of the best chain authored with the keys.
+    pub authored_blocks: u64,
+    /// The blocks authored with the keys that another node broadcast, of slots after the node
+    /// started. Blocks of earlier slots may be those the node authored before a restart.
+    pub foreign_blocks: u64,
+}
+
+/// The GRANDPA rounds the keys of the node voted in since it started.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrandpaHealth {
+    /// Whether a key of the node is in the current authority set.
+    pub is_voter: bool,
+    /// The completed rounds the node saw as a voter.
+    pub rounds: u64,
+    /// The rounds the node prevoted in.
+    pub prevotes: u64,
+    /// The rounds the node precommitted in.
+    pub precommits: u64,
+}
+
+/// The health of the validator of the node at a best block.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// The number of the best block.
+    pub block_number: BlockNumber,
+    /// The current session.
+    pub session: u32,
+    /// The validators whose session keys are in the keystore.
+    pub validators: Vec<String>,
+    /// Whether the next session keys of the validators are all in the keystore.
+    pub session_keys_match: bool,
+    /// The key types of next session keys missing from the keystore.
+    pub missing_keys: Vec<String>,
+    /// BABE block production.
+    pub babe: BabeHealth,
+    /// GRANDPA votes.
+    pub grandpa: GrandpaHealth,
+    /// Whether `ImOnline` received the heartbeat of the node in the current session, unknown
+    /// when no key of the node is an `ImOnline` authority of the session.
+    pub heartbeat: Option<bool>,
+    /// What puts the validator at risk of being slashed or reported offline.
+    pub alerts: Vec<String>,
+}
+
+/// The Prometheus gauges of the report.
+struct Metrics {
+    babe_slots: GaugeVec<U64>,
+    grandpa_rounds: GaugeVec<U64>,
+    heartbeat: Gauge<U64>,
+    session_keys_match: Gauge<U64>,
+    alerts: Gauge<U64>,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Metrics {
+            babe_slots: register(
+                GaugeVec::new(
+                    Opts::new(
+                        "coinnet_validator_babe_slots",
+                        "Slots of the current epoch claimed and authored with the local keys",
+                    ),
+                    &["kind"],
+                )?,
+                registry,
+            )?,
+            grandpa_rounds: register(
+                GaugeVec::new(
+                    Opts::new(
+                        "coinnet_validator_grandpa_rounds",
+                        "GRANDPA rounds seen as a voter and voted in since the node started",
+                    ),
+                    &["kind"],
+                )?,
+                registry,
+            )?,
+            heartbeat: register(
+                Gauge::new(
+                    "coinnet_validator_heartbeat",
+                    "Whether ImOnline received the heartbeat of the node in the current session",
+                )?,
+                registry,
+            )?,
+            session_keys_match: register(
+                Gauge::new(
+                    "coinnet_validator_session_keys_match",
+                    "Whether the next session keys on chain are all in the keystore",
+                )?,
+                registry,
+            )?,
+            alerts: register(
+                Gauge::new(
+                    "coinnet_validator_alerts",
+                    "Slashing and offline risks of the validator",
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    fn update(&self, report: &HealthReport) {
+        let babe = &report.babe;
+        self.babe_slots.with_label_values(&["claimed"]).set(babe.claimed_slots);
+        self.babe_slots.with_label_values(&["authored"]).set(babe.authored_blocks);
+        self.babe_slots.with_label_values(&["foreign"]).set(babe.foreign_blocks);
+        let grandpa = &report.grandpa;
+        self.grandpa_rounds.with_label_values(&["seen"]).set(grandpa.rounds);
+        self.grandpa_rounds.with_label_values(&["prevoted"]).set(grandpa.prevotes);
+        self.grandpa_rounds.with_label_values(&["precommitted"]).set(grandpa.precommits);
+        self.heartbeat.set(report.heartbeat.unwrap_or_default().into());
+        self.session_keys_match.set(report.session_keys_match.into());
+        self.alerts.set(report.alerts.len() as u64);
+    }
+}
+
+/// Follows the best chain and keeps the report up to date.
+pub struct HealthMonitor {
+    client: Arc<FullClient>,
+    keystore: SyncCryptoStorePtr,
+    voter_state: SharedVoterState,
+    ss58_format: Ss58AddressFormat,
+    metrics: Option<Metrics>,
+    report: SharedHealthReport,
+    /// The last slot counted, none before the first block.
+    last_slot: Option<u64>,
+    /// The slot the monitor started in.
+    started_slot: u64,
+    /// The share of the current epoch, and session, that has passed.
+    epoch_progress: f64,
+    /// The last GRANDPA round counted.
+    last_round: u64,
+}
+
+impl HealthMonitor {
+    /// Create a monitor of the keys of `keystore`, registering its metrics with `registry`. Slots
+    /// last `slot_duration` milliseconds.
+    pub fn new(
+        client: Arc<FullClient>,
+        keystore: SyncCryptoStorePtr,
+        voter_state: SharedVoterState,
+        slot_duration: u64,
+        chain_spec: &dyn ChainSpec,
+        registry: Option<&Registry>,
+    ) -> Result<Self, String> {
+        let metrics = registry
+            .map(Metrics::register)
+            .transpose()
+            .map_err(|e| format!("cannot register the validator metrics: {}", e))?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Ok(HealthMonitor {
+            client,
+            keystore,
+            voter_state,
+            ss58_format: ss58_format(chain_spec),
+            metrics,
+            report: Default::default(),
+            last_slot: None,
+            started_slot: now.as_millis() as u64 / slot_duration,
+            epoch_progress: 0.0,
+            last_round: 0,
+        })
+    }
+
+    /// The report, for the RPC.
+    pub fn report(&self) -> SharedHealthReport {
+        self.report.clone()
+    }
+
+    /// Update the report at every new best block until the node shuts down.
+    pub async fn run(mut self) {
+        let mut imported = self.client.import_notification_stream();
+        while let Some(notification) = imported.next().await {
+            if !notification.is_new_best {
+                continue;
+            }
+            if let Err(e) = self.imported(&notification.header, notification.origin) {
+                log::warn!(target: "health", "Cannot update the validator health: {}", e);
+            }
+        }
+    }
+
+    fn imported(
+        &mut self,
+        header: &<Block as BlockT>::Header,
+        origin: BlockOrigin,
+    ) -> Result<(), String> {
+        let at = BlockId::Hash(header.hash());
+        let mut report = self.report.read().clone();
+        report.block_number = *header.number();
+        self.babe(&mut report, &at, header, origin)?;
+        self.grandpa(&mut report, &at)?;
+        self.session(&mut report, &at)?;
+
+        let alerts = self.alerts(&report);
+        for alert in alerts.iter().filter(|alert| !report.alerts.contains(alert)) {
+            log::warn!(target: "health", "{}", alert);
+        }
+        report.alerts = alerts;
+        if let Some(metrics) = &self.metrics {
+            metrics.update(&report);
+        }
+        *self.report.write() = report;
+        Ok(())
+    }
+
+    fn has_key(&self, public: Vec<u8>, key_type: KeyTypeId) -> bool {
+        SyncCryptoStore::has_keys(&*self.keystore, &[(public, key_type)])
+    }
+
+    /// Count the slots up to the block the keys could claim, and the block if they authored it.
+    fn babe(
+        &mut self,
+        report: &mut HealthReport,
+        at: &BlockId<Block>,
+        header: &<Block as BlockT>::Header,
+        origin: BlockOrigin,
+    ) -> Result<(), String> {
+        let epoch = self
+            .client
+            .runtime_api()
+            .current_epoch(at)
+            .map_err(|e| format!("cannot read the BABE epoch: {}", e))?;
+        let pre_digest = find_pre_digest::<Block>(header)
+            .map_err(|e| format!("cannot read the BABE pre-digest: {}", e))?;
+        let slot = *pre_digest.slot();
+        let start_slot = *epoch.start_slot;
+
+        let last_slot = match self.last_slot {
+            Some(last_slot) if report.babe.epoch == epoch.epoch_index => last_slot,
+            _ => {
+                report.babe = BabeHealth { epoch: epoch.epoch_index, ..Default::default() };
+                start_slot.saturating_sub(1)
+            }
+        };
+        self.epoch_progress = slot.saturating_sub(start_slot) as f64 / epoch.duration as f64;
+        if slot <= last_slot {
+            // a reorg to a block of a slot already counted.
+            return Ok(());
+        }
+
+        let epoch = Epoch {
+            epoch_index: epoch.epoch_index,
+            start_slot: epoch.start_slot,
+            duration: epoch.duration,
+            authorities: epoch.authorities,
+            randomness: epoch.randomness,
+            config: epoch.config,
+        };
+        let first = (last_slot + 1).max(slot.saturating_sub(epoch.duration));
+        for claimable in first..=slot {
+            if claim_slot(claimable.into(), &epoch, &self.keystore).is_some() {
+                report.babe.claimed_slots += 1;
+            }
+        }
+        let author = epoch.authorities.get(pre_digest.authority_index() as usize);
+        if let Some((author, _)) = author {
+            if self.has_key(author.to_raw_vec(), key_types::BABE) {
+                report.babe.authored_blocks += 1;
+                if origin == BlockOrigin::NetworkBroadcast && slot > self.started_slot {
+                    report.babe.foreign_blocks += 1;
+                }
+            }
+        }
+        self.last_slot = Some(slot);
+        Ok(())
+    }
+
+    /// Count the GRANDPA rounds completed since the last block and the votes of the keys in them.
+    fn grandpa(&mut self, report: &mut HealthReport, at: &BlockId<Block>) -> Result<(), String> {
+        let ours: Vec<_> = self
+            .client
+            .runtime_api()
+            .grandpa_authorities(at)
+            .map_err(|e| format!("cannot read the GRANDPA authorities: {}", e))?
+            .into_iter()
+            .map(|(id, _)| id)
+            .filter(|id| self.has_key(id.to_raw_vec(), key_types::GRANDPA))
+            .collect();
+        report.grandpa.is_voter = !ours.is_empty();
+
+        let state = match self.voter_state.voter_state() {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        if state.best_round.0 < self.last_round {
+            // the rounds of a new authority set start over.
+            self.last_round = 0;
+        }
+        let mut rounds: Vec<_> =
+            state.background_rounds.iter().filter(|(round, _)| **round > self.last_round).collect();
+        rounds.sort_by_key(|(round, _)| **round);
+        for (round, state) in rounds {
+            if report.grandpa.is_voter {
+                report.grandpa.rounds += 1;
+                if ours.iter().any(|id| state.prevote_ids.contains(id)) {
+                    report.grandpa.prevotes += 1;
+                }
+                if ours.iter().any(|id| state.precommit_ids.contains(id)) {
+                    report.grandpa.precommits += 1;
+                }
+            }
+            self.last_round = *round;
+        }
+        Ok(())
+    }
+
+    /// Check the heartbeat of the current session and the next session keys on chain.
+    fn session(&self, report: &mut HealthReport, at: &BlockId<Block>) -> Result<(), String> {
+        let session = storage::<u32>(&self.client, at, storage_key(b"Session", b"CurrentIndex"))?
+            .unwrap_or_default();
+        report.session = session;
+
+        let im_online_keys = storage::<Vec<sp_core::sr25519::Public>>(
+            &self.client,
+            at,
+            storage_key(b"ImOnline", b"Keys"),
+        )?
+        .unwrap_or_default();
+        let index = im_online_keys
+            .iter()
+            .position(|key| self.has_key(key.to_raw_vec(), key_types::IM_ONLINE));
+        report.heartbeat = match index {
+            Some(index) => {
+                let mut key = storage_key(b"ImOnline", b"ReceivedHeartbeats");
+                key.extend(twox_64(&session.encode()));
+                key.extend(session.encode());
+                key.extend(twox_64(&(index as u32).encode()));
+                key.extend((index as u32).encode());
+                Some(storage::<Vec<u8>>(&self.client, at, key)?.is_some())
+            }
+            None => None,
+        };
+
+        // the validators the session keys of the keystore are registered for.
+        let mut validators = BTreeSet::new();
+        for key_type in SESSION_KEY_TYPES.iter() {
+            let publics: Vec<Vec<u8>> = if *key_type == key_types::GRANDPA {
+                SyncCryptoStore::ed25519_public_keys(&*self.keystore, *key_type)
+                    .iter()
+                    .map(Public::to_raw_vec)
+                    .collect()
+            } else {
+                SyncCryptoStore::sr25519_public_keys(&*self.keystore, *key_type)
+                    .iter()
+                    .map(Public::to_raw_vec)
+                    .collect()
+            };
+            for public in publics {
+                let owner = (*key_type, public).encode();
+                let mut key = storage_key(b"Session", b"KeyOwner");
+                key.extend(twox_64(&owner));
+                key.extend(owner);
+                validators.extend(storage::<AccountId>(&self.client, at, key)?);
+            }
+        }
+
+        let mut missing = BTreeSet::new();
+        for validator in &validators {
+            let mut key = storage_key(b"Session", b"NextKeys");
+            key.extend(twox_64(&validator.encode()));
+            key.extend(validator.encode());
+            let next_keys = storage::<SessionKeys>(&self.client, at, key)?;
+            for (public, key_type) in
+                next_keys.map(SessionKeys::into_raw_public_keys).unwrap_or_default()
+            {
+                if !self.has_key(public, key_type) {
+                    missing.insert(key_type_name(&key_type));
+                }
+            }
+        }
+        report.validators = validators
+            .iter()
+            .map(|validator| validator.to_ss58check_with_version(self.ss58_format))
+            .collect();
+        report.session_keys_match = !validators.is_empty() && missing.is_empty();
+        report.missing_keys = missing.into_iter().collect();
+        Ok(())
+    }
+
+    /// What puts the validator at risk in a report.
+    fn alerts(&self, report: &HealthReport) -> Vec<String> {
+        let mut alerts = Vec::new();
+        if report.validators.is_empty() {
+            alerts.push("no session keys of the keystore are registered on chain".into());
+        } else if !report.missing_keys.is_empty() {
+            alerts.push(format!(
+                "the keystore lacks the {} keys of the next session",
+                report.missing_keys.join(", ")
+            ));
+        }
+
+        let babe = &report.babe;
+        if babe.foreign_blocks > 0 {
+            alerts.push(format!(
+                "another node authored {} blocks of epoch {} with the BABE keys of this node, \
+                 which is equivocation",
+                babe.foreign_blocks, babe.epoch
+            ));
+        }
+        if babe.claimed_slots >= MIN_SAMPLES && babe.authored_blocks * 2 < babe.claimed_slots {
+            alerts.push(format!(
+                "only {} blocks of the best chain were authored in the {} slots claimed in epoch {}",
+                babe.authored_blocks, babe.claimed_slots, babe.epoch
+            ));
+        }
+
+        let grandpa = &report.grandpa;
+        if grandpa.rounds >= MIN_SAMPLES
+            && (grandpa.prevotes * 2 < grandpa.rounds || grandpa.precommits * 2 < grandpa.rounds)
+        {
+            alerts.push(format!(
+                "prevoted in {} and precommitted in {} of {} GRANDPA rounds",
+                grandpa.prevotes, grandpa.precommits, grandpa.rounds
+            ));
+        }
+
+        if report.heartbeat == Some(false) && self.epoch_progress > HEARTBEAT_DEADLINE {
+            alerts.push(format!(
+                "no ImOnline heartbeat in session {} yet, the validator may be reported offline",
+                report.session
+            ));
+        }
+        alerts
+    }
+}
@@ -0,0 +1,392 @@
+//! The block indexer behind `--index-db`: blocks, extrinsics, events and transfers of the chain in
+//! an SQLite database, for explorers and other tools that would rather run SQL than scrape RPC.
+//!
+//! The index follows the best chain. The rows of a block that is not finalized yet are deleted when
+//! a reorg retracts it and the blocks of the new best chain are indexed in its place, so
+//! `blocks.finalized` tells the rows that can still change from those that cannot. On start the
+//! indexer catches up from the last block it indexed, from genesis with a new database. The fee of
+//! an extrinsic is computed with `TransactionPayment` on the state of the parent block, so the fees
+//! of a backfill are only complete on an archive node.
+//!
+//! Amounts are decimal strings, as they do not fit SQLite integers, and accounts are SS58
+//! addresses. Calls and events are the JSON the `inspect-block` subcommand prints, with raw
+//! amounts.
+
+use crate::chain::{actual_fee, block_events, era_nonce_tip, extrinsics, outcome, ss58_format};
+use crate::inspect::decode;
+use crate::metadata::{Formatter, NativeMetadata};
+use crate::service::FullClient;
+use codec::Encode;
+use frame_system::Phase;
+use futures::{future, stream, StreamExt};
+use node_primitives::{BlockNumber, Hash};
+use node_runtime::Call;
+use rusqlite::{params, Connection, OptionalExtension};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sc_service::ChainSpec;
+use serde_json::Value;
+use sp_blockchain::HeaderBackend;
+use sp_core::crypto::Ss58AddressFormat;
+use sp_runtime::{
+    generic::BlockId,
+    traits::{BlakeTwo256, Hash as _, Header as _},
+};
+use std::{fmt, fs, path::Path, sync::Arc};
+
+const SCHEMA: &str = "
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS blocks (
+    number INTEGER NOT NULL,
+    hash TEXT NOT NULL PRIMARY KEY,
+    parent_hash TEXT NOT NULL,
+    -- milliseconds since the Unix epoch, from `Timestamp::set`
+    timestamp INTEGER,
+    finalized INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS blocks_by_number ON blocks (number);
+
+CREATE TABLE IF NOT EXISTS extrinsics (
+    block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+    block_number INTEGER NOT NULL,
+    idx INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    pallet TEXT,
+    name TEXT,
+    call TEXT NOT NULL,
+    -- NULL for unsigned extrinsics, as are nonce, tip and fee
+    signer TEXT,
+    nonce INTEGER,
+    tip TEXT,
+    fee TEXT,
+    success INTEGER NOT NULL,
+    PRIMARY KEY (block_hash, idx)
+);
+CREATE INDEX IF NOT EXISTS extrinsics_by_hash ON extrinsics (hash);
+CREATE INDEX IF NOT EXISTS extrinsics_by_signer ON extrinsics (signer);
+
+CREATE TABLE IF NOT EXISTS events (
+    block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+    block_number INTEGER NOT NULL,
+    idx INTEGER NOT NULL,
+    -- 'initialization', 'extrinsic' or 'finalization'
+    phase TEXT NOT NULL,
+    extrinsic_idx INTEGER,
+    pallet TEXT,
+    name TEXT,
+    event TEXT NOT NULL,
+    PRIMARY KEY (block_hash, idx)
+);
+CREATE INDEX IF NOT EXISTS events_by_name ON events (pallet, name);
+
+CREATE TABLE IF NOT EXISTS transfers (
+    block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+    block_number INTEGER NOT NULL,
+    event_idx INTEGER NOT NULL,
+    extrinsic_idx INTEGER,
+    -- NULL for the native token
+    asset_id INTEGER,
+    sender TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (block_hash, event_idx)
+);
+CREATE INDEX IF NOT EXISTS transfers_by_sender ON transfers (sender);
+CREATE INDEX IF NOT EXISTS transfers_by_recipient ON transfers (recipient);
+";
+
+#[derive(Debug)]
+enum Error {
+    Database(rusqlite::Error),
+    Client(sp_blockchain::Error),
+    Other(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Database(e) => write!(f, "database error: {}", e),
+            Error::Client(e) => write!(f, "client error: {}", e),
+            Error::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl From<sp_blockchain::Error> for Error {
+    fn from(e: sp_blockchain::Error) -> Self {
+        Error::Client(e)
+    }
+}
+
+/// A JSON value as the text of a column: strings without their quotes.
+fn column(value: Value) -> String {
+    match value {
+        Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+/// The pallet and name of a call or event as two columns.
+fn split(name: Option<(&str, &str)>) -> (Option<String>, Option<String>) {
+    match name {
+        Some((pallet, name)) => (Some(pallet.into()), Some(name.into())),
+        None => (None, None),
+    }
+}
+
+/// The asset, sender, recipient and amount of a transfer event.
+fn transfer(event: &Value) -> Option<(Option<u64>, &Value, &Value, &Value)> {
+    let args = event["args"].as_array()?;
+    match (event["pallet"].as_str()?, event["event"].as_str()?, &args[..]) {
+        ("Balances", "Transfer", [from, to, amount]) => Some((None, from, to, amount)),
+        ("Assets", "Transferred", [id, from, to, amount])
+        | ("Assets", "TransferredApproved", [id, from, _, to, amount]) => {
+            Some((id.as_u64(), from, to, amount))
+        }
+        _ => None,
+    }
+}
+
+/// The index, with the client it reads the chain from.
+pub struct Indexer {
+    client: Arc<FullClient>,
+    db: Connection,
+    metadata: NativeMetadata,
+    ss58_format: Ss58AddressFormat,
+}
+
+impl Indexer {
+    /// Open the index at `path`, creating it if it does not exist.
+    pub fn open(
+        path: &Path,
+        client: Arc<FullClient>,
+        chain_spec: &dyn ChainSpec,
+    ) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("cannot create {}: {}", parent.display(), e))?;
+        }
+        let db = Connection::open(path)
+            .and_then(|db| db.execute_batch(SCHEMA).map(|_| db))
+            .map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+        Ok(Indexer {
+            client,
+            db,
+            metadata: NativeMetadata::new()?,
+            ss58_format: ss58_format(chain_spec),
+        })
+    }
+
+    /// Keep the index in line with the chain until the node shuts down.
+    pub async fn run(mut self) {
+        // subscribe before catching up, so that nothing imported meanwhile is missed.
+        let best = self
+            .client
+            .import_notification_stream()
+            .filter(|notification| future::ready(notification.is_new_best))
+            .map(|_| None);
+        let finalized = self
+            .client
+            .finality_notification_stream()
+            .map(|notification| Some(*notification.header.number()));
+        let mut notifications = stream::select(best, finalized);
+
+        log::info!(target: "indexer", "Catching up with the best block");
+        self.log_error(Self::follow_best);
+        while let Some(notification) = notifications.next().await {
+            match notification {
+                None => self.log_error(Self::follow_best),
+                Some(number) => self.log_error(|indexer| indexer.finalize(number)),
+            }
+        }
+    }
+
+    fn log_error(&mut self, f: impl FnOnce(&mut Self) -> Result<(), Error>) {
+        if let Err(e) = f(self) {
+            log::error!(target: "indexer", "Indexing failed: {}", e);
+        }
+    }
+
+    /// Roll back the blocks a reorg retracted, then index the best chain after the last block
+    /// that is left.
+    fn follow_best(&mut self) -> Result<(), Error> {
+        let best_number = self.client.info().best_number;
+        let mut next = loop {
+            let last = self
+                .db
+                .query_row(
+                    "SELECT number, hash, finalized FROM blocks ORDER BY number DESC LIMIT 1",
+                    params![],
+                    |row| {
+                        Ok((row.get::<_, BlockNumber>(0)?, row.get::<_, String>(1)?, row.get(2)?))
+                    },
+                )
+                .optional()?;
+            let (number, hash, finalized) = match last {
+                Some(last) => last,
+                None => break 0,
+            };
+            let canonical = self.client.hash(number)?.map(|hash| format!("{:?}", hash));
+            if canonical.as_deref() == Some(hash.as_str()) {
+                break number + 1;
+            }
+            if finalized {
+                return Err(Error::Other(format!(
+                    "finalized block #{} {} is not on the best chain",
+                    number, hash
+                )));
+            }
+            log::info!(target: "indexer", "Rolling back retracted block #{} {}", number, hash);
+            self.db.execute("DELETE FROM blocks WHERE hash = ?1", params![hash])?;
+        };
+
+        while next <= best_number {
+            let hash = self
+                .client
+                .hash(next)?
+                .ok_or_else(|| Error::Other(format!("no block #{} on the best chain", next)))?;
+            self.index(hash)?;
+            next += 1;
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self, number: BlockNumber) -> Result<(), Error> {
+        self.db.execute(
+            "UPDATE blocks SET finalized = 1 WHERE number <= ?1 AND finalized = 0",
+            params![number],
+        )?;
+        Ok(())
+    }
+
+    /// Write a block with its extrinsics, events and transfers, in one database transaction.
+    fn index(&mut self, hash: Hash) -> Result<(), Error> {
+        let block = self
+            .client
+            .block(&BlockId::Hash(hash))?
+            .ok_or_else(|| Error::Other(format!("no block {:?} in the database", hash)))?
+            .block;
+        let number = *block.header.number();
+        let parent = BlockId::Hash(*block.header.parent_hash());
+        let extrinsics = extrinsics(&block).map_err(Error::Other)?;
+        let events = block_events(&self.client, &BlockId::Hash(hash)).map_err(Error::Other)?;
+        let finalized = number <= self.client.info().finalized_number;
+
+        let no_assets = |_| None;
+        let formatter = Formatter { ss58_format: self.ss58_format, token: None, asset: &no_assets };
+        let metadata = &self.metadata;
+        let block_hash = format!("{:?}", hash);
+        let timestamp = extrinsics.iter().find_map(|extrinsic| match &extrinsic.function {
+            Call::Timestamp(pallet_timestamp::Call::set(now)) => Some(*now as i64),
+            _ => None,
+        });
+
+        let db = self.db.transaction()?;
+        db.execute(
+            "INSERT INTO blocks (number, hash, parent_hash, timestamp, finalized)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                number,
+                block_hash,
+                format!("{:?}", block.header.parent_hash()),
+                timestamp,
+                finalized
+            ],
+        )?;
+
+        for (index, (extrinsic, opaque)) in extrinsics.iter().zip(&block.extrinsics).enumerate() {
+            let outcome = outcome(&events, index);
+            let (signer, nonce, tip, fee) = match &extrinsic.signature {
+                Some((address, _, extra)) => {
+                    let (_, nonce, tip) = era_nonce_tip(extra);
+                    let fee = outcome
+                        .as_ref()
+                        .and_then(|(_, info)| {
+                            actual_fee(&self.client, &parent, opaque, tip, info).ok()
+                        })
+                        .map(|fee| fee.to_string());
+                    let signer = column(formatter.address(address));
+                    (Some(signer), Some(nonce), Some(tip.to_string()), fee)
+                }
+                None => (None, None, None, None),
+            };
+            let (pallet, name) = split(metadata.call_name(&extrinsic.function.encode()));
+            let call = decode(&extrinsic.function, |input| metadata.decode_call(input, &formatter));
+
+            db.execute(
+                "INSERT INTO extrinsics
+                 (block_hash, block_number, idx, hash, pallet, name, call, signer, nonce, tip, fee,
+                  success)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    block_hash,
+                    number,
+                    index as u32,
+                    format!("{:?}", BlakeTwo256::hash(&extrinsic.encode())),
+                    pallet,
+                    name,
+                    call.to_string(),
+                    signer,
+                    nonce,
+                    tip,
+                    fee,
+                    outcome.map_or(false, |(success, _)| success)
+                ],
+            )?;
+        }
+
+        for (index, record) in events.iter().enumerate() {
+            let (phase, extrinsic) = match record.phase {
+                Phase::Initialization => ("initialization", None),
+                Phase::ApplyExtrinsic(extrinsic) => ("extrinsic", Some(extrinsic)),
+                Phase::Finalization => ("finalization", None),
+            };
+            let (pallet, name) = split(metadata.event_name(&record.event.encode()));
+            let event = decode(&record.event, |input| metadata.decode_event(input, &formatter));
+
+            db.execute(
+                "INSERT INTO events
+                 (block_hash, block_number, idx, phase, extrinsic_idx, pallet, name, event)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    block_hash,
+                    number,
+                    index as u32,
+                    phase,
+                    extrinsic,
+                    pallet,
+                    name,
+                    event.to_string()
+                ],
+            )?;
+            if let Some((asset_id, from, to, amount)) = transfer(&event) {
+                db.execute(
+                    "INSERT INTO transfers
+                     (block_hash, block_number, event_idx, extrinsic_idx, asset_id, sender,
+                      recipient, amount)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        block_hash,
+                        number,
+                        index as u32,
+                        extrinsic,
+                        asset_id.map(|id| id as u32),
+                        column(from.clone()),
+                        column(to.clone()),
+                        column(amount.clone())
+                    ],
+                )?;
+            }
+        }
+
+        db.commit()?;
+        log::debug!(target: "indexer", "Indexed block #{} {}", number, block_hash);
+        Ok(())
+    }
+}
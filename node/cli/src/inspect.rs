@@ -0,0 +1,219 @@
+//! The `inspect-block` subcommand: a block of the local database with the events it emitted,
+//! decoded with the metadata of the native runtime.
+//!
+//! Unlike `inspect`, which prints the `Debug` output of a block, accounts are shown in the SS58
+//! format of the network, amounts of the native token and of assets with their decimals, and
+//! every extrinsic with the events it emitted. Calls and events the metadata codecs cannot
+//! decode fall back to their `Debug` output.
+
+use crate::chain::{
+    block_events, era_nonce_tip, extrinsics, ss58_format, storage, storage_key, token,
+};
+use crate::cli::InspectBlockCmd;
+use crate::metadata::{parse_hash, Formatter, NativeMetadata};
+use crate::service::new_partial;
+use codec::{Decode, Encode};
+use frame_system::Phase;
+use node_primitives::Balance;
+use node_runtime::Call;
+use sc_cli::{CliConfiguration, Error, ImportParams, OutputType, SharedParams};
+use sc_client_api::BlockBackend;
+use sc_service::{Configuration, PartialComponents};
+use serde_json::{json, Value};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58AddressFormat, hashing::blake2_128};
+use sp_runtime::{generic::BlockId, traits::Header as _};
+
+/// The metadata of an asset, as `pallet_assets` stores it.
+#[derive(Decode)]
+struct AssetMetadata {
+    _deposit: Balance,
+    _name: Vec<u8>,
+    symbol: Vec<u8>,
+    decimals: u8,
+    _is_frozen: bool,
+}
+
+/// Decode a call or an event with the metadata codecs, falling back to its `Debug` output.
+pub(crate) fn decode<T: Encode + std::fmt::Debug>(
+    value: &T,
+    decode: impl FnOnce(&mut &[u8]) -> Result<Value, String>,
+) -> Value {
+    let encoded = value.encode();
+    let mut input = &encoded[..];
+    match decode(&mut input) {
+        Ok(decoded) if input.is_empty() => decoded,
+        _ => Value::String(format!("{:?}", value)),
+    }
+}
+
+/// A call or event in one line, e.g. `Balances.transfer(dest: 5F..., value: 1 1CN)`.
+pub(crate) fn describe(value: &Value) -> String {
+    match value {
+        Value::Object(object) => {
+            let pallet = object.get("pallet").and_then(Value::as_str).unwrap_or_default();
+            let name = object
+                .get("call")
+                .or_else(|| object.get("event"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let args = match object.get("args") {
+                Some(Value::Object(args)) => args
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, describe(value)))
+                    .collect::<Vec<_>>(),
+                Some(Value::Array(args)) => args.iter().map(describe).collect(),
+                _ => {
+                    return object
+                        .iter()
+                        .map(|(name, value)| format!("{}: {}", name, describe(value)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            };
+            format!("{}.{}({})", pallet, name, args.join(", "))
+        }
+        Value::Array(values) => {
+            format!("[{}]", values.iter().map(describe).collect::<Vec<_>>().join(", "))
+        }
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl InspectBlockCmd {
+    /// Run the command.
+    pub fn run(&self, config: Configuration) -> Result<(), Error> {
+        let token = token(&*config.chain_spec);
+        let PartialComponents { client, .. } = new_partial(&config)?;
+
+        let id = match &self.block {
+            None => BlockId::Hash(client.info().best_hash),
+            Some(block) if block.starts_with("0x") => {
+                BlockId::Hash(parse_hash("the block hash", block).map_err(Error::Input)?)
+            }
+            Some(block) => BlockId::Number(block.parse().map_err(|e| {
+                Error::Input(format!("{} is not a block number or hash: {}", block, e))
+            })?),
+        };
+        let block = client
+            .block(&id)?
+            .ok_or_else(|| Error::Input(format!("no block {} in the database", id)))?
+            .block;
+        let hash = block.header.hash();
+        let at = BlockId::Hash(hash);
+
+        let events = block_events(&client, &at).map_err(Error::Input)?;
+
+        let asset = |id: u32| {
+            let mut key = storage_key(b"Assets", b"Metadata");
+            key.extend(blake2_128(&id.encode()));
+            key.extend(id.encode());
+            let metadata = storage::<AssetMetadata>(&client, &at, key).ok()??;
+            Some((String::from_utf8_lossy(&metadata.symbol).into_owned(), metadata.decimals))
+        };
+        let formatter = Formatter {
+            ss58_format: self
+                .ss58_prefix
+                .map_or_else(|| ss58_format(&*config.chain_spec), Ss58AddressFormat::Custom),
+            token: Some(token),
+            asset: &asset,
+        };
+        let metadata = NativeMetadata::new().map_err(Error::Input)?;
+        let call = |call: &Call| decode(call, |input| metadata.decode_call(input, &formatter));
+        let events_of = |phase: Phase| {
+            events
+                .iter()
+                .filter(|record| record.phase == phase)
+                .map(|record| {
+                    decode(&record.event, |input| metadata.decode_event(input, &formatter))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let extrinsics = extrinsics(&block)
+            .map_err(Error::Input)?
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.extrinsic.map_or(true, |only| only as usize == *index))
+            .map(|(index, extrinsic)| {
+                let signed = extrinsic.signature.as_ref().map(|(address, _, extra)| {
+                    let (era, nonce, tip) = era_nonce_tip(extra);
+                    json!({
+                        "signer": formatter.address(address),
+                        "nonce": nonce,
+                        "tip": formatter.native(tip),
+                        "era": format!("{:?}", era),
+                    })
+                });
+                json!({
+                    "index": index,
+                    "signed": signed,
+                    "call": call(&extrinsic.function),
+                    "events": events_of(Phase::ApplyExtrinsic(index as u32)),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut inspected = json!({
+            "number": block.header.number,
+            "hash": format!("{:?}", hash),
+            "parentHash": format!("{:?}", block.header.parent_hash),
+            "extrinsics": extrinsics,
+        });
+        if self.extrinsic.is_none() {
+            inspected["initialization"] = events_of(Phase::Initialization).into();
+            inspected["finalization"] = events_of(Phase::Finalization).into();
+        }
+
+        match self.output_type.output_type {
+            OutputType::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&inspected).expect("Json pretty print failed")
+            ),
+            OutputType::Text => print_text(&inspected),
+        }
+        Ok(())
+    }
+}
+
+fn print_text(block: &Value) {
+    let events = |events: &Value| {
+        for event in events.as_array().into_iter().flatten() {
+            println!("    {}", describe(event));
+        }
+    };
+
+    println!(
+        "Block #{} {}, parent {}",
+        block["number"],
+        block["hash"].as_str().unwrap_or_default(),
+        block["parentHash"].as_str().unwrap_or_default(),
+    );
+    if let Some(initialization) = block.get("initialization") {
+        println!("  Initialization events:");
+        events(initialization);
+    }
+    for extrinsic in block["extrinsics"].as_array().into_iter().flatten() {
+        println!("  Extrinsic {}: {}", extrinsic["index"], describe(&extrinsic["call"]));
+        if !extrinsic["signed"].is_null() {
+            println!("    Signed: {}", describe(&extrinsic["signed"]));
+        }
+        println!("    Events:");
+        events(&extrinsic["events"]);
+    }
+    if let Some(finalization) = block.get("finalization") {
+        println!("  Finalization events:");
+        events(finalization);
+    }
+}
+
+impl CliConfiguration for InspectBlockCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn import_params(&self) -> Option<&ImportParams> {
+        Some(&self.import_params)
+    }
+}
@@ -34,12 +34,42 @@ pub mod chain_spec;
 
 #[macro_use]
 mod service;
+mod block_announce;
+mod voting_rules;
 #[cfg(feature = "browser")]
 mod browser;
+#[cfg(any(feature = "cli", feature = "browser"))]
+mod chain;
+#[cfg(feature = "cli")]
+mod checkpoint;
 #[cfg(feature = "cli")]
 mod cli;
 #[cfg(feature = "cli")]
 mod command;
+#[cfg(feature = "cli")]
+mod generate_spec;
+#[cfg(feature = "cli")]
+mod health;
+#[cfg(feature = "cli")]
+mod indexer;
+#[cfg(feature = "cli")]
+mod inspect;
+#[cfg(feature = "cli")]
+mod metadata;
+#[cfg(feature = "cli")]
+mod metrics;
+#[cfg(feature = "cli")]
+mod remote_signer;
+#[cfg(feature = "cli")]
+mod rpc;
+#[cfg(feature = "cli")]
+mod runtime_upgrade;
+#[cfg(feature = "cli")]
+mod session_keys;
+#[cfg(feature = "cli")]
+mod slashing_protection;
+#[cfg(feature = "cli")]
+mod tx;
 
 #[cfg(feature = "browser")]
 pub use browser::*;
@@ -47,3 +77,5 @@ pub use browser::*;
 pub use cli::*;
 #[cfg(feature = "cli")]
 pub use command::*;
+#[cfg(feature = "cli")]
+pub use remote_signer::run_remote_signer;
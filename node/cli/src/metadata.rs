@@ -0,0 +1,515 @@
+//! Calls and events of the native runtime as JSON, driven by the runtime metadata.
+//!
+//! A call is written as
+//! `{"pallet": "Balances", "call": "transfer", "args": {"dest": "5F...", "value": "1000"}}`
+//! and an event as `{"pallet": "Balances", "event": "Transfer", "args": ["5F...", "5F...", "1000"]}`.
+//! The metadata only names the types of the arguments, so the codecs know the types this runtime
+//! uses and nothing more: accounts, integers and balances, hashes, bytes, booleans, nested calls,
+//! and `Compact`, `Option`, `Vec` and `Box` of them.
+
+use crate::generate_spec::parse_key;
+use codec::{Compact, Decode, Encode};
+use frame_metadata::{
+    DecodeDifferent, DecodeDifferentArray, FnEncode, FunctionMetadata, ModuleMetadata,
+    RuntimeMetadata, RuntimeMetadataPrefixed, RuntimeMetadataV13,
+};
+use node_primitives::{AccountId, Hash};
+use node_runtime::{Address, Runtime};
+use serde_json::{json, Value};
+use sp_core::{
+    crypto::{Ss58AddressFormat, Ss58Codec},
+    hexdisplay::HexDisplay,
+    H256,
+};
+use sp_runtime::MultiAddress;
+use std::collections::BTreeMap;
+
+/// What a type named in the metadata is to the codecs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TypeKind<'a> {
+    Compact(&'a str),
+    Option(&'a str),
+    Bytes,
+    Vec(&'a str),
+    Call,
+    Address,
+    AccountId,
+    Hash,
+    Bool,
+    Integer(usize, Role),
+}
+
+/// What an integer stands for, which decides how it is rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    Plain,
+    NativeBalance,
+    AssetId,
+    AssetBalance,
+}
+
+/// The argument of `ty` if it is `wrapper<argument>`.
+fn generic_argument<'a>(ty: &'a str, wrapper: &str) -> Option<&'a str> {
+    ty.strip_prefix(wrapper)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+/// The name of a type without its path and generics, e.g. `Source` for
+/// `<T::Lookup as StaticLookup>::Source` and `BalanceOf` for `BalanceOf<T, I>`.
+fn base_name(ty: &str) -> &str {
+    let name = ty.rsplit("::").next().unwrap_or(ty);
+    name.split('<').next().unwrap_or(name)
+}
+
+/// Classify the type `ty`, stripped of whitespace, of an argument of a call or event of `pallet`.
+fn classify<'a>(pallet: &str, ty: &'a str) -> Result<TypeKind<'a>, String> {
+    if let Some(inner) = generic_argument(ty, "Box") {
+        return classify(pallet, inner);
+    }
+    if let Some(inner) = generic_argument(ty, "Compact") {
+        return Ok(TypeKind::Compact(inner));
+    }
+    if let Some(inner) = generic_argument(ty, "Option") {
+        return Ok(TypeKind::Option(inner));
+    }
+    if ty == "Vec<u8>" || ty == "Bytes" {
+        return Ok(TypeKind::Bytes);
+    }
+    if let Some(inner) = generic_argument(ty, "Vec") {
+        return Ok(TypeKind::Vec(inner));
+    }
+
+    Ok(match (pallet, base_name(ty)) {
+        (_, "Call") | (_, "CallOf") => TypeKind::Call,
+        (_, "Source") | (_, "LookupSource") | (_, "Address") => TypeKind::Address,
+        (_, "AccountId") => TypeKind::AccountId,
+        (_, "Hash") | (_, "H256") => TypeKind::Hash,
+        (_, "bool") => TypeKind::Bool,
+        (_, "u8") | (_, "Percent") => TypeKind::Integer(1, Role::Plain),
+        (_, "u16") => TypeKind::Integer(2, Role::Plain),
+        (_, "AssetId") | (_, "AssetIdOf") => TypeKind::Integer(4, Role::AssetId),
+        (_, "u32")
+        | (_, "BlockNumber")
+        | (_, "Index")
+        | (_, "AccountIndex")
+        | (_, "ProposalIndex")
+        | (_, "ReferendumIndex")
+        | (_, "PropIndex")
+        | (_, "MemberCount")
+        | (_, "EraIndex")
+        | (_, "SessionIndex")
+        | (_, "Perbill")
+        | (_, "Permill") => TypeKind::Integer(4, Role::Plain),
+        // the assets of `pallet_assets` have a balance type of their own.
+        ("Assets", "Balance") | (_, "AssetBalance") | (_, "AssetBalanceOf") => {
+            TypeKind::Integer(8, Role::AssetBalance)
+        }
+        (_, "u64") | (_, "Moment") | (_, "Weight") | (_, "Perquintill") => {
+            TypeKind::Integer(8, Role::Plain)
+        }
+        (_, "Balance") | (_, "BalanceOf") => TypeKind::Integer(16, Role::NativeBalance),
+        (_, "u128") => TypeKind::Integer(16, Role::Plain),
+        _ => return Err(format!("unsupported type `{}`", ty)),
+    })
+}
+
+fn text(value: &DecodeDifferent<&'static str, String>) -> &str {
+    match value {
+        DecodeDifferent::Encode(value) => value,
+        DecodeDifferent::Decoded(value) => value,
+    }
+}
+
+fn items<T>(value: &DecodeDifferentArray<T>) -> &[T] {
+    match value {
+        DecodeDifferent::Encode(value) => value,
+        DecodeDifferent::Decoded(value) => value,
+    }
+}
+
+fn names(value: &DecodeDifferentArray<&'static str, String>) -> Vec<&str> {
+    match value {
+        DecodeDifferent::Encode(names) => names.to_vec(),
+        DecodeDifferent::Decoded(names) => names.iter().map(String::as_str).collect(),
+    }
+}
+
+fn functions<T>(value: &Option<DecodeDifferent<FnEncode<&'static [T]>, Vec<T>>>) -> &[T] {
+    match value {
+        Some(DecodeDifferent::Encode(FnEncode(items))) => items(),
+        Some(DecodeDifferent::Decoded(items)) => items,
+        None => &[],
+    }
+}
+
+/// The pallet index, call index and argument types of a call, which transactions of the call
+/// depend on.
+pub(crate) type CallSignature = (u8, u8, Vec<String>);
+
+/// The signatures of the calls in SCALE encoded metadata, by pallet and call name.
+pub(crate) fn call_signatures(
+    metadata: &[u8],
+) -> Result<BTreeMap<(String, String), CallSignature>, String> {
+    let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+        .map_err(|e| format!("cannot decode the metadata: {}", e))?;
+    let metadata = match metadata.1 {
+        RuntimeMetadata::V13(metadata) => metadata,
+        _ => return Err("the metadata has an unknown version".into()),
+    };
+    let mut signatures = BTreeMap::new();
+    for module in items(&metadata.modules) {
+        for (index, call) in functions::<FunctionMetadata>(&module.calls).iter().enumerate() {
+            let types = items(&call.arguments)
+                .iter()
+                .map(|argument| text(&argument.ty).to_string())
+                .collect();
+            signatures.insert(
+                (text(&module.name).to_string(), text(&call.name).to_string()),
+                (module.index, index as u8, types),
+            );
+        }
+    }
+    Ok(signatures)
+}
+
+pub(crate) fn parse_hex(what: &str, value: &str) -> Result<Vec<u8>, String> {
+    sp_core::bytes::from_hex(value).map_err(|e| format!("{} is not valid hex: {}", what, e))
+}
+
+pub(crate) fn parse_hash(what: &str, value: &str) -> Result<Hash, String> {
+    let bytes = parse_hex(what, value)?;
+    if bytes.len() != 32 {
+        return Err(format!("{} is {} bytes long, expected 32", what, bytes.len()));
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+fn parse_integer(value: &Value) -> Result<u128, String> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .map(Into::into)
+            .ok_or_else(|| format!("{} is not an unsigned integer", number)),
+        Value::String(number) => number
+            .replace('_', "")
+            .parse()
+            .map_err(|e| format!("{} is not an unsigned integer: {}", number, e)),
+        _ => Err("expected an unsigned integer".into()),
+    }
+}
+
+/// `amount` in units of `10^decimals`, e.g. `1.5 1CN`.
+pub(crate) fn format_amount(amount: u128, decimals: u8, symbol: &str) -> String {
+    let unit = match 10u128.checked_pow(decimals.into()) {
+        Some(unit) => unit,
+        None => return format!("{} {}", amount, symbol),
+    };
+    let fraction = format!("{:0width$}", amount % unit, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{} {}", amount / unit, symbol)
+    } else {
+        format!("{}.{} {}", amount / unit, fraction, symbol)
+    }
+}
+
+/// How decoded values are rendered.
+pub(crate) struct Formatter<'a> {
+    /// The SS58 format of accounts.
+    pub ss58_format: Ss58AddressFormat,
+    /// The symbol and decimals of the native token, if known.
+    pub token: Option<(String, u8)>,
+    /// The symbol and decimals of an asset, if it has metadata.
+    pub asset: &'a dyn Fn(u32) -> Option<(String, u8)>,
+}
+
+impl<'a> Formatter<'a> {
+    /// An account in the SS58 format of the formatter.
+    pub fn account(&self, account: &AccountId) -> Value {
+        Value::String(account.to_ss58check_with_version(self.ss58_format))
+    }
+
+    /// An address, as an account if it is one.
+    pub fn address(&self, address: &Address) -> Value {
+        match address {
+            MultiAddress::Id(account) => self.account(account),
+            MultiAddress::Index(index) => json!({ "index": index }),
+            address => Value::String(format!("{:?}", address)),
+        }
+    }
+
+    /// An amount of the native token.
+    pub fn native(&self, amount: u128) -> Value {
+        self.integer(amount, Role::NativeBalance, None)
+    }
+
+    fn integer(&self, integer: u128, role: Role, asset_id: Option<u32>) -> Value {
+        let formatted = match role {
+            Role::NativeBalance => self
+                .token
+                .as_ref()
+                .map(|(symbol, decimals)| format_amount(integer, *decimals, symbol)),
+            Role::AssetBalance => asset_id
+                .and_then(|id| (self.asset)(id))
+                .map(|(symbol, decimals)| format_amount(integer, decimals, &symbol)),
+            Role::Plain | Role::AssetId => None,
+        };
+        match formatted {
+            Some(formatted) => Value::String(formatted),
+            None if integer > u64::max_value().into() => Value::String(integer.to_string()),
+            None => json!(integer as u64),
+        }
+    }
+}
+
+/// The calls and events of the native runtime.
+pub(crate) struct NativeMetadata {
+    metadata: RuntimeMetadataV13,
+}
+
+impl NativeMetadata {
+    pub fn new() -> Result<Self, String> {
+        match Runtime::metadata().1 {
+            RuntimeMetadata::V13(metadata) => Ok(NativeMetadata { metadata }),
+            _ => Err("the native runtime has metadata of an unknown version".into()),
+        }
+    }
+
+    fn module_by_name(&self, name: &str) -> Result<&ModuleMetadata, String> {
+        items(&self.metadata.modules)
+            .iter()
+            .find(|module| text(&module.name) == name)
+            .ok_or_else(|| format!("no pallet {}", name))
+    }
+
+    fn module_by_index(&self, index: u8) -> Result<&ModuleMetadata, String> {
+        items(&self.metadata.modules)
+            .iter()
+            .find(|module| module.index == index)
+            .ok_or_else(|| format!("no pallet with index {}", index))
+    }
+
+    /// The pallet and name of an encoded call, even one whose arguments cannot be decoded.
+    pub fn call_name(&self, call: &[u8]) -> Option<(&str, &str)> {
+        let module = self.module_by_index(*call.get(0)?).ok()?;
+        let call = functions::<FunctionMetadata>(&module.calls).get(*call.get(1)? as usize)?;
+        Some((text(&module.name), text(&call.name)))
+    }
+
+    /// The pallet and name of an encoded event, even one whose arguments cannot be decoded.
+    pub fn event_name(&self, event: &[u8]) -> Option<(&str, &str)> {
+        let module = self.module_by_index(*event.get(0)?).ok()?;
+        let event = functions(&module.event).get(*event.get(1)? as usize)?;
+        Some((text(&module.name), text(&event.name)))
+    }
+
+    /// Encode a call from its JSON description.
+    pub fn encode_call(&self, value: &Value) -> Result<Vec<u8>, String> {
+        let pallet = value["pallet"].as_str().ok_or("the call needs a pallet")?;
+        let name = value["call"].as_str().ok_or("the call needs a call name")?;
+        let args = match &value["args"] {
+            Value::Null => serde_json::Map::new(),
+            Value::Object(args) => args.clone(),
+            _ => return Err("the args of a call must be an object".into()),
+        };
+        let known = |key: &&String| ["pallet", "call", "args"].contains(&key.as_str());
+        if let Some(key) = value
+            .as_object()
+            .and_then(|value| value.keys().find(|key| !known(key)))
+        {
+            return Err(format!("unknown field `{}` in a call", key));
+        }
+
+        let module = self.module_by_name(pallet)?;
+        let (call_index, call) = functions::<FunctionMetadata>(&module.calls)
+            .iter()
+            .enumerate()
+            .find(|(_, call)| text(&call.name) == name)
+            .ok_or_else(|| format!("no call {}.{}", pallet, name))?;
+
+        let mut encoded = vec![module.index, call_index as u8];
+        let arguments = items(&call.arguments);
+        for argument in arguments {
+            let arg = text(&argument.name);
+            let value = args
+                .get(arg)
+                .ok_or_else(|| format!("{}.{} needs the argument {}", pallet, name, arg))?;
+            self.encode_value(pallet, text(&argument.ty), value, &mut encoded)
+                .map_err(|e| format!("{}.{}({}): {}", pallet, name, arg, e))?;
+        }
+        if let Some(unknown) = args
+            .keys()
+            .find(|key| !arguments.iter().any(|argument| text(&argument.name) == key.as_str()))
+        {
+            return Err(format!("{}.{} has no argument {}", pallet, name, unknown));
+        }
+        Ok(encoded)
+    }
+
+    /// Encode `value` as the type the metadata names `ty`.
+    fn encode_value(
+        &self,
+        pallet: &str,
+        ty: &str,
+        value: &Value,
+        out: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        if let Some(scale) = value.get("scale").and_then(Value::as_str) {
+            out.extend(parse_hex("the SCALE encoding", scale)?);
+            return Ok(());
+        }
+
+        let ty = ty.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        let account = || parse_key(value.as_str().ok_or("expected an account")?, AccountId::from);
+        match classify(pallet, &ty).map_err(|e| {
+            format!("{}, give its SCALE encoding as {{\"scale\": \"0x...\"}}", e)
+        })? {
+            TypeKind::Compact(_) => Compact(parse_integer(value)?).encode_to(out),
+            TypeKind::Option(inner) => {
+                if value.is_null() {
+                    out.push(0);
+                } else {
+                    out.push(1);
+                    self.encode_value(pallet, inner, value, out)?;
+                }
+            }
+            TypeKind::Bytes => {
+                let bytes = match value.as_str() {
+                    Some(hex) if hex.starts_with("0x") => parse_hex("the bytes", hex)?,
+                    Some(text) => text.as_bytes().to_vec(),
+                    None => return Err("expected a hex or text string".into()),
+                };
+                bytes.encode_to(out);
+            }
+            TypeKind::Vec(inner) => {
+                let values = value.as_array().ok_or("expected an array")?;
+                Compact(values.len() as u32).encode_to(out);
+                for value in values {
+                    self.encode_value(pallet, inner, value, out)?;
+                }
+            }
+            TypeKind::Call => out.extend(self.encode_call(value)?),
+            TypeKind::Address => Address::Id(account()?).encode_to(out),
+            TypeKind::AccountId => account()?.encode_to(out),
+            TypeKind::Hash => {
+                let hash = value.as_str().ok_or("expected a hex hash")?;
+                parse_hash("the hash", hash)?.encode_to(out);
+            }
+            TypeKind::Bool => value.as_bool().ok_or("expected a boolean")?.encode_to(out),
+            TypeKind::Integer(width, _) => {
+                let integer = parse_integer(value)?;
+                if width < 16 && integer >> (8 * width) != 0 {
+                    return Err(format!("{} does not fit in {} bytes", integer, width));
+                }
+                out.extend(&integer.to_le_bytes()[..width]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode an encoded call into its JSON description.
+    pub fn decode_call(&self, input: &mut &[u8], formatter: &Formatter) -> Result<Value, String> {
+        let [module_index, call_index] = <[u8; 2]>::decode(input).map_err(|e| e.to_string())?;
+        let module = self.module_by_index(module_index)?;
+        let pallet = text(&module.name);
+        let call = functions::<FunctionMetadata>(&module.calls)
+            .get(call_index as usize)
+            .ok_or_else(|| format!("{} has no call with index {}", pallet, call_index))?;
+
+        let mut asset_id = None;
+        let mut args = serde_json::Map::new();
+        for argument in items(&call.arguments) {
+            let value =
+                self.decode_value(pallet, text(&argument.ty), input, formatter, &mut asset_id)?;
+            args.insert(text(&argument.name).into(), value);
+        }
+        Ok(json!({ "pallet": pallet, "call": text(&call.name), "args": args }))
+    }
+
+    /// Decode an encoded event into its JSON description.
+    pub fn decode_event(&self, input: &mut &[u8], formatter: &Formatter) -> Result<Value, String> {
+        let [module_index, event_index] = <[u8; 2]>::decode(input).map_err(|e| e.to_string())?;
+        let module = self.module_by_index(module_index)?;
+        let pallet = text(&module.name);
+        let event = functions(&module.event)
+            .get(event_index as usize)
+            .ok_or_else(|| format!("{} has no event with index {}", pallet, event_index))?;
+
+        let mut asset_id = None;
+        let args = names(&event.arguments)
+            .into_iter()
+            .map(|ty| self.decode_value(pallet, ty, input, formatter, &mut asset_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(json!({ "pallet": pallet, "event": text(&event.name), "args": args }))
+    }
+
+    /// Decode a value of the type the metadata names `ty`. Remembers the last asset id, so
+    /// that asset balances following it are rendered with the decimals of the asset.
+    fn decode_value(
+        &self,
+        pallet: &str,
+        ty: &str,
+        input: &mut &[u8],
+        formatter: &Formatter,
+        asset_id: &mut Option<u32>,
+    ) -> Result<Value, String> {
+        let ty = ty.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        let error = |e: codec::Error| format!("cannot decode `{}`: {}", ty, e);
+        Ok(match classify(pallet, &ty)? {
+            TypeKind::Compact(inner) => {
+                let integer = Compact::<u128>::decode(input).map_err(error)?.0;
+                let role = match classify(pallet, inner)? {
+                    TypeKind::Integer(_, role) => role,
+                    _ => Role::Plain,
+                };
+                self.integer(integer, role, formatter, asset_id)
+            }
+            TypeKind::Option(inner) => match u8::decode(input).map_err(error)? {
+                0 => Value::Null,
+                1 => self.decode_value(pallet, inner, input, formatter, asset_id)?,
+                _ => return Err(format!("invalid `{}`", ty)),
+            },
+            TypeKind::Bytes => {
+                let bytes = Vec::<u8>::decode(input).map_err(error)?;
+                match String::from_utf8(bytes) {
+                    Ok(text) if !text.chars().any(char::is_control) => Value::String(text),
+                    Ok(text) => Value::String(format!("0x{}", HexDisplay::from(&text.as_bytes()))),
+                    Err(e) => Value::String(format!("0x{}", HexDisplay::from(&e.into_bytes()))),
+                }
+            }
+            TypeKind::Vec(inner) => {
+                let len = Compact::<u32>::decode(input).map_err(error)?.0;
+                (0..len)
+                    .map(|_| self.decode_value(pallet, inner, input, formatter, asset_id))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into()
+            }
+            TypeKind::Call => self.decode_call(input, formatter)?,
+            TypeKind::Address => formatter.address(&Address::decode(input).map_err(error)?),
+            TypeKind::AccountId => formatter.account(&AccountId::decode(input).map_err(error)?),
+            TypeKind::Hash => Value::String(format!("{:?}", H256::decode(input).map_err(error)?)),
+            TypeKind::Bool => bool::decode(input).map_err(error)?.into(),
+            TypeKind::Integer(width, role) => {
+                if input.len() < width {
+                    return Err(format!("cannot decode `{}`: not enough data", ty));
+                }
+                let mut bytes = [0u8; 16];
+                bytes[..width].copy_from_slice(&input[..width]);
+                *input = &input[width..];
+                self.integer(u128::from_le_bytes(bytes), role, formatter, asset_id)
+            }
+        })
+    }
+
+    fn integer(
+        &self,
+        integer: u128,
+        role: Role,
+        formatter: &Formatter,
+        asset_id: &mut Option<u32>,
+    ) -> Value {
+        if role == Role::AssetId {
+            *asset_id = Some(integer as u32);
+        }
+        formatter.integer(integer, role, *asset_id)
+    }
+}
@@ -0,0 +1,283 @@
+//! Chain metrics for the Prometheus endpoint, next to the generic ones of substrate.
+//!
+//! Everything is read from the client at finalized heads. Extrinsic counts and fees are counted
+//! for every finalized block; the gauges of the state (fee multiplier, assets, treasury balance,
+//! staking era) follow the last finalized block. Balances are in the smallest unit of the token.
+
+use crate::chain::{
+    account, actual_fee, block_events, era_nonce_tip, extrinsics, fee_multiplier, outcome, storage,
+    storage_key,
+};
+use crate::metadata::NativeMetadata;
+use crate::service::FullClient;
+use codec::Encode;
+use frame_support::traits::Get;
+use futures::StreamExt;
+use node_primitives::{Balance, Block, BlockNumber, Hash};
+use node_runtime::{Multiplier, SessionsPerEra, TreasuryPalletId, FEE_SPLIT};
+use prometheus_endpoint::{
+    register, Counter, CounterVec, Gauge, GaugeVec, Opts, PrometheusError, Registry, F64, U64,
+};
+use sc_client_api::{BlockBackend, BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::twox_64, storage::StorageKey};
+use sp_runtime::{
+    generic::BlockId,
+    traits::{AccountIdConversion, Header as _},
+    FixedPointNumber,
+};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Split `amount` the way `Imbalance::ration` does.
+fn ration(amount: Balance, (first, second): (u32, u32)) -> (Balance, Balance) {
+    let first = amount.saturating_mul(first.into()) / Balance::from(first + second);
+    (first, amount - first)
+}
+
+/// The metrics, with the client they are read from.
+pub struct ChainMetrics {
+    client: Arc<FullClient>,
+    metadata: NativeMetadata,
+    last_finalized: BlockNumber,
+    block_extrinsics: GaugeVec<U64>,
+    extrinsics: CounterVec<U64>,
+    fees: CounterVec<F64>,
+    failed_extrinsics: Counter<U64>,
+    fee_multiplier: Gauge<F64>,
+    assets: Gauge<U64>,
+    asset_holders: GaugeVec<U64>,
+    treasury_balance: Gauge<F64>,
+    active_era: Gauge<U64>,
+    era_progress: Gauge<F64>,
+}
+
+impl ChainMetrics {
+    /// Register the metrics with `registry`.
+    pub fn register(registry: &Registry, client: Arc<FullClient>) -> Result<Self, String> {
+        let metrics = || -> Result<_, PrometheusError> {
+            Ok((
+                register(
+                    GaugeVec::new(
+                        Opts::new(
+                            "coinnet_block_extrinsics",
+                            "Extrinsics in the last finalized block per pallet",
+                        ),
+                        &["pallet"],
+                    )?,
+                    registry,
+                )?,
+                register(
+                    CounterVec::new(
+                        Opts::new(
+                            "coinnet_extrinsics_total",
+                            "Extrinsics in finalized blocks per pallet",
+                        ),
+                        &["pallet"],
+                    )?,
+                    registry,
+                )?,
+                register(
+                    CounterVec::new(
+                        Opts::new(
+                            "coinnet_fees_total",
+                            "Fees and tips paid in finalized blocks, by who they went to",
+                        ),
+                        &["recipient"],
+                    )?,
+                    registry,
+                )?,
+                register(
+                    Counter::new(
+                        "coinnet_failed_extrinsics_total",
+                        "Extrinsics in finalized blocks that failed",
+                    )?,
+                    registry,
+                )?,
+                register(
+                    Gauge::new(
+                        "coinnet_fee_multiplier",
+                        "The multiplier of weight fees of TransactionPayment",
+                    )?,
+                    registry,
+                )?,
+                register(Gauge::new("coinnet_assets", "Assets of the Assets pallet")?, registry)?,
+                register(
+                    GaugeVec::new(
+                        Opts::new("coinnet_asset_holders", "Accounts holding an asset"),
+                        &["asset"],
+                    )?,
+                    registry,
+                )?,
+                register(
+                    Gauge::new("coinnet_treasury_balance", "The free balance of the treasury")?,
+                    registry,
+                )?,
+                register(Gauge::new("coinnet_active_era", "The active staking era")?, registry)?,
+                register(
+                    Gauge::new(
+                        "coinnet_era_progress",
+                        "The share of the sessions of the active era that have started",
+                    )?,
+                    registry,
+                )?,
+            ))
+        };
+        let (
+            block_extrinsics,
+            extrinsics,
+            fees,
+            failed_extrinsics,
+            fee_multiplier,
+            assets,
+            asset_holders,
+            treasury_balance,
+            active_era,
+            era_progress,
+        ) = metrics().map_err(|e| format!("cannot register the chain metrics: {}", e))?;
+
+        Ok(ChainMetrics {
+            last_finalized: client.info().finalized_number,
+            client,
+            metadata: NativeMetadata::new()?,
+            block_extrinsics,
+            extrinsics,
+            fees,
+            failed_extrinsics,
+            fee_multiplier,
+            assets,
+            asset_holders,
+            treasury_balance,
+            active_era,
+            era_progress,
+        })
+    }
+
+    /// Update the metrics at every finalized head until the node shuts down.
+    pub async fn run(mut self) {
+        let mut finalized = self.client.finality_notification_stream();
+        while let Some(notification) = finalized.next().await {
+            if let Err(e) = self.finalized(*notification.header.number(), notification.hash) {
+                log::warn!(target: "metrics", "Cannot update the chain metrics: {}", e);
+            }
+        }
+    }
+
+    fn finalized(&mut self, number: BlockNumber, hash: Hash) -> Result<(), String> {
+        // a notification may finalize several blocks at once.
+        while self.last_finalized < number {
+            let next = self.last_finalized + 1;
+            let hash = self
+                .client
+                .hash(next)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("no finalized block #{}", next))?;
+            self.last_finalized = next;
+            self.count(hash, next == number)?;
+        }
+        self.update_state(&BlockId::Hash(hash))
+    }
+
+    /// Count the extrinsics and fees of a block.
+    fn count(&self, hash: Hash, last: bool) -> Result<(), String> {
+        let block = self
+            .client
+            .block(&BlockId::Hash(hash))
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("no block {:?} in the database", hash))?
+            .block;
+        let parent = BlockId::Hash(*block.header.parent_hash());
+        let events = block_events(&self.client, &BlockId::Hash(hash))?;
+
+        let mut per_pallet = BTreeMap::<&str, u64>::new();
+        let (mut to_treasury, mut to_author) = (0, 0);
+        for (index, (extrinsic, opaque)) in
+            extrinsics(&block)?.iter().zip(&block.extrinsics).enumerate()
+        {
+            let pallet = self
+                .metadata
+                .call_name(&extrinsic.function.encode())
+                .map_or("unknown", |(pallet, _)| pallet);
+            *per_pallet.entry(pallet).or_default() += 1;
+
+            let (success, info) = match outcome(&events, index) {
+                Some(outcome) => outcome,
+                None => continue,
+            };
+            if !success {
+                self.failed_extrinsics.inc();
+            }
+            if let Some((_, _, extra)) = &extrinsic.signature {
+                let (_, _, tip) = era_nonce_tip(extra);
+                let fee = actual_fee(&self.client, &parent, opaque, tip, &info)?;
+                let fee = ration(fee - tip, FEE_SPLIT);
+                let tip = ration(tip, FEE_SPLIT);
+                to_treasury += fee.0 + tip.0;
+                to_author += fee.1 + tip.1;
+            }
+        }
+
+        if last {
+            self.block_extrinsics.reset();
+        }
+        for (pallet, count) in per_pallet {
+            self.extrinsics.with_label_values(&[pallet]).inc_by(count);
+            if last {
+                self.block_extrinsics.with_label_values(&[pallet]).set(count);
+            }
+        }
+        self.fees.with_label_values(&["treasury"]).inc_by(to_treasury as f64);
+        self.fees.with_label_values(&["author"]).inc_by(to_author as f64);
+        Ok(())
+    }
+
+    /// Update the gauges of the state at a finalized head.
+    fn update_state(&self, at: &BlockId<Block>) -> Result<(), String> {
+        let multiplier = fee_multiplier(&self.client, at)?;
+        self.fee_multiplier.set(multiplier.into_inner() as f64 / Multiplier::DIV as f64);
+
+        let assets = self.keys(at, storage_key(b"Assets", b"Asset"))?;
+        self.assets.set(assets.len() as u64);
+        // the keys of `Assets::Account` are the prefix, the hash of the asset id, the asset id
+        // and the hashed account.
+        let prefix = storage_key(b"Assets", b"Account");
+        let id_at = prefix.len() + 16;
+        let mut holders = BTreeMap::<u32, u64>::new();
+        for key in self.keys(at, prefix)? {
+            if let Some(id) = key.0.get(id_at..id_at + 4) {
+                let id = u32::from_le_bytes([id[0], id[1], id[2], id[3]]);
+                *holders.entry(id).or_default() += 1;
+            }
+        }
+        self.asset_holders.reset();
+        for (id, holders) in holders {
+            self.asset_holders.with_label_values(&[&id.to_string()]).set(holders);
+        }
+
+        let treasury = account(&self.client, at, &TreasuryPalletId::get().into_account())?;
+        self.treasury_balance.set(treasury.data.free as f64);
+
+        let active_era = storage::<pallet_staking::ActiveEraInfo>(
+            &self.client,
+            at,
+            storage_key(b"Staking", b"ActiveEra"),
+        )?;
+        if let Some(active_era) = active_era {
+            self.active_era.set(active_era.index.into());
+            let mut key = storage_key(b"Staking", b"ErasStartSessionIndex");
+            key.extend(twox_64(&active_era.index.encode()));
+            key.extend(active_era.index.encode());
+            let start = storage::<u32>(&self.client, at, key)?;
+            let current =
+                storage::<u32>(&self.client, at, storage_key(b"Session", b"CurrentIndex"))?;
+            if let (Some(start), Some(current)) = (start, current) {
+                let started = current.saturating_sub(start) + 1;
+                self.era_progress.set(started as f64 / SessionsPerEra::get() as f64);
+            }
+        }
+        Ok(())
+    }
+
+    fn keys(&self, at: &BlockId<Block>, prefix: Vec<u8>) -> Result<Vec<StorageKey>, String> {
+        self.client.storage_keys(at, &StorageKey(prefix)).map_err(|e| e.to_string())
+    }
+}
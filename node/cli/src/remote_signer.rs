@@ -0,0 +1,677 @@
+//! A keystore on a separate signing host (`--keystore-uri tcp://<host>:<port>`), and the
+//! reference signer serving one (`coinnet-remote-signer`).
+//!
+//! The node sends the signer one JSON request per line over TCP: the public keys of a key type, a
+//! signature of a message or a VRF signature of a transcript. The signer only answers nodes that
+//! know the secret in `COINNET_SIGNER_SECRET`: it opens each connection with a random challenge,
+//! and every line after it, in either direction, is prefixed with
+//! `blake2_256(secret ++ challenge ++ direction ++ sequence number ++ line)`, which also keeps
+//! lines from being replayed or reordered. The protocol does not encrypt anything: run it on a
+//! private network.
+//!
+//! Keys are generated and inserted on the signer, `author_rotateKeys` and `author_insertKey` fail
+//! on a node using one. The node fetches the public keys of the session key types when it
+//! connects and again every [`KEYS_REFRESH`] in the background, and answers which keys it has
+//! from them: only signatures wait for the signer. The async keystore sends them from blocking
+//! tasks, so that waiting for the signer does not hold up the other tasks of the node.
+
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sc_keystore::LocalKeystore;
+use serde::{Deserialize, Serialize};
+use sp_consensus_babe::{VRFOutput, VRFProof};
+use sp_core::{
+    bytes::{from_hex, to_hex},
+    crypto::{key_types, CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+    ecdsa, ed25519,
+    hashing::blake2_256,
+    sr25519,
+    traits::SpawnNamed,
+    Bytes,
+};
+use sp_keystore::{
+    vrf::{VRFSignature, VRFTranscriptData, VRFTranscriptValue},
+    CryptoStore, Error as KeystoreError, SyncCryptoStore,
+};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+/// The environment variable holding the secret the node and the signer share.
+pub const SECRET_VAR: &str = "COINNET_SIGNER_SECRET";
+
+/// How long the node waits for the signer.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often the node fetches the public keys of the signer again.
+pub const KEYS_REFRESH: Duration = Duration::from_secs(60);
+
+/// The key types of the session keys, whose public keys are fetched on connecting.
+const SESSION_KEY_TYPES: [KeyTypeId; 4] =
+    [key_types::BABE, key_types::GRANDPA, key_types::IM_ONLINE, key_types::AUTHORITY_DISCOVERY];
+
+/// The direction of a line, in its MAC.
+const TO_SIGNER: u8 = 0;
+const TO_NODE: u8 = 1;
+
+/// A request to the signer. Key types and crypto types are their four characters.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "camelCase")]
+enum Request {
+    /// The public keys of a key type.
+    #[serde(rename_all = "camelCase")]
+    Keys { key_type: String },
+    /// Sign a message with a key.
+    #[serde(rename_all = "camelCase")]
+    Sign { key_type: String, crypto: String, public: Bytes, message: Bytes },
+    /// Sign a VRF transcript with an sr25519 key.
+    #[serde(rename_all = "camelCase")]
+    VrfSign {
+        key_type: String,
+        public: Bytes,
+        label: String,
+        items: Vec<(String, TranscriptValue)>,
+    },
+}
+
+/// A value of a VRF transcript.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TranscriptValue {
+    Bytes(Bytes),
+    U64(u64),
+}
+
+/// The answer of the signer.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Response {
+    /// The crypto types and public keys of a key type.
+    Keys(Vec<(String, Bytes)>),
+    /// A signature.
+    Signature(Bytes),
+    /// The SCALE-encoded output and proof of a VRF signature.
+    VrfSignature { output: Bytes, proof: Bytes },
+    /// Why the signer refused the request.
+    Error(String),
+}
+
+/// Read the shared secret from the environment.
+fn secret() -> Result<Vec<u8>, String> {
+    match std::env::var(SECRET_VAR) {
+        Ok(secret) if !secret.is_empty() => Ok(secret.into_bytes()),
+        _ => Err(format!("the remote signer needs a secret in {}", SECRET_VAR)),
+    }
+}
+
+fn name(id: [u8; 4]) -> String {
+    String::from_utf8_lossy(&id).into_owned()
+}
+
+fn four_chars(name: &str) -> Result<[u8; 4], String> {
+    <[u8; 4]>::try_from(name.as_bytes()).map_err(|_| format!("invalid type {}", name))
+}
+
+/// One end of an authenticated connection.
+struct Channel {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    secret: Vec<u8>,
+    challenge: [u8; 32],
+    sent: u64,
+    received: u64,
+}
+
+impl Channel {
+    fn mac(&self, direction: u8, sequence: u64, line: &str) -> [u8; 32] {
+        let mut data = self.secret.clone();
+        data.extend(&self.challenge);
+        data.push(direction);
+        data.extend(&sequence.to_le_bytes());
+        data.extend(line.as_bytes());
+        blake2_256(&data)
+    }
+
+    fn send<T: Serialize>(&mut self, direction: u8, message: &T) -> Result<(), String> {
+        let line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        let mac = self.mac(direction, self.sent, &line);
+        self.sent += 1;
+        writeln!(self.writer, "{} {}", to_hex(&mac, false), line).map_err(|e| e.to_string())
+    }
+
+    fn receive<T: for<'de> Deserialize<'de>>(&mut self, direction: u8) -> Result<T, String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Err("connection closed".into());
+        }
+        let (mac, line) = line.trim_end().split_at(line.find(' ').unwrap_or(0));
+        let expected = self.mac(direction, self.received, line.trim_start());
+        let mac = from_hex(mac).unwrap_or_default();
+        // compare all bytes, so that the time taken does not tell how many were right.
+        let equal = mac.len() == expected.len()
+            && mac.iter().zip(expected.iter()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0;
+        if !equal {
+            return Err("the line is not authenticated".into());
+        }
+        self.received += 1;
+        serde_json::from_str(line.trim_start()).map_err(|e| format!("invalid line: {}", e))
+    }
+}
+
+/// The connection of the node to the signer.
+struct Connection {
+    address: String,
+    secret: Vec<u8>,
+    channel: Mutex<Option<Channel>>,
+}
+
+/// The public keys of the signer by key type, as last fetched.
+struct KeyCache {
+    keys: HashMap<KeyTypeId, Vec<CryptoTypePublicPair>>,
+    fetched: Instant,
+    refreshing: bool,
+}
+
+/// A keystore forwarding signing to a remote signer.
+#[derive(Clone)]
+pub struct RemoteKeystore {
+    connection: Arc<Connection>,
+    spawner: Arc<dyn SpawnNamed>,
+    cache: Arc<Mutex<KeyCache>>,
+}
+
+impl RemoteKeystore {
+    /// Connect to the signer at `uri` (`tcp://<host>:<port>`), with the secret in
+    /// `COINNET_SIGNER_SECRET`. Requests of the async keystore are sent from tasks of `spawner`.
+    pub fn connect(uri: &str, spawner: Arc<dyn SpawnNamed>) -> Result<Self, String> {
+        let address = uri.strip_prefix("tcp://").ok_or_else(|| {
+            format!("unsupported keystore URI {}, expected tcp://<host>:<port>", uri)
+        })?;
+        let connection =
+            Connection { address: address.into(), secret: secret()?, channel: Mutex::new(None) };
+        let keystore = RemoteKeystore {
+            connection: Arc::new(connection),
+            spawner,
+            cache: Arc::new(Mutex::new(KeyCache {
+                keys: HashMap::new(),
+                fetched: Instant::now(),
+                refreshing: false,
+            })),
+        };
+        // fail now rather than when the first slot comes.
+        for id in SESSION_KEY_TYPES.iter() {
+            let keys = keystore.fetch_keys(*id).map_err(|e| e.to_string())?;
+            keystore.cache.lock().keys.insert(*id, keys);
+        }
+        Ok(keystore)
+    }
+
+    /// Ask the signer for its public keys of a key type.
+    fn fetch_keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        match self.call(&Request::Keys { key_type: name(id.0) }).map_err(KeystoreError::Other)? {
+            Response::Keys(keys) => keys
+                .into_iter()
+                .map(|(crypto, public)| {
+                    Ok(CryptoTypePublicPair(CryptoTypeId(four_chars(&crypto)?), public.0))
+                })
+                .collect::<Result<_, String>>()
+                .map_err(KeystoreError::Other),
+            response => Err(KeystoreError::Other(format!("unexpected answer {:?}", response))),
+        }
+    }
+
+    /// Fetch the keys of every cached key type again. The keys of a key type the signer does not
+    /// answer for are kept as they were.
+    fn refresh(&self) {
+        let ids = self.cache.lock().keys.keys().copied().collect::<Vec<_>>();
+        let fetched = ids
+            .into_iter()
+            .filter_map(|id| self.fetch_keys(id).ok().map(|keys| (id, keys)))
+            .collect::<Vec<_>>();
+        let mut cache = self.cache.lock();
+        cache.keys.extend(fetched);
+        cache.fetched = Instant::now();
+        cache.refreshing = false;
+    }
+
+    /// The public keys of a key type from the cache, which is refreshed in a blocking task once
+    /// it is older than [`KEYS_REFRESH`]. Key types that are not cached yet are fetched now.
+    fn cached_keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        let mut cache = self.cache.lock();
+        if cache.fetched.elapsed() >= KEYS_REFRESH && !cache.refreshing {
+            cache.refreshing = true;
+            let keystore = self.clone();
+            self.spawner
+                .spawn_blocking("remote-signer-keys", Box::pin(async move { keystore.refresh() }));
+        }
+        if let Some(keys) = cache.keys.get(&id) {
+            return Ok(keys.clone());
+        }
+        drop(cache);
+
+        let keys = self.fetch_keys(id)?;
+        self.cache.lock().keys.insert(id, keys.clone());
+        Ok(keys)
+    }
+
+    /// Run `f` on the keystore in a blocking task, none if the task was cancelled.
+    async fn blocking<R, F>(&self, f: F) -> Option<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&RemoteKeystore) -> R + Send + 'static,
+    {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        let keystore = self.clone();
+        self.spawner.spawn_blocking(
+            "remote-signer",
+            Box::pin(async move {
+                let _ = sender.send(f(&keystore));
+            }),
+        );
+        receiver.await.ok()
+    }
+
+    fn call(&self, request: &Request) -> Result<Response, String> {
+        self.connection.call(request)
+    }
+
+    fn public_keys(&self, id: KeyTypeId, crypto: CryptoTypeId) -> Vec<Vec<u8>> {
+        self.cached_keys(id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|key| key.0 == crypto)
+            .map(|key| key.1)
+            .collect()
+    }
+}
+
+impl Connection {
+    fn open(&self) -> Result<Channel, String> {
+        let stream = TcpStream::connect(&self.address).map_err(|e| e.to_string())?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+        let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+        let mut challenge = String::new();
+        reader.read_line(&mut challenge).map_err(|e| e.to_string())?;
+        let challenge = from_hex(challenge.trim_end())
+            .ok()
+            .and_then(|challenge| <[u8; 32]>::try_from(&challenge[..]).ok())
+            .ok_or("invalid challenge")?;
+        Ok(Channel {
+            reader,
+            writer: stream,
+            secret: self.secret.clone(),
+            challenge,
+            sent: 0,
+            received: 0,
+        })
+    }
+
+    /// Send a request, on a new connection if the last one broke.
+    fn call(&self, request: &Request) -> Result<Response, String> {
+        let mut channel = self.channel.lock();
+        let mut attempt = || -> Result<Response, String> {
+            if channel.is_none() {
+                *channel = Some(self.open()?);
+            }
+            let open = channel.as_mut().expect("opened above; qed");
+            let response = open.send(TO_SIGNER, request).and_then(|_| open.receive(TO_NODE));
+            if response.is_err() {
+                *channel = None;
+            }
+            response
+        };
+        let response = attempt().or_else(|_| attempt()).map_err(|e| {
+            log::warn!(target: "remote-signer", "Request to {} failed: {}", self.address, e);
+            format!("remote signer {}: {}", self.address, e)
+        })?;
+        match response {
+            Response::Error(e) => Err(e),
+            response => Ok(response),
+        }
+    }
+}
+
+fn not_generated() -> KeystoreError {
+    KeystoreError::Other("keys are generated on the remote signer".into())
+}
+
+fn cancelled() -> KeystoreError {
+    KeystoreError::Other("the request to the remote signer was cancelled".into())
+}
+
+#[async_trait]
+impl CryptoStore for RemoteKeystore {
+    async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+        SyncCryptoStore::sr25519_public_keys(self, id)
+    }
+
+    async fn sr25519_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<sr25519::Public, KeystoreError> {
+        SyncCryptoStore::sr25519_generate_new(self, id, seed)
+    }
+
+    async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+        SyncCryptoStore::ed25519_public_keys(self, id)
+    }
+
+    async fn ed25519_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ed25519::Public, KeystoreError> {
+        SyncCryptoStore::ed25519_generate_new(self, id, seed)
+    }
+
+    async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+        SyncCryptoStore::ecdsa_public_keys(self, id)
+    }
+
+    async fn ecdsa_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ecdsa::Public, KeystoreError> {
+        SyncCryptoStore::ecdsa_generate_new(self, id, seed)
+    }
+
+    async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+        SyncCryptoStore::insert_unknown(self, id, suri, public)
+    }
+
+    async fn supported_keys(
+        &self,
+        id: KeyTypeId,
+        keys: Vec<CryptoTypePublicPair>,
+    ) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        SyncCryptoStore::supported_keys(self, id, keys)
+    }
+
+    async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        SyncCryptoStore::keys(self, id)
+    }
+
+    async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+        SyncCryptoStore::has_keys(self, public_keys)
+    }
+
+    async fn sign_with(
+        &self,
+        id: KeyTypeId,
+        key: &CryptoTypePublicPair,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, KeystoreError> {
+        let (key, msg) = (key.clone(), msg.to_vec());
+        self.blocking(move |keystore| SyncCryptoStore::sign_with(keystore, id, &key, &msg))
+            .await
+            .unwrap_or_else(|| Err(cancelled()))
+    }
+
+    async fn sr25519_vrf_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &sr25519::Public,
+        transcript_data: VRFTranscriptData,
+    ) -> Result<VRFSignature, KeystoreError> {
+        let public = *public;
+        self.blocking(move |keystore| {
+            SyncCryptoStore::sr25519_vrf_sign(keystore, key_type, &public, transcript_data)
+        })
+        .await
+        .unwrap_or_else(|| Err(cancelled()))
+    }
+}
+
+impl SyncCryptoStore for RemoteKeystore {
+    fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+        let keys = self.public_keys(id, sr25519::CRYPTO_ID);
+        keys.iter().filter_map(|key| sr25519::Public::try_from(&key[..]).ok()).collect()
+    }
+
+    fn sr25519_generate_new(
+        &self,
+        _id: KeyTypeId,
+        _seed: Option<&str>,
+    ) -> Result<sr25519::Public, KeystoreError> {
+        Err(not_generated())
+    }
+
+    fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+        let keys = self.public_keys(id, ed25519::CRYPTO_ID);
+        keys.iter().filter_map(|key| ed25519::Public::try_from(&key[..]).ok()).collect()
+    }
+
+    fn ed25519_generate_new(
+        &self,
+        _id: KeyTypeId,
+        _seed: Option<&str>,
+    ) -> Result<ed25519::Public, KeystoreError> {
+        Err(not_generated())
+    }
+
+    fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+        let keys = self.public_keys(id, ecdsa::CRYPTO_ID);
+        keys.iter().filter_map(|key| ecdsa::Public::try_from(&key[..]).ok()).collect()
+    }
+
+    fn ecdsa_generate_new(
+        &self,
+        _id: KeyTypeId,
+        _seed: Option<&str>,
+    ) -> Result<ecdsa::Public, KeystoreError> {
+        Err(not_generated())
+    }
+
+    fn insert_unknown(&self, _id: KeyTypeId, _suri: &str, _public: &[u8]) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn supported_keys(
+        &self,
+        id: KeyTypeId,
+        keys: Vec<CryptoTypePublicPair>,
+    ) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        let all = SyncCryptoStore::keys(self, id)?;
+        Ok(keys.into_iter().filter(|key| all.contains(key)).collect())
+    }
+
+    fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        self.cached_keys(id)
+    }
+
+    fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+        public_keys.iter().all(|(public, id)| {
+            SyncCryptoStore::keys(self, *id)
+                .map_or(false, |keys| keys.iter().any(|key| &key.1 == public))
+        })
+    }
+
+    fn sign_with(
+        &self,
+        id: KeyTypeId,
+        key: &CryptoTypePublicPair,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, KeystoreError> {
+        let request = Request::Sign {
+            key_type: name(id.0),
+            crypto: name(key.0 .0),
+            public: key.1.clone().into(),
+            message: msg.to_vec().into(),
+        };
+        match self.call(&request).map_err(KeystoreError::Other)? {
+            Response::Signature(signature) => Ok(signature.0),
+            response => Err(KeystoreError::Other(format!("unexpected answer {:?}", response))),
+        }
+    }
+
+    fn sr25519_vrf_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &sr25519::Public,
+        transcript_data: VRFTranscriptData,
+    ) -> Result<VRFSignature, KeystoreError> {
+        let items = transcript_data
+            .items
+            .into_iter()
+            .map(|(label, value)| {
+                let value = match value {
+                    VRFTranscriptValue::Bytes(bytes) => TranscriptValue::Bytes(bytes.into()),
+                    VRFTranscriptValue::U64(value) => TranscriptValue::U64(value),
+                };
+                (label.to_string(), value)
+            })
+            .collect();
+        let request = Request::VrfSign {
+            key_type: name(key_type.0),
+            public: public.to_vec().into(),
+            label: String::from_utf8_lossy(transcript_data.label).into_owned(),
+            items,
+        };
+        match self.call(&request).map_err(KeystoreError::Other)? {
+            Response::VrfSignature { output, proof } => {
+                let output = VRFOutput::decode(&mut &output[..])
+                    .map_err(|e| KeystoreError::Other(format!("invalid VRF output: {}", e)))?;
+                let proof = VRFProof::decode(&mut &proof[..])
+                    .map_err(|e| KeystoreError::Other(format!("invalid VRF proof: {}", e)))?;
+                Ok(VRFSignature { output: output.0, proof: proof.0 })
+            }
+            response => Err(KeystoreError::Other(format!("unexpected answer {:?}", response))),
+        }
+    }
+}
+
+/// The labels of VRF transcripts, which the keystore wants static. Each one is leaked once.
+static LABELS: Mutex<Vec<&'static str>> = parking_lot::const_mutex(Vec::new());
+
+fn label(label: String) -> &'static str {
+    let mut labels = LABELS.lock();
+    match labels.iter().find(|known| **known == label) {
+        Some(known) => known,
+        None => {
+            let leaked: &'static str = Box::leak(label.into_boxed_str());
+            labels.push(leaked);
+            leaked
+        }
+    }
+}
+
+/// Answer a request with the keys of `keystore`.
+fn answer(keystore: &LocalKeystore, request: Request) -> Result<Response, String> {
+    let key_type = |key_type: &str| four_chars(key_type).map(KeyTypeId);
+    match request {
+        Request::Keys { key_type: id } => {
+            let keys =
+                SyncCryptoStore::keys(keystore, key_type(&id)?).map_err(|e| e.to_string())?;
+            Ok(Response::Keys(keys.into_iter().map(|key| (name(key.0 .0), key.1.into())).collect()))
+        }
+        Request::Sign { key_type: id, crypto, public, message } => {
+            let key = CryptoTypePublicPair(CryptoTypeId(four_chars(&crypto)?), public.0);
+            SyncCryptoStore::sign_with(keystore, key_type(&id)?, &key, &message)
+                .map(|signature| Response::Signature(signature.into()))
+                .map_err(|e| e.to_string())
+        }
+        Request::VrfSign { key_type: id, public, label: transcript_label, items } => {
+            let public = sr25519::Public::try_from(&public[..])
+                .map_err(|_| "invalid sr25519 public key".to_string())?;
+            let transcript_data = VRFTranscriptData {
+                label: label(transcript_label).as_bytes(),
+                items: items
+                    .into_iter()
+                    .map(|(item, value)| {
+                        let value = match value {
+                            TranscriptValue::Bytes(bytes) => VRFTranscriptValue::Bytes(bytes.0),
+                            TranscriptValue::U64(value) => VRFTranscriptValue::U64(value),
+                        };
+                        (label(item), value)
+                    })
+                    .collect(),
+            };
+            let signature = SyncCryptoStore::sr25519_vrf_sign(
+                keystore,
+                key_type(&id)?,
+                &public,
+                transcript_data,
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(Response::VrfSignature {
+                output: VRFOutput(signature.output).encode().into(),
+                proof: VRFProof(signature.proof).encode().into(),
+            })
+        }
+    }
+}
+
+/// Serve a node on a connection until it closes or sends a line that is not authenticated.
+fn serve(stream: TcpStream, keystore: &LocalKeystore, secret: Vec<u8>) -> Result<(), String> {
+    let challenge: [u8; 32] = rand::random();
+    let mut writer = stream.try_clone().map_err(|e| e.to_string())?;
+    writeln!(writer, "{}", to_hex(&challenge, false)).map_err(|e| e.to_string())?;
+    let mut channel =
+        Channel { reader: BufReader::new(stream), writer, secret, challenge, sent: 0, received: 0 };
+    loop {
+        let request = channel.receive::<Request>(TO_SIGNER)?;
+        log::debug!(target: "remote-signer", "{:?}", request);
+        let response = answer(keystore, request).unwrap_or_else(Response::Error);
+        channel.send(TO_NODE, &response)?;
+    }
+}
+
+/// The reference remote signer: serves the keys of a local keystore to nodes started with
+/// `--keystore-uri tcp://<listen>`, sharing the secret in `COINNET_SIGNER_SECRET`.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "coinnet-remote-signer")]
+pub struct RemoteSignerCmd {
+    /// The address to listen on.
+    #[structopt(long, default_value = "127.0.0.1:9955")]
+    pub listen: String,
+
+    /// The keystore to sign with, as `key insert --keystore-path` fills it.
+    #[structopt(long, parse(from_os_str))]
+    pub keystore_path: PathBuf,
+}
+
+impl RemoteSignerCmd {
+    /// Run the signer.
+    pub fn run(&self) -> Result<(), String> {
+        let secret = secret()?;
+        let keystore = Arc::new(
+            LocalKeystore::open(&self.keystore_path, None)
+                .map_err(|e| format!("cannot open the keystore: {}", e))?,
+        );
+        let listener = TcpListener::bind(&self.listen)
+            .map_err(|e| format!("cannot listen on {}: {}", self.listen, e))?;
+        log::info!(target: "remote-signer", "Listening on {}", self.listen);
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            let peer = stream.peer_addr().map(|peer| peer.to_string()).unwrap_or_default();
+            let (keystore, secret) = (keystore.clone(), secret.clone());
+            std::thread::spawn(move || {
+                log::info!(target: "remote-signer", "Node connected from {}", peer);
+                if let Err(e) = serve(stream, &keystore, secret) {
+                    log::info!(target: "remote-signer", "Node {} disconnected: {}", peer, e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Parse the command line and run the reference remote signer.
+pub fn run_remote_signer() -> sc_cli::Result<()> {
+    let _ = env_logger::try_init();
+    RemoteSignerCmd::from_args().run().map_err(sc_cli::Error::Input)
+}
@@ -0,0 +1,305 @@
+//! `balances_accountAt` and `balances_subscribeAccountRange`: the state of an account in the past.
+//!
+//! A point in the past is a block number or a timestamp in milliseconds, which stands for the
+//! last block `Timestamp` set a time up to it in, found by binary search over the finalized chain.
+//! Only finalized blocks are looked at, and the state of old blocks is only there on archive
+//! nodes (`--pruning archive`).
+
+use super::runtime_error;
+use crate::chain::{account, asset_account_key, storage, timestamp, AccountInfo, AssetBalance};
+use crate::generate_spec::parse_key;
+use crate::service::FullClient;
+use futures::{FutureExt, SinkExt};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use node_primitives::{AccountId, BlockNumber, Hash, Index, Moment};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::generic::BlockId;
+use std::sync::Arc;
+
+/// The most states a range subscription sends.
+const MAX_POINTS: u64 = 10_000;
+
+/// A point in the past.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum At {
+    /// A block number.
+    Number(BlockNumber),
+    /// A time in milliseconds since the Unix epoch.
+    Timestamp(Moment),
+}
+
+/// The balance of an account in an asset of `pallet_assets`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetState {
+    /// The asset.
+    pub id: u32,
+    /// The balance of the account.
+    pub balance: NumberOrHex,
+    /// Whether the account is frozen in the asset.
+    pub is_frozen: bool,
+}
+
+/// The state of an account at a block.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountState {
+    /// The number of the block.
+    pub block_number: BlockNumber,
+    /// The hash of the block.
+    pub block_hash: Hash,
+    /// The time of the block in milliseconds since the Unix epoch, zero for the genesis block.
+    pub timestamp: Moment,
+    /// The number of transactions the account has sent.
+    pub nonce: Index,
+    /// The free balance of the native token.
+    pub free: NumberOrHex,
+    /// The reserved balance of the native token.
+    pub reserved: NumberOrHex,
+    /// The free balance that cannot be used for anything but fees.
+    pub misc_frozen: NumberOrHex,
+    /// The free balance that cannot be used for fees.
+    pub fee_frozen: NumberOrHex,
+    /// The balance in the asset asked for, if any.
+    pub asset: Option<AssetState>,
+}
+
+/// Historical balance RPC methods.
+#[rpc]
+pub trait BalanceHistoryApi {
+    /// RPC metadata
+    type Metadata;
+
+    /// The state of an account, and its balance in `asset` if given, at a point in the past.
+    #[rpc(name = "balances_accountAt")]
+    fn account_at(&self, account: String, at: At, asset: Option<u32>) -> Result<AccountState>;
+
+    /// The states of an account from one point to another, both block numbers or both
+    /// timestamps, every `step` blocks or milliseconds. The subscription ends after the last
+    /// state, or after the first error.
+    #[pubsub(
+        subscription = "balances_accountRange",
+        subscribe,
+        name = "balances_subscribeAccountRange"
+    )]
+    fn subscribe_account_range(
+        &self,
+        metadata: Self::Metadata,
+        subscriber: Subscriber<AccountState>,
+        account: String,
+        from: At,
+        to: At,
+        step: u64,
+        asset: Option<u32>,
+    );
+
+    /// Cancel a range subscription.
+    #[pubsub(
+        subscription = "balances_accountRange",
+        unsubscribe,
+        name = "balances_unsubscribeAccountRange"
+    )]
+    fn unsubscribe_account_range(
+        &self,
+        metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+}
+
+/// Implements the [`BalanceHistoryApi`] RPC trait with the state of a full node.
+pub struct BalanceHistory {
+    client: Arc<FullClient>,
+    manager: SubscriptionManager,
+}
+
+impl BalanceHistory {
+    /// Create a new `BalanceHistory` reading the state of `client`.
+    pub fn new(client: Arc<FullClient>, manager: SubscriptionManager) -> Self {
+        BalanceHistory { client, manager }
+    }
+}
+
+fn pruned_error(number: BlockNumber, e: String) -> Error {
+    Error {
+        code: ErrorCode::ServerError(2),
+        message: format!(
+            "The state of block #{} is not available. Historical state needs an archive node \
+             (--pruning archive).",
+            number
+        ),
+        data: Some(e.into()),
+    }
+}
+
+/// The hash of a finalized block.
+fn finalized_hash(client: &FullClient, number: BlockNumber) -> Result<Hash> {
+    let finalized = client.info().finalized_number;
+    if number > finalized {
+        return Err(Error::invalid_params(format!(
+            "block #{} is not finalized yet, the last finalized block is #{}",
+            number, finalized
+        )));
+    }
+    client
+        .hash(number)
+        .map_err(|e| runtime_error("Unable to read the block.", e))?
+        .ok_or_else(|| runtime_error("Unable to read the block.", number))
+}
+
+/// The time of a finalized block.
+fn block_timestamp(client: &FullClient, number: BlockNumber) -> Result<Moment> {
+    let hash = finalized_hash(client, number)?;
+    timestamp(client, &BlockId::Hash(hash)).map_err(|e| pruned_error(number, e))
+}
+
+/// The number of the last finalized block at `at`.
+fn block_number(client: &FullClient, at: At) -> Result<BlockNumber> {
+    let moment = match at {
+        At::Number(number) => return Ok(number),
+        At::Timestamp(moment) => moment,
+    };
+    let finalized = client.info().finalized_number;
+    let latest = block_timestamp(client, finalized)?;
+    if latest < moment {
+        return Err(Error::invalid_params(format!(
+            "the last finalized block #{} is from {}, before {}",
+            finalized, latest, moment
+        )));
+    }
+    // the genesis block has no timestamp, so it comes before any time.
+    let (mut low, mut high) = (0, finalized);
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if block_timestamp(client, middle)? <= moment {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// The state of `account` at a point in the past.
+fn account_state(
+    client: &FullClient,
+    account_id: &AccountId,
+    at: At,
+    asset: Option<u32>,
+) -> Result<AccountState> {
+    let number = block_number(client, at)?;
+    let hash = finalized_hash(client, number)?;
+    let at = BlockId::Hash(hash);
+    let AccountInfo { nonce, data, .. } =
+        account(client, &at, account_id).map_err(|e| pruned_error(number, e))?;
+    let asset = match asset {
+        Some(id) => {
+            let balance = storage::<AssetBalance>(client, &at, asset_account_key(id, account_id))
+                .map_err(|e| pruned_error(number, e))?
+                .unwrap_or_default();
+            Some(AssetState { id, balance: balance.balance.into(), is_frozen: balance.is_frozen })
+        }
+        None => None,
+    };
+
+    Ok(AccountState {
+        block_number: number,
+        block_hash: hash,
+        timestamp: timestamp(client, &at).map_err(|e| pruned_error(number, e))?,
+        nonce,
+        free: data.free.into(),
+        reserved: data.reserved.into(),
+        misc_frozen: data.misc_frozen.into(),
+        fee_frozen: data.fee_frozen.into(),
+        asset,
+    })
+}
+
+/// The points from `from` to `to` every `step`, `to` included.
+fn points(from: At, to: At, step: u64) -> Result<Vec<At>> {
+    let (from, to, kind): (u64, u64, fn(u64) -> At) = match (from, to) {
+        (At::Number(from), At::Number(to)) => {
+            (from.into(), to.into(), |number| At::Number(number as BlockNumber))
+        }
+        (At::Timestamp(from), At::Timestamp(to)) => (from, to, At::Timestamp),
+        _ => return Err(Error::invalid_params("the range must be two numbers or two timestamps")),
+    };
+    if step == 0 || from > to {
+        return Err(Error::invalid_params("the range is empty"));
+    }
+    if (to - from) / step >= MAX_POINTS {
+        return Err(Error::invalid_params(format!(
+            "the range has more than {} points, take a larger step",
+            MAX_POINTS
+        )));
+    }
+    let mut points: Vec<_> = (0..=(to - from) / step).map(|i| kind(from + i * step)).collect();
+    if (to - from) % step != 0 {
+        points.push(kind(to));
+    }
+    Ok(points)
+}
+
+impl BalanceHistoryApi for BalanceHistory {
+    type Metadata = sc_rpc::Metadata;
+
+    fn account_at(&self, account: String, at: At, asset: Option<u32>) -> Result<AccountState> {
+        let account = parse_key(&account, AccountId::from).map_err(Error::invalid_params)?;
+        account_state(&self.client, &account, at, asset)
+    }
+
+    fn subscribe_account_range(
+        &self,
+        _metadata: Self::Metadata,
+        subscriber: Subscriber<AccountState>,
+        account: String,
+        from: At,
+        to: At,
+        step: u64,
+        asset: Option<u32>,
+    ) {
+        let params = parse_key(&account, AccountId::from)
+            .map_err(Error::invalid_params)
+            .and_then(|account| Ok((account, points(from, to, step)?)));
+        let (account, points) = match params {
+            Ok(params) => params,
+            Err(e) => {
+                let _ = subscriber.reject(e);
+                return;
+            }
+        };
+
+        let client = self.client.clone();
+        // the states are read as they are sent, until the first one that cannot be.
+        let states = points.into_iter().scan(false, move |failed, at| {
+            if *failed {
+                return None;
+            }
+            let state = account_state(&client, &account, at, asset);
+            *failed = state.is_err();
+            Some(Ok::<_, ()>(state))
+        });
+        self.manager.add(subscriber, |sink| {
+            let mut states = futures::stream::iter(states);
+            async move {
+                let _ = sink
+                    .sink_map_err(|e| log::warn!("Error sending notifications: {:?}", e))
+                    .send_all(&mut states)
+                    .await;
+            }
+            .boxed()
+        });
+    }
+
+    fn unsubscribe_account_range(
+        &self,
+        _metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.manager.cancel(id))
+    }
+}
@@ -0,0 +1,138 @@
+//! `system_dryRunDetailed`: what an extrinsic would do, without submitting it.
+//!
+//! The extrinsic is applied on top of the state of a block through the `DryRunApi` of the
+//! runtime, and the changes it made to the storage are compared with that state before they are
+//! discarded. Like `system_dryRun`, the method is unsafe: it executes arbitrary extrinsics.
+
+use super::runtime_error;
+use crate::inspect::decode;
+use crate::metadata::{Formatter, NativeMetadata};
+use crate::service::FullClient;
+use codec::Decode;
+use coinnet_runtime_api::DryRunApi;
+use jsonrpc_core::{Error, Result};
+use jsonrpc_derive::rpc;
+use node_primitives::{Block, Hash};
+use sc_client_api::StorageProvider;
+use sc_rpc_api::DenyUnsafe;
+use sc_service::ChainSpec;
+use serde::Serialize;
+use serde_json::Value;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58AddressFormat, storage::StorageKey, Bytes};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, ApplyExtrinsicResult};
+use std::sync::Arc;
+
+/// A storage entry the extrinsic changed.
+#[derive(Debug, Serialize)]
+pub struct StorageChange {
+    /// The key of the entry.
+    pub key: Bytes,
+    /// The value before the extrinsic, if there was one.
+    pub before: Option<Bytes>,
+    /// The value after the extrinsic, unless it removed the entry.
+    pub after: Option<Bytes>,
+}
+
+/// What applying an extrinsic did.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunDetailed {
+    /// The validity of the extrinsic and, if it was applied, the outcome of its dispatch.
+    pub result: ApplyExtrinsicResult,
+    /// The weight the extrinsic actually used.
+    pub weight: u64,
+    /// The fee the extrinsic paid, tip included.
+    pub fee: NumberOrHex,
+    /// The events the extrinsic emitted, decoded with the metadata of the native runtime.
+    pub events: Vec<Value>,
+    /// The storage entries the extrinsic changed, ordered by key. Changes to child tries are
+    /// left out.
+    pub changes: Vec<StorageChange>,
+}
+
+/// Dry-run RPC methods.
+#[rpc]
+pub trait DryRunDetailedApi {
+    /// Apply a SCALE-encoded extrinsic on top of the state of block `at`, the best block by
+    /// default, and return what it did.
+    #[rpc(name = "system_dryRunDetailed")]
+    fn dry_run_detailed(&self, extrinsic: Bytes, at: Option<Hash>) -> Result<DryRunDetailed>;
+}
+
+/// Implements the [`DryRunDetailedApi`] RPC trait with the state of a full node.
+pub struct DryRun {
+    client: Arc<FullClient>,
+    deny_unsafe: DenyUnsafe,
+    metadata: NativeMetadata,
+    ss58_format: Ss58AddressFormat,
+}
+
+impl DryRun {
+    /// Create a new `DryRun` for a node of the network of `chain_spec`.
+    pub fn new(
+        client: Arc<FullClient>,
+        deny_unsafe: DenyUnsafe,
+        chain_spec: &dyn ChainSpec,
+    ) -> std::result::Result<Self, String> {
+        Ok(DryRun {
+            client,
+            deny_unsafe,
+            metadata: NativeMetadata::new()?,
+            ss58_format: crate::chain::ss58_format(chain_spec),
+        })
+    }
+}
+
+impl DryRunDetailedApi for DryRun {
+    fn dry_run_detailed(&self, extrinsic: Bytes, at: Option<Hash>) -> Result<DryRunDetailed> {
+        self.deny_unsafe.check_if_safe()?;
+        let extrinsic = <Block as BlockT>::Extrinsic::decode(&mut &extrinsic[..])
+            .map_err(|e| Error::invalid_params(format!("not an extrinsic: {}", e)))?;
+        let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+        let at = BlockId::Hash(hash);
+
+        let api = self.client.runtime_api();
+        let dry_run =
+            api.dry_run(&at, extrinsic).map_err(|e| runtime_error("Unable to dry run.", e))?;
+        let state = self.client.state_at(&at).map_err(|e| runtime_error("Unknown block.", e))?;
+        let storage_changes = api
+            .into_storage_changes(&state, None, hash)
+            .map_err(|e| runtime_error("Unable to collect the changes.", e))?;
+
+        let mut changes = Vec::new();
+        for (key, after) in storage_changes.main_storage_changes {
+            let before = self
+                .client
+                .storage(&at, &StorageKey(key.clone()))
+                .map_err(|e| runtime_error("Unable to read the state.", e))?
+                .map(|value| value.0);
+            if before != after {
+                changes.push(StorageChange {
+                    key: key.into(),
+                    before: before.map(Bytes),
+                    after: after.map(Bytes),
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.key.0.cmp(&b.key.0));
+
+        let no_assets = |_| None;
+        let formatter = Formatter { ss58_format: self.ss58_format, token: None, asset: &no_assets };
+        let events = dry_run
+            .events
+            .iter()
+            .map(|event| decode(event, |input| self.metadata.decode_event(input, &formatter)))
+            .collect();
+
+        Ok(DryRunDetailed {
+            result: dry_run.result,
+            weight: dry_run.weight,
+            fee: dry_run.fee.into(),
+            events,
+            changes,
+        })
+    }
+}
@@ -0,0 +1,197 @@
+//! `payment_estimateFee`: the fee of a call in a few blocks, as the fee multiplier moves.
+//!
+//! `payment_queryInfo` gives the fee under the multiplier of the next block only. The multiplier
+//! follows how full the blocks are (`TargetedFeeAdjustment`), so the estimate has the runtime at
+//! the block (`FeeMultiplierApi`) run its update for the blocks ahead under three assumptions:
+//! empty blocks (low), blocks as full as the best one (expected) and full blocks (high).
+
+use super::runtime_error;
+use crate::chain::{storage, storage_key};
+use crate::service::FullClient;
+use codec::{Decode, Encode};
+use coinnet_runtime_api::FeeMultiplierApi;
+use frame_support::{
+    traits::Get,
+    weights::{DispatchClass, Weight, WeightToFeePolynomial},
+};
+use frame_system::ConsumedWeight;
+use jsonrpc_core::{Error, Result};
+use jsonrpc_derive::rpc;
+use node_primitives::{Balance, Block, Hash};
+use node_runtime::{signed_extra, Address, Call, Runtime, RuntimeBlockWeights, UncheckedExtrinsic};
+use pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi;
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{sr25519, Bytes};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{
+    generic::{BlockId, Era},
+    traits::Block as BlockT,
+    FixedPointNumber, MultiSignature,
+};
+use std::{convert::TryFrom, sync::Arc};
+
+type WeightToFee = <Runtime as pallet_transaction_payment::Config>::WeightToFee;
+
+/// How far ahead fees can be estimated: a day of blocks.
+const MAX_BLOCKS_AHEAD: u32 = 14_400;
+
+/// What the fee of a transaction is made of, in the expected case.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeBreakdown {
+    /// The fee every transaction pays.
+    pub base_fee: NumberOrHex,
+    /// The fee for the length of the transaction.
+    pub len_fee: NumberOrHex,
+    /// The fee for the weight of the call, scaled by the fee multiplier.
+    pub adjusted_weight_fee: NumberOrHex,
+    /// The tip.
+    pub tip: NumberOrHex,
+}
+
+/// The fee of a transaction included `blocks_ahead` blocks after the next one.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeEstimate {
+    /// The weight of the call.
+    pub weight: Weight,
+    /// The dispatch class of the call.
+    pub class: DispatchClass,
+    /// The length of the transaction the fee is for.
+    pub len: u32,
+    /// The fee if the blocks until then are empty.
+    pub low: NumberOrHex,
+    /// The fee if the blocks until then are as full as the best block.
+    pub expected: NumberOrHex,
+    /// The fee if the blocks until then are full.
+    pub high: NumberOrHex,
+    /// The expected fee by its parts.
+    pub breakdown: FeeBreakdown,
+}
+
+/// Fee estimation RPC methods.
+#[rpc]
+pub trait FeeEstimationApi {
+    /// Estimate the fee of a SCALE-encoded call in a signed transaction included `blocks_ahead`
+    /// blocks after the next one. Without `len`, the length is that of the call in a mortal
+    /// transaction with a small nonce.
+    #[rpc(name = "payment_estimateFee")]
+    fn estimate_fee(
+        &self,
+        call: Bytes,
+        len: Option<u32>,
+        blocks_ahead: u32,
+        tip: Option<NumberOrHex>,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate>;
+}
+
+/// Implements the [`FeeEstimationApi`] RPC trait with the state of a full node.
+pub struct FeeEstimation {
+    client: Arc<FullClient>,
+}
+
+impl FeeEstimation {
+    /// Create a new `FeeEstimation` reading the state of `client`.
+    pub fn new(client: Arc<FullClient>) -> Self {
+        FeeEstimation { client }
+    }
+}
+
+impl FeeEstimationApi for FeeEstimation {
+    fn estimate_fee(
+        &self,
+        call: Bytes,
+        len: Option<u32>,
+        blocks_ahead: u32,
+        tip: Option<NumberOrHex>,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate> {
+        if blocks_ahead > MAX_BLOCKS_AHEAD {
+            return Err(Error::invalid_params(format!(
+                "cannot estimate fees more than {} blocks ahead",
+                MAX_BLOCKS_AHEAD
+            )));
+        }
+        let tip = match tip {
+            Some(tip) => {
+                Balance::try_from(tip).map_err(|_| Error::invalid_params("the tip is too large"))?
+            }
+            None => 0,
+        };
+        let mut input = &call[..];
+        let call = match Call::decode(&mut input) {
+            Ok(call) if input.is_empty() => call,
+            _ => return Err(Error::invalid_params("not a call of the runtime")),
+        };
+
+        let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+        let at = BlockId::Hash(hash);
+        let number = self
+            .client
+            .number(hash)
+            .map_err(|e| runtime_error("Unable to read the block.", e))?
+            .ok_or_else(|| Error::invalid_params("unknown block"))?;
+        // the signature is not checked, only its length counts.
+        let extrinsic = UncheckedExtrinsic::new_signed(
+            call,
+            Address::Id(Default::default()),
+            MultiSignature::Sr25519(sr25519::Signature::from_raw([0; 64])),
+            signed_extra(Era::mortal(256, number.into()), 0, tip),
+        );
+        let len = len.unwrap_or(extrinsic.encoded_size() as u32);
+        let opaque: <Block as BlockT>::Extrinsic = Decode::decode(&mut &extrinsic.encode()[..])
+            .expect("an opaque extrinsic decodes from any extrinsic; qed");
+
+        let api = self.client.runtime_api();
+        let info = api
+            .query_info(&at, opaque.clone(), len)
+            .map_err(|e| runtime_error("Unable to query dispatch info.", e))?;
+        let details = api
+            .query_fee_details(&at, opaque, len)
+            .map_err(|e| runtime_error("Unable to query fee details.", e))?;
+        // calls that pay no fee only pay the tip.
+        let (base_fee, len_fee, weight_fee) = match details.inclusion_fee {
+            Some(fee) => (fee.base_fee, fee.len_fee, WeightToFee::calc(&info.weight)),
+            None => (0, 0, 0),
+        };
+
+        let best_weight =
+            storage::<ConsumedWeight>(&self.client, &at, storage_key(b"System", b"BlockWeight"))
+                .map_err(|e| runtime_error("Unable to read the state.", e))?
+                .map_or(0, |weight| *weight.get(DispatchClass::Normal));
+        let weights = RuntimeBlockWeights::get();
+        let normal = weights.get(DispatchClass::Normal);
+        let full_weight = normal.max_total.unwrap_or(weights.max_block);
+
+        let fee = |weight| -> Result<(Balance, Balance)> {
+            let multiplier = api
+                .estimate_multiplier(&at, weight, blocks_ahead)
+                .map_err(|e| runtime_error("Unable to estimate the fee multiplier.", e))?;
+            let adjusted_weight_fee = multiplier.saturating_mul_int(weight_fee);
+            let fee = base_fee
+                .saturating_add(len_fee)
+                .saturating_add(adjusted_weight_fee)
+                .saturating_add(tip);
+            Ok((fee, adjusted_weight_fee))
+        };
+        let (expected, adjusted_weight_fee) = fee(best_weight)?;
+
+        Ok(FeeEstimate {
+            weight: info.weight,
+            class: info.class,
+            len,
+            low: fee(0)?.0.into(),
+            expected: expected.into(),
+            high: fee(full_weight)?.0.into(),
+            breakdown: FeeBreakdown {
+                base_fee: base_fee.into(),
+                len_fee: len_fee.into(),
+                adjusted_weight_fee: adjusted_weight_fee.into(),
+                tip: tip.into(),
+            },
+        })
+    }
+}
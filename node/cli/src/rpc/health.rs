@@ -0,0 +1,35 @@
+//! `validator_health`: the health of the validator this node runs, from the health monitor
+//! (`--validator-health`). The method is unsafe: it tells which validator runs on the node.
+
+use crate::health::{HealthReport, SharedHealthReport};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use sc_rpc_api::DenyUnsafe;
+
+/// Validator health RPC methods.
+#[rpc]
+pub trait ValidatorHealthApi {
+    /// The health of the validator at the last best block.
+    #[rpc(name = "validator_health")]
+    fn health(&self) -> Result<HealthReport>;
+}
+
+/// Implements the [`ValidatorHealthApi`] RPC trait with the report of the health monitor.
+pub struct ValidatorHealth {
+    report: SharedHealthReport,
+    deny_unsafe: DenyUnsafe,
+}
+
+impl ValidatorHealth {
+    /// Create a new `ValidatorHealth` serving `report`.
+    pub fn new(report: SharedHealthReport, deny_unsafe: DenyUnsafe) -> Self {
+        ValidatorHealth { report, deny_unsafe }
+    }
+}
+
+impl ValidatorHealthApi for ValidatorHealth {
+    fn health(&self) -> Result<HealthReport> {
+        self.deny_unsafe.check_if_safe()?;
+        Ok(self.report.read().clone())
+    }
+}
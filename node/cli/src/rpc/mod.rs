@@ -0,0 +1,24 @@
+//! RPC methods of this node, next to those `node_rpc` provides for any substrate node.
+
+mod balance;
+mod dry_run;
+mod fee;
+mod health;
+mod txpool;
+
+pub use balance::{BalanceHistory, BalanceHistoryApi};
+pub use dry_run::{DryRun, DryRunDetailedApi};
+pub use fee::{FeeEstimation, FeeEstimationApi};
+pub use health::{ValidatorHealth, ValidatorHealthApi};
+pub use txpool::{TxPool, TxPoolApi};
+
+use jsonrpc_core::{Error, ErrorCode};
+
+/// An error of the client or of the runtime while serving a call, with its details as data.
+fn runtime_error(message: &str, e: impl std::fmt::Debug) -> Error {
+    Error {
+        code: ErrorCode::ServerError(1),
+        message: message.into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
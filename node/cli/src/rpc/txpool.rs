@@ -0,0 +1,201 @@
+//! `txpool_*`: the transactions of the pool by sender, for finding out why a transaction is stuck.
+//!
+//! Ready transactions can go into the next block; future ones wait for a transaction before them,
+//! usually one with a lower nonce of the same sender. `txpool_remove` is unsafe: it evicts a
+//! transaction and bans its hash from the pool for a while.
+
+use crate::chain::{era_nonce_tip, ss58_format, token};
+use crate::inspect::{decode, describe};
+use crate::metadata::{Formatter, NativeMetadata};
+use crate::service::FullClient;
+use codec::{Decode, Encode};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_primitives::{Block, Hash, Index};
+use node_runtime::UncheckedExtrinsic;
+use sc_rpc_api::DenyUnsafe;
+use sc_service::ChainSpec;
+use serde::Serialize;
+use serde_json::Value;
+use sp_core::{crypto::Ss58AddressFormat, Bytes};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::Block as BlockT;
+use sp_transaction_pool::{InPoolTransaction, TransactionPool};
+use std::{collections::BTreeMap, sync::Arc};
+
+type FullPool = sc_transaction_pool::FullPool<Block, FullClient>;
+
+/// A transaction of the pool with its hash and priority.
+type Transaction = (Hash, <Block as BlockT>::Extrinsic, Option<u64>);
+
+/// The key of unsigned transactions in the maps by sender.
+const UNSIGNED: &str = "unsigned";
+
+/// A transaction of the pool.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolTransaction {
+    /// The hash of the transaction.
+    pub hash: Hash,
+    /// The nonce of a signed transaction.
+    pub nonce: Option<Index>,
+    /// The tip of a signed transaction.
+    pub tip: Option<NumberOrHex>,
+    /// The priority the runtime gave the transaction, known for ready transactions.
+    pub priority: Option<u64>,
+    /// The call, decoded with the metadata of the native runtime.
+    pub call: Value,
+    /// The SCALE-encoded transaction.
+    pub extrinsic: Bytes,
+}
+
+/// The transactions of the pool by sender, ordered by nonce.
+#[derive(Debug, Serialize)]
+pub struct PoolContent<T> {
+    /// Transactions that can be included in the next block.
+    pub ready: BTreeMap<String, Vec<T>>,
+    /// Transactions waiting for others to be included first.
+    pub future: BTreeMap<String, Vec<T>>,
+}
+
+/// Transaction pool RPC methods.
+#[rpc]
+pub trait TxPoolApi {
+    /// The transactions of the pool.
+    #[rpc(name = "txpool_content")]
+    fn content(&self) -> Result<PoolContent<PoolTransaction>>;
+
+    /// The transactions of the pool, each in one line.
+    #[rpc(name = "txpool_inspect")]
+    fn inspect(&self) -> Result<PoolContent<String>>;
+
+    /// Remove a transaction from the pool and ban its hash for a while. Returns whether the
+    /// transaction was in the pool.
+    #[rpc(name = "txpool_remove")]
+    fn remove(&self, hash: Hash) -> Result<bool>;
+}
+
+/// Implements the [`TxPoolApi`] RPC trait for the transaction pool of a full node.
+pub struct TxPool {
+    pool: Arc<FullPool>,
+    deny_unsafe: DenyUnsafe,
+    metadata: NativeMetadata,
+    ss58_format: Ss58AddressFormat,
+    token: (String, u8),
+}
+
+impl TxPool {
+    /// Create a new `TxPool` for the pool of a node of the network of `chain_spec`.
+    pub fn new(
+        pool: Arc<FullPool>,
+        deny_unsafe: DenyUnsafe,
+        chain_spec: &dyn ChainSpec,
+    ) -> std::result::Result<Self, String> {
+        Ok(TxPool {
+            pool,
+            deny_unsafe,
+            metadata: NativeMetadata::new()?,
+            ss58_format: ss58_format(chain_spec),
+            token: token(chain_spec),
+        })
+    }
+
+    /// The ready and future transactions of the pool, with their priority if known.
+    fn transactions(&self) -> (Vec<Transaction>, Vec<Transaction>) {
+        let ready =
+            self.pool.ready().map(|tx| (*tx.hash(), tx.data().clone(), Some(*tx.priority())));
+        let future = self.pool.pool().validated_pool().futures().into_iter();
+        (ready.collect(), future.map(|(hash, extrinsic)| (hash, extrinsic, None)).collect())
+    }
+
+    /// Decode the transactions of the pool and group them by sender.
+    fn by_sender<T>(
+        &self,
+        with_token: bool,
+        summary: impl Fn(Hash, Option<u64>, &[u8], UncheckedExtrinsic, &Formatter) -> T,
+    ) -> Result<PoolContent<T>> {
+        let no_assets = |_| None;
+        let formatter = Formatter {
+            ss58_format: self.ss58_format,
+            token: if with_token { Some(self.token.clone()) } else { None },
+            asset: &no_assets,
+        };
+        let group = |transactions: Vec<Transaction>| -> Result<BTreeMap<String, Vec<T>>> {
+            let mut by_sender = BTreeMap::<String, Vec<(Option<Index>, T)>>::new();
+            for (hash, opaque, priority) in transactions {
+                let encoded = opaque.encode();
+                let extrinsic =
+                    UncheckedExtrinsic::decode(&mut &encoded[..]).map_err(|e| Error {
+                        code: ErrorCode::InternalError,
+                        message: format!("cannot decode transaction {:?}: {}", hash, e),
+                        data: None,
+                    })?;
+                let (sender, nonce) = match &extrinsic.signature {
+                    Some((address, _, extra)) => {
+                        let (_, nonce, _) = era_nonce_tip(extra);
+                        let sender = match formatter.address(address) {
+                            Value::String(sender) => sender,
+                            sender => sender.to_string(),
+                        };
+                        (sender, Some(nonce))
+                    }
+                    None => (UNSIGNED.into(), None),
+                };
+                let summary = summary(hash, priority, &encoded, extrinsic, &formatter);
+                by_sender.entry(sender).or_default().push((nonce, summary));
+            }
+            Ok(by_sender
+                .into_iter()
+                .map(|(sender, mut transactions)| {
+                    transactions.sort_by_key(|(nonce, _)| *nonce);
+                    (sender, transactions.into_iter().map(|(_, summary)| summary).collect())
+                })
+                .collect())
+        };
+
+        let (ready, future) = self.transactions();
+        Ok(PoolContent { ready: group(ready)?, future: group(future)? })
+    }
+}
+
+impl TxPoolApi for TxPool {
+    fn content(&self) -> Result<PoolContent<PoolTransaction>> {
+        self.by_sender(false, |hash, priority, encoded, extrinsic, formatter| {
+            let tip = extrinsic.signature.as_ref().map(|(_, _, extra)| era_nonce_tip(extra));
+            PoolTransaction {
+                hash,
+                nonce: tip.as_ref().map(|(_, nonce, _)| *nonce),
+                tip: tip.map(|(_, _, tip)| tip.into()),
+                priority,
+                call: decode(&extrinsic.function, |input| {
+                    self.metadata.decode_call(input, formatter)
+                }),
+                extrinsic: Bytes(encoded.to_vec()),
+            }
+        })
+    }
+
+    fn inspect(&self) -> Result<PoolContent<String>> {
+        self.by_sender(true, |hash, priority, _, extrinsic, formatter| {
+            let call =
+                decode(&extrinsic.function, |input| self.metadata.decode_call(input, formatter));
+            let signed = extrinsic.signature.as_ref().map(|(_, _, extra)| {
+                let (_, nonce, tip) = era_nonce_tip(extra);
+                format!("nonce {}, tip {}, ", nonce, describe(&formatter.native(tip)))
+            });
+            let priority = priority.map_or("unknown".into(), |priority| priority.to_string());
+            format!(
+                "{:?}: {} ({}priority {})",
+                hash,
+                describe(&call),
+                signed.unwrap_or_default(),
+                priority
+            )
+        })
+    }
+
+    fn remove(&self, hash: Hash) -> Result<bool> {
+        self.deny_unsafe.check_if_safe()?;
+        Ok(!self.pool.remove_invalid(&[hash]).is_empty())
+    }
+}
@@ -0,0 +1,199 @@
+//! The `runtime-upgrade` command, which checks a new runtime against the one it replaces and
+//! prints the calls that enact a forkless upgrade to it.
+//!
+//! The version of the new runtime must keep the spec name and raise the spec version, or
+//! `System.set_code` refuses it. Its transaction version must not go down, and must go up when a
+//! call of the current runtime was removed or moved to other indices, or takes other arguments,
+//! because transactions signed for the current runtime would then mean something else; calls
+//! that are only added leave it alone. Both runtimes are run to read their version and metadata,
+//! the current one being the native runtime, or that of a chain when `--url` is given.
+//!
+//! The calls are printed as the JSON descriptions `tx build` takes, one per line. They hold the
+//! whole runtime, which is too long for the command line, so they are best written to files, one
+//! per call:
+//!
+//! ```sh
+//! coinnet-node runtime-upgrade --wasm node_runtime.compact.compressed.wasm > upgrade.json
+//! head -1 upgrade.json > note_preimage.json && tail -1 upgrade.json > propose.json
+//! coinnet-node tx build --call note_preimage.json --nonce 0 --genesis-hash 0x... > unsigned
+//! ```
+
+use crate::cli::RuntimeUpgradeCmd;
+use crate::metadata::call_signatures;
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use jsonrpc_core_client::transports::http;
+use node_executor::Executor;
+use node_primitives::Hash;
+use node_runtime::{Call, MinimumDeposit, Runtime, VERSION};
+use sc_cli::Error;
+use sc_executor::{NativeExecutor, RuntimeInfo, WasmExecutionMethod};
+use sc_rpc_api::state::StateClient;
+use serde_json::json;
+use sp_api::RuntimeVersion;
+use sp_core::{
+    hexdisplay::HexDisplay,
+    traits::{CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+    Bytes, NativeOrEncoded, NeverNativeValue,
+};
+use sp_io::TestExternalities;
+use sp_maybe_compressed_blob::CODE_BLOB_BOMB_LIMIT;
+use sp_runtime::traits::{BlakeTwo256, Hash as _};
+use std::fs;
+
+impl RuntimeUpgradeCmd {
+    /// Run the command.
+    pub fn run(&self) -> Result<(), Error> {
+        let code = fs::read(&self.wasm)?;
+        let wasm = sp_maybe_compressed_blob::decompress(&code, CODE_BLOB_BOMB_LIMIT)
+            .map_err(|e| Error::Input(format!("cannot decompress the runtime: {}", e)))?;
+        let (version, metadata) = run_runtime(&wasm).map_err(Error::Input)?;
+        let (current_version, current_metadata) = match &self.url {
+            Some(url) => chain_runtime(url)
+                .map_err(|e| Error::Input(format!("cannot query {}: {}", url, e)))?,
+            None => (VERSION, Runtime::metadata().encode()),
+        };
+        check_upgrade(&current_version, &current_metadata, &version, &metadata)
+            .map_err(Error::Input)?;
+        eprintln!(
+            "Upgrade of {} from spec version {} to {}, transaction version {} to {}",
+            version.spec_name,
+            current_version.spec_version,
+            version.spec_version,
+            current_version.transaction_version,
+            version.transaction_version,
+        );
+
+        let set_code = json!({
+            "pallet": "System",
+            "call": "set_code",
+            "args": { "code": format!("0x{}", HexDisplay::from(&code)) },
+        });
+        let calls = match self.origin.as_str() {
+            "root" => vec![set_code],
+            "sudo" => {
+                eprintln!(
+                    "Warning: the first runtime upgrade of a chain removes its sudo key, later \
+                     upgrades go through democracy"
+                );
+                // the weight of `set_code` is that of a whole block, which no transaction fits in.
+                vec![json!({
+                    "pallet": "Sudo",
+                    "call": "sudo_unchecked_weight",
+                    "args": { "call": set_code, "_weight": 0 },
+                })]
+            }
+            "democracy" => {
+                let proposal = Call::System(frame_system::Call::set_code(code)).encode();
+                let preimage = format!("0x{}", HexDisplay::from(&proposal));
+                let deposit = self.deposit.unwrap_or_else(MinimumDeposit::get);
+                vec![
+                    json!({
+                        "pallet": "Democracy",
+                        "call": "note_preimage",
+                        "args": { "encoded_proposal": preimage },
+                    }),
+                    json!({
+                        "pallet": "Democracy",
+                        "call": "propose",
+                        "args": {
+                            "proposal_hash": format!("{:?}", BlakeTwo256::hash(&proposal)),
+                            "value": deposit.to_string(),
+                        },
+                    }),
+                ]
+            }
+            origin => return Err(Error::Input(format!("unknown origin {}", origin))),
+        };
+        for call in calls {
+            println!("{}", call);
+        }
+        Ok(())
+    }
+}
+
+/// Read the version and the SCALE encoded metadata of an uncompressed runtime by running it.
+fn run_runtime(wasm: &[u8]) -> Result<(RuntimeVersion, Vec<u8>), String> {
+    let executor = NativeExecutor::<Executor>::new(WasmExecutionMethod::Interpreted, None, 1);
+    let code_fetcher = WrappedRuntimeCode(wasm.into());
+    let runtime_code = RuntimeCode {
+        code_fetcher: &code_fetcher,
+        heap_pages: None,
+        hash: sp_core::blake2_256(wasm).to_vec(),
+    };
+    let mut ext = TestExternalities::default();
+    let mut ext = ext.ext();
+
+    let version = executor
+        .runtime_version(&mut ext, &runtime_code)
+        .map_err(|e| format!("cannot read the version of the runtime: {}", e))?;
+    let (metadata, _) = executor.call::<NeverNativeValue, fn() -> _>(
+        &mut ext,
+        &runtime_code,
+        "Metadata_metadata",
+        &[],
+        false,
+        None,
+    );
+    let metadata = match metadata {
+        Ok(NativeOrEncoded::Encoded(encoded)) => Vec::<u8>::decode(&mut &encoded[..])
+            .map_err(|e| format!("cannot decode the metadata of the runtime: {}", e))?,
+        Ok(NativeOrEncoded::Native(never)) => match never {},
+        Err(e) => return Err(format!("cannot read the metadata of the runtime: {}", e)),
+    };
+    Ok((version, metadata))
+}
+
+/// The version and the SCALE encoded metadata of the runtime of the chain of a node.
+fn chain_runtime(url: &str) -> Result<(RuntimeVersion, Vec<u8>), jsonrpc_core_client::RpcError> {
+    futures::executor::block_on(async {
+        let client: StateClient<Hash> = http::connect(url).await?;
+        let version = client.runtime_version(None).await?;
+        let Bytes(metadata) = client.metadata(None).await?;
+        Ok((version, metadata))
+    })
+}
+
+/// Check that a runtime may replace the current one.
+fn check_upgrade(
+    current: &RuntimeVersion,
+    current_metadata: &[u8],
+    new: &RuntimeVersion,
+    new_metadata: &[u8],
+) -> Result<(), String> {
+    if new.spec_name != current.spec_name {
+        return Err(format!(
+            "the runtime is {}, it cannot replace {}",
+            new.spec_name, current.spec_name
+        ));
+    }
+    if new.spec_version <= current.spec_version {
+        return Err(format!(
+            "the spec version {} is not above the current spec version {}",
+            new.spec_version, current.spec_version
+        ));
+    }
+    if new.transaction_version < current.transaction_version {
+        return Err(format!(
+            "the transaction version {} is below the current transaction version {}",
+            new.transaction_version, current.transaction_version
+        ));
+    }
+    if new.transaction_version == current.transaction_version {
+        let current_calls = call_signatures(current_metadata)?;
+        let new_calls = call_signatures(new_metadata)?;
+        let changed: Vec<_> = current_calls
+            .iter()
+            .filter(|(name, signature)| new_calls.get(*name) != Some(signature))
+            .map(|((pallet, call), _)| format!("{}.{}", pallet, call))
+            .collect();
+        if !changed.is_empty() {
+            return Err(format!(
+                "{} changed or went away, so the transaction version must be above {}",
+                changed.join(", "),
+                current.transaction_version
+            ));
+        }
+    }
+    Ok(())
+}
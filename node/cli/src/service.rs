@@ -20,7 +20,7 @@
 
 //! Service implementation. Specialized wrapper over substrate service.
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 use sc_consensus_babe;
 use node_primitives::Block;
 use node_runtime::RuntimeApi;
@@ -36,13 +36,76 @@ use node_executor::Executor;
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_consensus_babe::SlotProportion;
 
-type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
+pub(crate) type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 type FullGrandpaBlockImport =
 	grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
 type LightClient = sc_service::TLightClient<Block, RuntimeApi, Executor>;
 
+/// `sc_service::new_full_parts`, with the remote signer of `--keystore-uri`, if any, set on the
+/// keystore container before the client is built: the keystore extension of the client, which
+/// the offchain workers sign ImOnline heartbeats with, is then the remote signer too.
+#[cfg(feature = "cli")]
+fn new_full_parts(
+	config: &Configuration,
+	telemetry: Option<sc_telemetry::TelemetryHandle>,
+) -> Result<sc_service::TFullParts<Block, RuntimeApi, Executor>, ServiceError> {
+	let mut keystore_container = sc_service::KeystoreContainer::new(&config.keystore)?;
+	let task_manager = {
+		let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
+		TaskManager::new(config.task_executor.clone(), registry)?
+	};
+	if let Some(uri) = &config.keystore_remote {
+		let spawner = Arc::new(task_manager.spawn_handle());
+		let remote = crate::remote_signer::RemoteKeystore::connect(uri, spawner).map_err(|e| {
+			ServiceError::Other(format!("Cannot use the remote signer at {}: {}", uri, e))
+		})?;
+		keystore_container.set_remote_keystore(Arc::new(remote));
+	}
+
+	let executor = sc_executor::NativeExecutor::<Executor>::new(
+		config.wasm_method,
+		config.default_heap_pages,
+		config.max_runtime_instances,
+	);
+	let extensions = sc_client_api::execution_extensions::ExecutionExtensions::new(
+		config.execution_strategies.clone(),
+		Some(keystore_container.sync_keystore()),
+	);
+	let chain_extensions = config.chain_spec.extensions();
+	let (client, backend) = sc_service::new_client(
+		sc_client_db::DatabaseSettings {
+			state_cache_size: config.state_cache_size,
+			state_cache_child_ratio: config.state_cache_child_ratio.map(|v| (v, 100)),
+			state_pruning: config.state_pruning.clone(),
+			source: config.database.clone(),
+			keep_blocks: config.keep_blocks.clone(),
+			transaction_storage: config.transaction_storage.clone(),
+		},
+		executor,
+		config.chain_spec.as_storage_builder(),
+		sc_chain_spec::get_extension::<sc_client_api::ForkBlocks<Block>>(chain_extensions)
+			.cloned()
+			.unwrap_or_default(),
+		sc_chain_spec::get_extension::<sc_client_api::BadBlocks<Block>>(chain_extensions)
+			.cloned()
+			.unwrap_or_default(),
+		extensions,
+		Box::new(task_manager.spawn_handle()),
+		config.prometheus_config.as_ref().map(|config| config.registry.clone()),
+		telemetry,
+		sc_service::ClientConfig {
+			offchain_worker_enabled: config.offchain_worker.enabled,
+			offchain_indexing_api: config.offchain_worker.indexing_enabled,
+			wasm_runtime_overrides: config.wasm_runtime_overrides.clone(),
+			..Default::default()
+		},
+	)?;
+
+	Ok((client, backend, keystore_container, task_manager))
+}
+
 pub fn new_partial(
 	config: &Configuration,
 ) -> Result<sc_service::PartialComponents<
@@ -72,6 +135,12 @@ pub fn new_partial(
 		})
 		.transpose()?;
 
+	#[cfg(feature = "cli")]
+	let (client, backend, keystore_container, task_manager) = new_full_parts(
+		&config,
+		telemetry.as_ref().map(|(_, telemetry)| telemetry.handle()),
+	)?;
+	#[cfg(not(feature = "cli"))]
 	let (client, backend, keystore_container, task_manager) =
 		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(
 			&config,
@@ -164,12 +233,35 @@ pub fn new_partial(
 					shared_voter_state: shared_voter_state.clone(),
 					shared_authority_set: shared_authority_set.clone(),
 					justification_stream: justification_stream.clone(),
-					subscription_executor,
+					subscription_executor: subscription_executor.clone(),
 					finality_provider: finality_proof_provider.clone(),
 				},
 			};
 
-			node_rpc::create_full(deps)
+			#[allow(unused_mut)]
+			let mut io = node_rpc::create_full(deps);
+			#[cfg(feature = "cli")]
+			io.extend_with(crate::rpc::FeeEstimationApi::to_delegate(
+				crate::rpc::FeeEstimation::new(client.clone()),
+			));
+			#[cfg(feature = "cli")]
+			match crate::rpc::TxPool::new(pool.clone(), deny_unsafe, &*chain_spec) {
+				Ok(txpool) => io.extend_with(crate::rpc::TxPoolApi::to_delegate(txpool)),
+				Err(e) => log::warn!("Cannot serve the txpool RPCs: {}", e),
+			}
+			#[cfg(feature = "cli")]
+			io.extend_with(crate::rpc::BalanceHistoryApi::to_delegate(
+				crate::rpc::BalanceHistory::new(
+					client.clone(),
+					jsonrpc_pubsub::manager::SubscriptionManager::new(Arc::new(subscription_executor)),
+				),
+			));
+			#[cfg(feature = "cli")]
+			match crate::rpc::DryRun::new(client.clone(), deny_unsafe, &*chain_spec) {
+				Ok(dry_run) => io.extend_with(crate::rpc::DryRunDetailedApi::to_delegate(dry_run)),
+				Err(e) => log::warn!("Cannot serve system_dryRunDetailed: {}", e),
+			}
+			io
 		};
 
 		(rpc_extensions_builder, rpc_setup)
@@ -197,9 +289,37 @@ pub struct NewFullBase {
 	pub transaction_pool: Arc<sc_transaction_pool::FullPool<Block, FullClient>>,
 }
 
+/// Options of a full node that are not part of the substrate `Configuration`.
+#[derive(Debug, Clone)]
+pub struct NodeOptions {
+	/// Keep an SQLite index of the chain at this path.
+	pub index_db: Option<PathBuf>,
+	/// Monitor the health of the validator of the keystore.
+	pub validator_health: bool,
+	/// The number of blocks between GRANDPA justifications.
+	pub grandpa_justification_period: u32,
+	/// The GRANDPA voting rules, in order. The default rules of `VotingRulesBuilder` if none.
+	pub grandpa_voting_rules: Option<Vec<crate::voting_rules::GrandpaVotingRule>>,
+	/// Enable or disable the staking payout offchain worker. The stored setting if `None`.
+	pub staking_payouts: Option<bool>,
+}
+
+impl Default for NodeOptions {
+	fn default() -> Self {
+		NodeOptions {
+			index_db: None,
+			validator_health: false,
+			grandpa_justification_period: 512,
+			grandpa_voting_rules: None,
+			staking_payouts: None,
+		}
+	}
+}
+
 /// Creates a full service from the configuration.
 pub fn new_full_base(
 	mut config: Configuration,
+	options: NodeOptions,
 	with_startup_data: impl FnOnce(
 		&sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 		&sc_consensus_babe::BabeLink<Block>,
@@ -232,6 +352,7 @@ pub fn new_full_base(
 		)
 	);
 
+	let slot_duration = import_setup.2.config().slot_duration;
 	let (network, network_status_sinks, system_rpc_tx, network_starter) =
 		sc_service::build_network(sc_service::BuildNetworkParams {
 			config: &config,
@@ -240,7 +361,9 @@ pub fn new_full_base(
 			spawn_handle: task_manager.spawn_handle(),
 			import_queue,
 			on_demand: None,
-			block_announce_validator_builder: None,
+			block_announce_validator_builder: Some(Box::new(move |client| Box::new(
+				crate::block_announce::CoinnetBlockAnnounceValidator::new(client, slot_duration)
+			))),
 		})?;
 
 	if config.offchain_worker.enabled {
@@ -249,6 +372,31 @@ pub fn new_full_base(
 		);
 	}
 
+	if let Some(enabled) = options.staking_payouts {
+		use sc_client_api::Backend;
+		use sp_core::offchain::OffchainStorage;
+
+		// only written when given, so that a setting of the operator outlives restarts.
+		match backend.offchain_storage() {
+			Some(mut storage) => storage.set(
+				sc_offchain::STORAGE_PREFIX,
+				pallet_staking_payout::ENABLED_KEY,
+				// `true` or `false`, SCALE encoded.
+				&[enabled as u8],
+			),
+			None => return Err(ServiceError::Other(
+				"Staking payouts need the offchain storage of the database".into()
+			)),
+		}
+	}
+
+	#[cfg(feature = "cli")]
+	if let Some(path) = options.index_db {
+		let indexer = crate::indexer::Indexer::open(&path, client.clone(), &*config.chain_spec)
+			.map_err(|e| ServiceError::Other(format!("Cannot open the index: {}", e)))?;
+		task_manager.spawn_handle().spawn_blocking("indexer", indexer.run());
+	}
+
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
 	let backoff_authoring_blocks =
@@ -257,6 +405,56 @@ pub fn new_full_base(
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	#[cfg(feature = "cli")]
+	if let Some(registry) = prometheus_registry.as_ref() {
+		let metrics = crate::metrics::ChainMetrics::register(registry, client.clone())
+			.map_err(ServiceError::Other)?;
+		task_manager.spawn_handle().spawn_blocking("chain-metrics", metrics.run());
+	}
+
+	#[cfg(feature = "cli")]
+	let health_report = if options.validator_health {
+		let monitor = crate::health::HealthMonitor::new(
+			client.clone(),
+			keystore_container.sync_keystore(),
+			shared_voter_state.clone(),
+			slot_duration,
+			&*config.chain_spec,
+			prometheus_registry.as_ref(),
+		).map_err(ServiceError::Other)?;
+		let report = monitor.report();
+		task_manager.spawn_handle().spawn_blocking("validator-health", monitor.run());
+		Some(report)
+	} else {
+		None
+	};
+	let rpc_extensions_builder = move |
+		deny_unsafe: node_rpc::DenyUnsafe,
+		subscription_executor: sc_rpc::SubscriptionTaskExecutor,
+	| {
+		#[allow(unused_mut)]
+		let mut io = rpc_extensions_builder(deny_unsafe, subscription_executor);
+		#[cfg(feature = "cli")]
+		if let Some(report) = &health_report {
+			io.extend_with(crate::rpc::ValidatorHealthApi::to_delegate(
+				crate::rpc::ValidatorHealth::new(report.clone(), deny_unsafe),
+			));
+		}
+		io
+	};
+
+	#[cfg(feature = "cli")]
+	let slashing_protection = match (&role, config.base_path.as_ref()) {
+		(sc_service::config::Role::Authority { .. }, Some(base_path)) => {
+			let path = crate::slashing_protection::database_path(
+				&base_path.config_dir(config.chain_spec.id()),
+			);
+			Some(crate::slashing_protection::SlashingProtection::open(&path)
+				.map_err(ServiceError::Other)?)
+		},
+		_ => None,
+	};
+
 	let _rpc_handlers = sc_service::spawn_tasks(
 		sc_service::SpawnTasksParams {
 			config,
@@ -287,6 +485,13 @@ pub fn new_full_base(
 			prometheus_registry.as_ref(),
 			telemetry.as_ref().map(|x| x.handle()),
 		);
+		#[cfg(feature = "cli")]
+		let proposer = crate::slashing_protection::ProtectedProposerFactory::new(
+			proposer,
+			slashing_protection.clone().ok_or_else(|| ServiceError::Other(
+				"Validators need a base path for the slashing protection database".into(),
+			))?,
+		);
 
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
@@ -339,7 +544,18 @@ pub fn new_full_base(
 	// if the node isn't actively participating in consensus then it doesn't
 	// need a keystore, regardless of which protocol we use below.
 	let keystore = if role.is_authority() {
-		Some(keystore_container.sync_keystore())
+		// GRANDPA votes are checked against the slashing protection database as they are signed.
+		#[cfg(feature = "cli")]
+		let keystore = slashing_protection.map_or_else(
+			|| keystore_container.sync_keystore(),
+			|protection| Arc::new(crate::slashing_protection::ProtectedKeystore::new(
+				keystore_container.sync_keystore(),
+				protection,
+			)) as _,
+		);
+		#[cfg(not(feature = "cli"))]
+		let keystore = keystore_container.sync_keystore();
+		Some(keystore)
 	} else {
 		None
 	};
@@ -347,7 +563,7 @@ pub fn new_full_base(
 	let config = grandpa::Config {
 		// FIXME #1578 make this available through chainspec
 		gossip_duration: std::time::Duration::from_millis(333),
-		justification_period: 512,
+		justification_period: options.grandpa_justification_period,
 		name: Some(name),
 		observer_enabled: false,
 		keystore,
@@ -367,7 +583,9 @@ pub fn new_full_base(
 			link: grandpa_link,
 			network: network.clone(),
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
-			voting_rule: grandpa::VotingRulesBuilder::default().build(),
+			voting_rule: crate::voting_rules::voting_rules(
+				options.grandpa_voting_rules.as_deref(),
+			).build(),
 			prometheus_registry,
 			shared_voter_state,
 		};
@@ -394,12 +612,80 @@ pub fn new_full_base(
 /// Builds a new service for a full client.
 pub fn new_full(
 	config: Configuration,
+	options: NodeOptions,
 ) -> Result<TaskManager, ServiceError> {
-	new_full_base(config, |_, _| ()).map(|NewFullBase { task_manager, .. }| {
+	new_full_base(config, options, |_, _| ()).map(|NewFullBase { task_manager, .. }| {
 		task_manager
 	})
 }
 
+/// Start a light client with an empty database from the light sync state of its chain spec, if
+/// there is one: the finalized header becomes the finalized base of the database, without its
+/// ancestors, and the BABE epoch changes and the GRANDPA authority set go into the auxiliary
+/// storage the block imports load them from.
+///
+/// Returns the imported state, which the caller checks the GRANDPA block import against.
+fn import_light_sync_state(
+	client: &LightClient,
+	backend: &sc_service::TLightBackend<Block>,
+	chain_spec: &dyn sc_service::ChainSpec,
+	babe_config: &sc_consensus_babe::Config,
+) -> Result<Option<crate::chain_spec::LightSyncState>, ServiceError> {
+	use codec::{Decode, Encode};
+	use sc_client_api::{Backend as _, BlockImportOperation as _, NewBlockState};
+	use sc_consensus_babe::aux_schema;
+	use sp_blockchain::HeaderBackend as _;
+	use sp_runtime::traits::Header as _;
+
+	let state = match sc_chain_spec::get_extension::<crate::chain_spec::LightSyncStateExtension>(
+		chain_spec.extensions(),
+	) {
+		Some(Some(state)) => state.clone(),
+		_ => return Ok(None),
+	};
+	if backend.blockchain().info().finalized_number != 0 {
+		return Ok(None);
+	}
+
+	let header = <Block as BlockT>::Header::decode(&mut &state.finalized_block_header[..])
+		.map_err(|e| ServiceError::Other(format!("Invalid light sync state header: {}", e)))?;
+	let hash = header.hash();
+	log::info!("Starting from the light sync state at block #{} ({})", header.number(), hash);
+	// the keys and schema versions of `sc_consensus_babe::aux_schema` and
+	// `sc_finality_grandpa::aux_schema`, which only export their loaders. What the loaders read
+	// back is compared with the state below and in `new_light_base`, so that a schema change
+	// fails the start instead of corrupting the consensus state.
+	let aux = vec![
+		(b"babe_epoch_changes_version".to_vec(), Some(2u32.encode())),
+		(b"babe_epoch_changes".to_vec(), Some(state.babe_epoch_changes.to_vec())),
+		((b"block_weight", hash).encode(), Some(state.babe_finalized_block_weight.encode())),
+		(b"grandpa_schema_version".to_vec(), Some(3u32.encode())),
+		(b"grandpa_voters".to_vec(), Some(state.grandpa_authority_set.to_vec())),
+	];
+
+	let mut operation = backend.begin_operation()?;
+	operation.set_block_data(header, None, None, NewBlockState::Final)?;
+	operation.insert_aux(aux)?;
+	backend.commit_operation(operation)?;
+
+	let epoch_changes = aux_schema::load_epoch_changes::<Block, _>(client, babe_config)?;
+	if epoch_changes.lock().encode() != state.babe_epoch_changes.to_vec() {
+		return Err(ServiceError::Other(
+			"The BABE epoch changes of the light sync state do not load back, the BABE aux \
+			schema changed"
+				.into(),
+		));
+	}
+	if aux_schema::load_block_weight(client, hash)? != Some(state.babe_finalized_block_weight) {
+		return Err(ServiceError::Other(
+			"The BABE block weight of the light sync state does not load back, the BABE aux \
+			schema changed"
+				.into(),
+		));
+	}
+	Ok(Some(state))
+}
+
 pub fn new_light_base(
 	mut config: Configuration,
 ) -> Result<(
@@ -439,6 +725,12 @@ pub fn new_light_base(
 
 	config.network.extra_sets.push(grandpa::grandpa_peers_set_config());
 
+	// a light client only runs the runtime at the genesis block, which it has the state of, so
+	// the BABE configuration is read before the light sync state moves the best block.
+	let babe_config = sc_consensus_babe::Config::get_or_compute(&*client)?;
+	let light_sync_state =
+		import_light_sync_state(&client, &backend, &*config.chain_spec, &babe_config)?;
+
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
 	let transaction_pool = Arc::new(sc_transaction_pool::BasicPool::new_light(
@@ -449,16 +741,27 @@ pub fn new_light_base(
 		on_demand.clone(),
 	));
 
-	let (grandpa_block_import, _) = grandpa::block_import(
+	let (grandpa_block_import, grandpa_link) = grandpa::block_import(
 		client.clone(),
 		&(client.clone() as Arc<_>),
 		select_chain.clone(),
 		telemetry.as_ref().map(|x| x.handle()),
 	)?;
 	let justification_import = grandpa_block_import.clone();
+	if let Some(state) = light_sync_state {
+		use codec::Encode;
+		let authority_set = grandpa_link.shared_authority_set().clone_inner().encode();
+		if authority_set != state.grandpa_authority_set.to_vec() {
+			return Err(ServiceError::Other(
+				"The GRANDPA authority set of the light sync state does not load back, the \
+				GRANDPA aux schema changed"
+					.into(),
+			));
+		}
+	}
 
 	let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
-		sc_consensus_babe::Config::get_or_compute(&*client)?,
+		babe_config,
 		grandpa_block_import,
 		client.clone(),
 	)?;
@@ -598,7 +901,7 @@ mod tests {
 				let mut setup_handles = None;
 				let NewFullBase {
 					task_manager, inherent_data_providers, client, network, transaction_pool, ..
-				} = new_full_base(config,
+				} = new_full_base(config, Default::default(),
 					|
 						block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 						babe_link: &sc_consensus_babe::BabeLink<Block>,
@@ -773,7 +1076,7 @@ mod tests {
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. }
-					= new_full_base(config,|_, _| ())?;
+					= new_full_base(config, Default::default(), |_, _| ())?;
 				Ok(sc_service_test::TestNetComponents::new(task_manager, client, network, transaction_pool))
 			},
 			|config| {
@@ -0,0 +1,152 @@
+//! The `session-keys` subcommands: generating session keys into a keystore, decoding them and
+//! building the `Session::set_keys` call that registers them, all without a running node.
+
+use crate::cli::{
+    GenerateSessionKeysCmd, InspectSessionKeysCmd, SessionKeysSubcommand, SetKeysPayloadCmd,
+};
+use codec::{Decode, Encode};
+use node_runtime::{Call, SessionCall, SessionKeys};
+use sc_cli::{Error, OutputType, SubstrateCli};
+use sc_keystore::LocalKeystore;
+use sc_service::{config::KeystoreConfig, BasePath};
+use sp_core::{
+    crypto::{key_types, AccountId32, KeyTypeId, Ss58Codec},
+    hexdisplay::HexDisplay,
+};
+use sp_keystore::SyncCryptoStore;
+
+fn parse_hex(name: &str, value: &str) -> Result<Vec<u8>, Error> {
+    sp_core::bytes::from_hex(value)
+        .map_err(|e| Error::Input(format!("{} are not valid hex: {}", name, e)))
+}
+
+fn decode_keys(value: &str) -> Result<SessionKeys, Error> {
+    let encoded = parse_hex("session keys", value)?;
+    SessionKeys::decode(&mut &encoded[..])
+        .map_err(|e| Error::Input(format!("cannot decode session keys: {}", e)))
+}
+
+pub(crate) fn key_type_name(key_type: &KeyTypeId) -> String {
+    String::from_utf8_lossy(&key_type.0).into_owned()
+}
+
+impl SessionKeysSubcommand {
+    /// Run the command.
+    pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+        match self {
+            SessionKeysSubcommand::Generate(cmd) => cmd.run(cli),
+            SessionKeysSubcommand::Inspect(cmd) => cmd.run(),
+            SessionKeysSubcommand::SetKeysPayload(cmd) => cmd.run(),
+        }
+    }
+}
+
+impl GenerateSessionKeysCmd {
+    /// Run the command.
+    pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+        let base_path = self
+            .shared_params
+            .base_path()
+            .unwrap_or_else(|| BasePath::from_project("", "", &C::executable_name()));
+        let chain_id = self.shared_params.chain_id(self.shared_params.is_dev());
+        let chain_spec = cli.load_spec(&chain_id)?;
+        let config_dir = base_path.config_dir(chain_spec.id());
+
+        let keystore = match self.keystore_params.keystore_config(&config_dir)? {
+            (Some(_), _) => {
+                return Err(Error::Input(
+                    "session keys are only generated into a local keystore, not a remote one"
+                        .into(),
+                ))
+            }
+            (None, KeystoreConfig::Path { path, password }) => LocalKeystore::open(path, password)?,
+            (None, KeystoreConfig::InMemory) => {
+                return Err(Error::Input("session keys cannot be generated in memory".into()))
+            }
+        };
+
+        let generate_error = |key_type: KeyTypeId| {
+            move |e: sp_keystore::Error| {
+                Error::Input(format!(
+                    "cannot generate the {} key: {}",
+                    key_type_name(&key_type),
+                    e
+                ))
+            }
+        };
+        let keys = SessionKeys {
+            grandpa: SyncCryptoStore::ed25519_generate_new(&keystore, key_types::GRANDPA, None)
+                .map_err(generate_error(key_types::GRANDPA))?
+                .into(),
+            babe: SyncCryptoStore::sr25519_generate_new(&keystore, key_types::BABE, None)
+                .map_err(generate_error(key_types::BABE))?
+                .into(),
+            im_online: SyncCryptoStore::sr25519_generate_new(&keystore, key_types::IM_ONLINE, None)
+                .map_err(generate_error(key_types::IM_ONLINE))?
+                .into(),
+            authority_discovery: SyncCryptoStore::sr25519_generate_new(
+                &keystore,
+                key_types::AUTHORITY_DISCOVERY,
+                None,
+            )
+            .map_err(generate_error(key_types::AUTHORITY_DISCOVERY))?
+            .into(),
+        };
+
+        println!("0x{}", HexDisplay::from(&keys.encode()));
+        Ok(())
+    }
+}
+
+impl InspectSessionKeysCmd {
+    /// Run the command.
+    pub fn run(&self) -> Result<(), Error> {
+        let encoded = parse_hex("session keys", &self.keys)?;
+        let keys = SessionKeys::decode_into_raw_public_keys(&encoded)
+            .ok_or_else(|| Error::Input("cannot decode session keys".into()))?;
+
+        let keys = keys.into_iter().map(|(public, key_type)| {
+            let ss58 = <[u8; 32]>::decode(&mut &public[..])
+                .map(|raw| AccountId32::from(raw).to_ss58check())
+                .unwrap_or_default();
+            (key_type_name(&key_type), public, ss58)
+        });
+        match self.output_type.output_type {
+            OutputType::Json => {
+                let json = keys
+                    .map(|(key_type, public, ss58)| {
+                        serde_json::json!({
+                            "keyType": key_type,
+                            "publicKey": format!("0x{}", HexDisplay::from(&public)),
+                            "ss58Address": ss58,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json).expect("Json pretty print failed")
+                );
+            }
+            OutputType::Text => {
+                for (key_type, public, ss58) in keys {
+                    println!("{}:", key_type);
+                    println!("  Public key (hex):  0x{}", HexDisplay::from(&public));
+                    println!("  SS58 Address:      {}", ss58);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SetKeysPayloadCmd {
+    /// Run the command.
+    pub fn run(&self) -> Result<(), Error> {
+        let keys = decode_keys(&self.keys)?;
+        let proof = parse_hex("the proof", &self.proof)?;
+
+        let call = Call::Session(SessionCall::set_keys(keys, proof));
+        println!("0x{}", HexDisplay::from(&call.encode()));
+        Ok(())
+    }
+}
@@ -0,0 +1,704 @@
+//! Slashing protection: an SQLite database of what the validator signed, so that it never signs
+//! a second BABE block for a slot or a conflicting GRANDPA vote for a round, which
+//! `HandleEquivocation` would report to `Offences` and get slashed.
+//!
+//! BABE never authors in a past slot, so the database keeps the last slot a block was proposed
+//! for and the proposer refuses any slot up to it. GRANDPA votes are checked when the keystore
+//! signs them: a vote of a key for a round and stage must target the block the key voted for
+//! before, and rounds older than those the database keeps, or of a previous authority set, are
+//! refused. A refused vote stops the GRANDPA voter, and with it the node.
+//!
+//! The database lives next to the keystore (`slashing_protection.sqlite` in the chain directory)
+//! and moves with it: `slashing-protection export` writes it as JSON, and `slashing-protection
+//! import` merges such JSON into the database of another machine before the validator starts
+//! there.
+
+use crate::cli::{
+    ExportSlashingProtectionCmd, ImportSlashingProtectionCmd, SlashingProtectionSubcommand,
+};
+use async_trait::async_trait;
+use codec::Decode;
+use futures::{future, FutureExt, TryFutureExt};
+use node_primitives::{Block, BlockNumber, Hash};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use sc_cli::{Error, SharedParams, SubstrateCli};
+use sc_service::BasePath;
+use serde::{Deserialize, Serialize};
+use sp_consensus::{Environment, Proposal, Proposer};
+use sp_consensus_babe::digests::CompatibleDigestItem;
+use sp_core::{
+    crypto::{key_types, CryptoTypePublicPair, KeyTypeId},
+    ecdsa, ed25519, sr25519, Bytes,
+};
+use sp_inherents::InherentData;
+use sp_keystore::{
+    vrf::{VRFSignature, VRFTranscriptData},
+    CryptoStore, Error as KeystoreError, SyncCryptoStore, SyncCryptoStorePtr,
+};
+use sp_runtime::traits::{Block as BlockT, DigestFor};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+const SCHEMA: &str = "
+-- the one row holds the last slot a block was proposed for
+CREATE TABLE IF NOT EXISTS blocks (
+    slot INTEGER NOT NULL PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS votes (
+    public BLOB NOT NULL,
+    set_id INTEGER NOT NULL,
+    round INTEGER NOT NULL,
+    -- 'prevote', 'precommit' or 'primary-propose'
+    stage TEXT NOT NULL,
+    target_hash BLOB NOT NULL,
+    target_number INTEGER NOT NULL,
+    PRIMARY KEY (public, set_id, round, stage)
+);
+";
+
+/// The GRANDPA rounds of the current set the database keeps the votes of.
+const KEEP_ROUNDS: u64 = 1000;
+
+/// The stages of a round a GRANDPA key signs a message in.
+const STAGES: [&str; 3] = ["prevote", "precommit", "primary-propose"];
+
+/// A GRANDPA message, as `sp_finality_grandpa::localized_payload` encodes it before the round
+/// and set id: the stage and the target block.
+#[derive(Decode)]
+enum Vote {
+    Prevote(Hash, BlockNumber),
+    Precommit(Hash, BlockNumber),
+    PrimaryPropose(Hash, BlockNumber),
+}
+
+impl Vote {
+    fn stage_and_target(&self) -> (&'static str, Hash, BlockNumber) {
+        match self {
+            Vote::Prevote(hash, number) => (STAGES[0], *hash, *number),
+            Vote::Precommit(hash, number) => (STAGES[1], *hash, *number),
+            Vote::PrimaryPropose(hash, number) => (STAGES[2], *hash, *number),
+        }
+    }
+}
+
+/// A GRANDPA vote in the JSON of `slashing-protection export`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedVote {
+    public: Bytes,
+    set_id: u64,
+    round: u64,
+    stage: String,
+    target_hash: Hash,
+    target_number: BlockNumber,
+}
+
+/// The JSON of `slashing-protection export`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Interchange {
+    /// The id of the chain spec, so that the database does not move to another chain.
+    chain: String,
+    last_slot: Option<u64>,
+    votes: Vec<SignedVote>,
+}
+
+fn database_error(e: rusqlite::Error) -> String {
+    format!("slashing protection database error: {}", e)
+}
+
+/// The path of the database in the directory of a chain.
+pub fn database_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("slashing_protection.sqlite")
+}
+
+/// The slashing protection database.
+#[derive(Clone)]
+pub struct SlashingProtection {
+    db: Arc<Mutex<Connection>>,
+}
+
+impl SlashingProtection {
+    /// Open the database at `path`, creating it if it does not exist.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("cannot create {}: {}", parent.display(), e))?;
+        }
+        let db = Connection::open(path)
+            .and_then(|db| db.execute_batch(SCHEMA).map(|_| db))
+            .map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+        Ok(SlashingProtection { db: Arc::new(Mutex::new(db)) })
+    }
+
+    /// Record a block proposed for `slot`, unless one was proposed for it or a later slot.
+    fn check_block(&self, slot: u64) -> Result<(), String> {
+        let mut db = self.db.lock();
+        let tx = db.transaction().map_err(database_error)?;
+        let last = tx
+            .query_row("SELECT MAX(slot) FROM blocks", params![], |row| {
+                row.get::<_, Option<i64>>(0)
+            })
+            .map_err(database_error)?;
+        if let Some(last) = last {
+            if slot <= last as u64 {
+                return Err(format!(
+                    "refusing to author a block in slot {}, a block was authored in slot {}",
+                    slot, last
+                ));
+            }
+        }
+        tx.execute("INSERT INTO blocks (slot) VALUES (?1)", params![slot as i64])
+            .and_then(|_| tx.execute("DELETE FROM blocks WHERE slot < ?1", params![slot as i64]))
+            .and_then(|_| tx.commit())
+            .map_err(database_error)
+    }
+
+    /// Record a GRANDPA vote of `public`, unless it conflicts with one the key signed before.
+    fn check_vote(
+        &self,
+        public: &[u8],
+        set_id: u64,
+        round: u64,
+        stage: &str,
+        (target_hash, target_number): (Hash, BlockNumber),
+    ) -> Result<(), String> {
+        let mut db = self.db.lock();
+        let tx = db.transaction().map_err(database_error)?;
+        let last_set = tx
+            .query_row("SELECT MAX(set_id) FROM votes WHERE public = ?1", params![public], |row| {
+                row.get::<_, Option<i64>>(0)
+            })
+            .map_err(database_error)?;
+        if let Some(last_set) = last_set {
+            if set_id < last_set as u64 {
+                return Err(format!(
+                    "refusing to vote in GRANDPA set {} after voting in set {}",
+                    set_id, last_set
+                ));
+            }
+        }
+        let first_round = tx
+            .query_row(
+                "SELECT MIN(round) FROM votes WHERE public = ?1 AND set_id = ?2",
+                params![public, set_id as i64],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .map_err(database_error)?;
+        if let Some(first_round) = first_round {
+            if round < first_round as u64 {
+                return Err(format!(
+                    "refusing to vote in GRANDPA round {}, before the rounds the database knows",
+                    round
+                ));
+            }
+        }
+
+        let voted = tx
+            .query_row(
+                "SELECT target_hash, target_number FROM votes
+                 WHERE public = ?1 AND set_id = ?2 AND round = ?3 AND stage = ?4",
+                params![public, set_id as i64, round as i64, stage],
+                |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, u32>(1)?)),
+            )
+            .optional()
+            .map_err(database_error)?;
+        match voted {
+            Some((hash, number)) if hash == target_hash.as_bytes() && number == target_number => {
+                Ok(())
+            }
+            Some((_, number)) => Err(format!(
+                "refusing to {} for block #{} in GRANDPA round {} of set {}, the key voted for \
+                 block #{} already",
+                stage, target_number, round, set_id, number
+            )),
+            None => tx
+                .execute(
+                    "INSERT INTO votes
+                     (public, set_id, round, stage, target_hash, target_number)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        public,
+                        set_id as i64,
+                        round as i64,
+                        stage,
+                        target_hash.as_bytes(),
+                        target_number
+                    ],
+                )
+                .and_then(|_| {
+                    tx.execute(
+                        "DELETE FROM votes WHERE public = ?1 AND (set_id < ?2 OR round + ?3 < ?4)",
+                        params![public, set_id as i64, KEEP_ROUNDS as i64, round as i64],
+                    )
+                })
+                .and_then(|_| tx.commit())
+                .map_err(database_error),
+        }
+    }
+
+    fn export(&self, chain: String) -> Result<Interchange, String> {
+        let db = self.db.lock();
+        let last_slot = db
+            .query_row("SELECT MAX(slot) FROM blocks", params![], |row| {
+                row.get::<_, Option<i64>>(0)
+            })
+            .map_err(database_error)?;
+        let mut statement = db
+            .prepare(
+                "SELECT public, set_id, round, stage, target_hash, target_number FROM votes
+                 ORDER BY public, set_id, round, stage",
+            )
+            .map_err(database_error)?;
+        let votes = statement
+            .query_map(params![], |row| {
+                Ok(SignedVote {
+                    public: Bytes(row.get(0)?),
+                    set_id: row.get::<_, i64>(1)? as u64,
+                    round: row.get::<_, i64>(2)? as u64,
+                    stage: row.get(3)?,
+                    target_hash: Hash::from_slice(&row.get::<_, Vec<u8>>(4)?),
+                    target_number: row.get(5)?,
+                })
+            })
+            .and_then(|votes| votes.collect::<Result<_, _>>())
+            .map_err(database_error)?;
+        Ok(Interchange { chain, last_slot: last_slot.map(|slot| slot as u64), votes })
+    }
+
+    /// Merge an export into the database. Votes the database has for the same round and stage
+    /// are kept.
+    fn import(&self, interchange: &Interchange) -> Result<usize, String> {
+        let mut db = self.db.lock();
+        let tx = db.transaction().map_err(database_error)?;
+        if let Some(slot) = interchange.last_slot {
+            tx.execute("INSERT OR IGNORE INTO blocks (slot) VALUES (?1)", params![slot as i64])
+                .and_then(|_| {
+                    tx.execute(
+                        "DELETE FROM blocks WHERE slot < (SELECT MAX(slot) FROM blocks)",
+                        params![],
+                    )
+                })
+                .map_err(database_error)?;
+        }
+        let mut imported = 0;
+        for vote in &interchange.votes {
+            if !STAGES.contains(&&vote.stage[..]) {
+                return Err(format!("unknown GRANDPA vote stage {}", vote.stage));
+            }
+            imported += tx
+                .execute(
+                    "INSERT OR IGNORE INTO votes
+                     (public, set_id, round, stage, target_hash, target_number)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        &vote.public[..],
+                        vote.set_id as i64,
+                        vote.round as i64,
+                        vote.stage,
+                        vote.target_hash.as_bytes(),
+                        vote.target_number
+                    ],
+                )
+                .map_err(database_error)?;
+        }
+        tx.commit().map_err(database_error)?;
+        Ok(imported)
+    }
+}
+
+/// The proposer factory of BABE, refusing to propose a block in a slot the slashing protection
+/// database does not allow.
+pub struct ProtectedProposerFactory<E> {
+    inner: E,
+    protection: SlashingProtection,
+}
+
+impl<E> ProtectedProposerFactory<E> {
+    /// Wrap the proposer factory `inner`.
+    pub fn new(inner: E, protection: SlashingProtection) -> Self {
+        ProtectedProposerFactory { inner, protection }
+    }
+}
+
+impl<E: Environment<Block>> Environment<Block> for ProtectedProposerFactory<E> {
+    type Proposer = ProtectedProposer<E::Proposer>;
+    type CreateProposer =
+        Pin<Box<dyn future::Future<Output = Result<Self::Proposer, Self::Error>> + Send>>;
+    type Error = E::Error;
+
+    fn init(&mut self, parent_header: &<Block as BlockT>::Header) -> Self::CreateProposer {
+        let protection = self.protection.clone();
+        self.inner
+            .init(parent_header)
+            .map_ok(move |inner| ProtectedProposer { inner, protection })
+            .boxed()
+    }
+}
+
+/// A proposer of [`ProtectedProposerFactory`].
+pub struct ProtectedProposer<P> {
+    inner: P,
+    protection: SlashingProtection,
+}
+
+impl<P: Proposer<Block>> Proposer<Block> for ProtectedProposer<P> {
+    type Error = P::Error;
+    type Transaction = P::Transaction;
+    type Proposal = future::Either<
+        P::Proposal,
+        future::Ready<Result<Proposal<Block, P::Transaction, P::Proof>, P::Error>>,
+    >;
+    type ProofRecording = P::ProofRecording;
+    type Proof = P::Proof;
+
+    fn propose(
+        self,
+        inherent_data: InherentData,
+        inherent_digests: DigestFor<Block>,
+        max_duration: Duration,
+        block_size_limit: Option<usize>,
+    ) -> Self::Proposal {
+        let slot = inherent_digests
+            .logs()
+            .iter()
+            .find_map(|log| log.as_babe_pre_digest())
+            .map(|pre_digest| *pre_digest.slot());
+        if let Some(slot) = slot {
+            if let Err(e) = self.protection.check_block(slot) {
+                log::warn!(target: "slashing-protection", "{}", e);
+                let e = sp_consensus::Error::CannotSign(Vec::new(), e);
+                return future::Either::Right(future::ready(Err(e.into())));
+            }
+        }
+        future::Either::Left(self.inner.propose(
+            inherent_data,
+            inherent_digests,
+            max_duration,
+            block_size_limit,
+        ))
+    }
+}
+
+/// A keystore checking the GRANDPA votes it signs against the slashing protection database.
+pub struct ProtectedKeystore {
+    inner: SyncCryptoStorePtr,
+    protection: SlashingProtection,
+}
+
+impl ProtectedKeystore {
+    /// Wrap the keystore `inner`.
+    pub fn new(inner: SyncCryptoStorePtr, protection: SlashingProtection) -> Self {
+        ProtectedKeystore { inner, protection }
+    }
+}
+
+#[async_trait]
+impl CryptoStore for ProtectedKeystore {
+    async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+        SyncCryptoStore::sr25519_public_keys(self, id)
+    }
+
+    async fn sr25519_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<sr25519::Public, KeystoreError> {
+        SyncCryptoStore::sr25519_generate_new(self, id, seed)
+    }
+
+    async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+        SyncCryptoStore::ed25519_public_keys(self, id)
+    }
+
+    async fn ed25519_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ed25519::Public, KeystoreError> {
+        SyncCryptoStore::ed25519_generate_new(self, id, seed)
+    }
+
+    async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+        SyncCryptoStore::ecdsa_public_keys(self, id)
+    }
+
+    async fn ecdsa_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ecdsa::Public, KeystoreError> {
+        SyncCryptoStore::ecdsa_generate_new(self, id, seed)
+    }
+
+    async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+        SyncCryptoStore::insert_unknown(self, id, suri, public)
+    }
+
+    async fn supported_keys(
+        &self,
+        id: KeyTypeId,
+        keys: Vec<CryptoTypePublicPair>,
+    ) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        SyncCryptoStore::supported_keys(self, id, keys)
+    }
+
+    async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        SyncCryptoStore::keys(self, id)
+    }
+
+    async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+        SyncCryptoStore::has_keys(self, public_keys)
+    }
+
+    async fn sign_with(
+        &self,
+        id: KeyTypeId,
+        key: &CryptoTypePublicPair,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, KeystoreError> {
+        SyncCryptoStore::sign_with(self, id, key, msg)
+    }
+
+    async fn sr25519_vrf_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &sr25519::Public,
+        transcript_data: VRFTranscriptData,
+    ) -> Result<VRFSignature, KeystoreError> {
+        SyncCryptoStore::sr25519_vrf_sign(self, key_type, public, transcript_data)
+    }
+}
+
+impl SyncCryptoStore for ProtectedKeystore {
+    fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+        SyncCryptoStore::sr25519_public_keys(&*self.inner, id)
+    }
+
+    fn sr25519_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<sr25519::Public, KeystoreError> {
+        SyncCryptoStore::sr25519_generate_new(&*self.inner, id, seed)
+    }
+
+    fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+        SyncCryptoStore::ed25519_public_keys(&*self.inner, id)
+    }
+
+    fn ed25519_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ed25519::Public, KeystoreError> {
+        SyncCryptoStore::ed25519_generate_new(&*self.inner, id, seed)
+    }
+
+    fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+        SyncCryptoStore::ecdsa_public_keys(&*self.inner, id)
+    }
+
+    fn ecdsa_generate_new(
+        &self,
+        id: KeyTypeId,
+        seed: Option<&str>,
+    ) -> Result<ecdsa::Public, KeystoreError> {
+        SyncCryptoStore::ecdsa_generate_new(&*self.inner, id, seed)
+    }
+
+    fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+        SyncCryptoStore::insert_unknown(&*self.inner, id, suri, public)
+    }
+
+    fn supported_keys(
+        &self,
+        id: KeyTypeId,
+        keys: Vec<CryptoTypePublicPair>,
+    ) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        SyncCryptoStore::supported_keys(&*self.inner, id, keys)
+    }
+
+    fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, KeystoreError> {
+        SyncCryptoStore::keys(&*self.inner, id)
+    }
+
+    fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+        SyncCryptoStore::has_keys(&*self.inner, public_keys)
+    }
+
+    fn sign_with(
+        &self,
+        id: KeyTypeId,
+        key: &CryptoTypePublicPair,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, KeystoreError> {
+        if id == key_types::GRANDPA {
+            let mut input = msg;
+            if let Ok((vote, round, set_id)) = <(Vote, u64, u64)>::decode(&mut input) {
+                if input.is_empty() {
+                    let (stage, hash, number) = vote.stage_and_target();
+                    self.protection
+                        .check_vote(&key.1, set_id, round, stage, (hash, number))
+                        .map_err(|e| {
+                            log::error!(target: "slashing-protection", "{}", e);
+                            KeystoreError::Other(e)
+                        })?;
+                }
+            }
+        }
+        SyncCryptoStore::sign_with(&*self.inner, id, key, msg)
+    }
+
+    fn sr25519_vrf_sign(
+        &self,
+        key_type: KeyTypeId,
+        public: &sr25519::Public,
+        transcript_data: VRFTranscriptData,
+    ) -> Result<VRFSignature, KeystoreError> {
+        SyncCryptoStore::sr25519_vrf_sign(&*self.inner, key_type, public, transcript_data)
+    }
+}
+
+/// Open the database of the chain of `shared_params`.
+fn open<C: SubstrateCli>(
+    cli: &C,
+    shared_params: &SharedParams,
+) -> Result<(SlashingProtection, String), Error> {
+    let base_path = shared_params
+        .base_path()
+        .unwrap_or_else(|| BasePath::from_project("", "", &C::executable_name()));
+    let chain_spec = cli.load_spec(&shared_params.chain_id(shared_params.is_dev()))?;
+    let path = database_path(&base_path.config_dir(chain_spec.id()));
+    let protection = SlashingProtection::open(&path).map_err(Error::Input)?;
+    Ok((protection, chain_spec.id().into()))
+}
+
+impl SlashingProtectionSubcommand {
+    /// Run the command.
+    pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+        match self {
+            SlashingProtectionSubcommand::Export(cmd) => cmd.run(cli),
+            SlashingProtectionSubcommand::Import(cmd) => cmd.run(cli),
+        }
+    }
+}
+
+impl ExportSlashingProtectionCmd {
+    /// Run the command.
+    pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+        let (protection, chain) = open(cli, &self.shared_params)?;
+        let interchange = protection.export(chain).map_err(Error::Input)?;
+        let json = serde_json::to_string_pretty(&interchange)
+            .map_err(|e| Error::Input(format!("cannot serialize the database: {}", e)))?;
+        match &self.output {
+            Some(path) => fs::write(path, json)?,
+            None => println!("{}", json),
+        }
+        Ok(())
+    }
+}
+
+impl ImportSlashingProtectionCmd {
+    /// Run the command.
+    pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+        let (protection, chain) = open(cli, &self.shared_params)?;
+        let interchange: Interchange = serde_json::from_slice(&fs::read(&self.input)?)
+            .map_err(|e| Error::Input(format!("invalid slashing protection export: {}", e)))?;
+        if interchange.chain != chain {
+            return Err(Error::Input(format!(
+                "the export is of chain {}, not {}",
+                interchange.chain, chain
+            )));
+        }
+        let imported = protection.import(&interchange).map_err(Error::Input)?;
+        println!("Imported {} GRANDPA votes", imported);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = &[1; 32];
+
+    fn target(byte: u8, number: BlockNumber) -> (Hash, BlockNumber) {
+        (Hash::repeat_byte(byte), number)
+    }
+
+    fn open_tmp() -> (tempfile::TempDir, SlashingProtection) {
+        let dir = tempfile::tempdir().unwrap();
+        let protection = SlashingProtection::open(&database_path(dir.path())).unwrap();
+        (dir, protection)
+    }
+
+    #[test]
+    fn refuses_blocks_up_to_the_last_slot() {
+        let (_dir, protection) = open_tmp();
+        protection.check_block(5).unwrap();
+        assert!(protection.check_block(5).is_err(), "a repeated slot should be refused");
+        assert!(protection.check_block(4).is_err(), "an earlier slot should be refused");
+        protection.check_block(6).unwrap();
+    }
+
+    #[test]
+    fn allows_signing_the_same_vote_again() {
+        let (_dir, protection) = open_tmp();
+        protection.check_vote(KEY, 0, 1, "prevote", target(1, 10)).unwrap();
+        protection.check_vote(KEY, 0, 1, "prevote", target(1, 10)).unwrap();
+    }
+
+    #[test]
+    fn refuses_conflicting_votes() {
+        let (_dir, protection) = open_tmp();
+        protection.check_vote(KEY, 0, 1, "prevote", target(1, 10)).unwrap();
+        assert!(protection.check_vote(KEY, 0, 1, "prevote", target(2, 10)).is_err());
+        assert!(protection.check_vote(KEY, 0, 1, "prevote", target(1, 11)).is_err());
+        // the other stages and rounds, and the other keys, are not in conflict.
+        protection.check_vote(KEY, 0, 1, "precommit", target(2, 10)).unwrap();
+        protection.check_vote(KEY, 0, 2, "prevote", target(2, 10)).unwrap();
+        protection.check_vote(&[2; 32], 0, 1, "prevote", target(2, 10)).unwrap();
+    }
+
+    #[test]
+    fn refuses_votes_of_an_older_set() {
+        let (_dir, protection) = open_tmp();
+        protection.check_vote(KEY, 1, 1, "prevote", target(1, 10)).unwrap();
+        assert!(protection.check_vote(KEY, 0, 5, "prevote", target(1, 10)).is_err());
+        protection.check_vote(KEY, 2, 1, "prevote", target(1, 10)).unwrap();
+        assert!(protection.check_vote(KEY, 1, 2, "prevote", target(1, 10)).is_err());
+    }
+
+    #[test]
+    fn refuses_rounds_below_the_retained_window() {
+        let (_dir, protection) = open_tmp();
+        protection.check_vote(KEY, 0, 1, "prevote", target(1, 10)).unwrap();
+        let round = KEEP_ROUNDS + 2;
+        protection.check_vote(KEY, 0, round, "prevote", target(1, 10)).unwrap();
+        assert!(protection.check_vote(KEY, 0, 1, "prevote", target(1, 10)).is_err());
+        assert!(protection.check_vote(KEY, 0, 2, "prevote", target(1, 10)).is_err());
+        protection.check_vote(KEY, 0, round + 1, "prevote", target(1, 10)).unwrap();
+    }
+
+    #[test]
+    fn refuses_votes_conflicting_with_imported_ones() {
+        let (_dir, protection) = open_tmp();
+        protection.check_block(5).unwrap();
+        protection.check_vote(KEY, 0, 1, "precommit", target(1, 10)).unwrap();
+        let interchange = protection.export("dev".into()).unwrap();
+
+        let (_dir, other) = open_tmp();
+        assert_eq!(other.import(&interchange), Ok(1));
+        assert!(other.check_block(5).is_err());
+        assert!(other.check_vote(KEY, 0, 1, "precommit", target(2, 10)).is_err());
+        other.check_vote(KEY, 0, 1, "precommit", target(1, 10)).unwrap();
+    }
+}
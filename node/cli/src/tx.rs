@@ -0,0 +1,193 @@
+//! The `tx` subcommands, for signing transactions on machines without a connection to a node.
+//!
+//! `tx build` turns a JSON description of a call into an unsigned transaction, using the
+//! metadata of the native runtime to encode the arguments (see the `metadata` module):
+//!
+//! ```json
+//! {
+//!     "pallet": "Balances",
+//!     "call": "transfer",
+//!     "args": { "dest": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", "value": "1000000000000" }
+//! }
+//! ```
+//!
+//! Accounts are SS58 addresses or hex, integers are JSON numbers or strings, byte vectors are hex
+//! or plain text and nested calls are call descriptions themselves. An argument of a type the
+//! encoder does not know can be given as its SCALE encoding, as `{ "scale": "0x..." }`.
+//!
+//! The unsigned transaction holds everything a signature covers, so `tx sign` needs nothing but
+//! it and the key. `tx submit` hands the signed transaction to a node.
+
+use crate::cli::{BuildTxCmd, SignTxCmd, SubmitTxCmd, TxSubcommand};
+use crate::metadata::{parse_hash, parse_hex, NativeMetadata};
+use codec::{Decode, Encode};
+use jsonrpc_core_client::transports::http;
+use node_primitives::{Balance, Hash, Index};
+use node_runtime::{signed_extra, Address, Call, SignedPayload, UncheckedExtrinsic, VERSION};
+use sc_cli::{utils, with_crypto_scheme, Error};
+use sc_rpc_api::author::AuthorClient;
+use serde_json::Value;
+use sp_core::{crypto::SecretString, hexdisplay::HexDisplay, Bytes, Pair};
+use sp_runtime::{generic::Era, traits::IdentifyAccount, MultiSignature, MultiSigner};
+use std::{fs, path::Path};
+
+/// Everything the signature of a transaction covers.
+#[derive(Encode, Decode)]
+struct UnsignedTransaction {
+    call: Call,
+    era: Era,
+    #[codec(compact)]
+    nonce: Index,
+    #[codec(compact)]
+    tip: Balance,
+    spec_version: u32,
+    transaction_version: u32,
+    genesis_hash: Hash,
+    /// The hash of the block the era starts at, the genesis hash for immortal transactions.
+    block_hash: Hash,
+}
+
+impl UnsignedTransaction {
+    fn into_signed_payload(self) -> SignedPayload {
+        SignedPayload::from_raw(
+            self.call,
+            signed_extra(self.era, self.nonce, self.tip),
+            (
+                self.spec_version,
+                self.transaction_version,
+                self.genesis_hash,
+                self.block_hash,
+                (),
+                (),
+                (),
+            ),
+        )
+    }
+}
+
+impl TxSubcommand {
+    /// Run the command.
+    pub fn run(&self) -> Result<(), Error> {
+        match self {
+            TxSubcommand::Build(cmd) => cmd.run(),
+            TxSubcommand::Sign(cmd) => cmd.run(),
+            TxSubcommand::Submit(cmd) => cmd.run(),
+        }
+    }
+}
+
+impl BuildTxCmd {
+    /// Run the command.
+    pub fn run(&self) -> Result<(), Error> {
+        let description = if Path::new(&self.call).is_file() {
+            fs::read_to_string(&self.call)?
+        } else {
+            self.call.clone()
+        };
+        let description: Value = serde_json::from_str(&description)
+            .map_err(|e| Error::Input(format!("the call is not valid JSON: {}", e)))?;
+        let encoded = NativeMetadata::new()
+            .and_then(|encoder| encoder.encode_call(&description))
+            .map_err(Error::Input)?;
+        let mut input = &encoded[..];
+        let call = match Call::decode(&mut input) {
+            Ok(call) if input.is_empty() => call,
+            _ => return Err(Error::Input("the arguments do not make a valid call".into())),
+        };
+
+        let genesis_hash = parse_hash("the genesis hash", &self.genesis_hash).map_err(Error::Input)?;
+        let (era, block_hash) = match (self.era_period, self.block_number, &self.block_hash) {
+            (Some(period), Some(number), Some(hash)) => (
+                Era::mortal(period, number),
+                parse_hash("the block hash", hash).map_err(Error::Input)?,
+            ),
+            _ => (Era::Immortal, genesis_hash),
+        };
+
+        let transaction = UnsignedTransaction {
+            call,
+            era,
+            nonce: self.nonce,
+            tip: self.tip,
+            spec_version: self.spec_version.unwrap_or(VERSION.spec_version),
+            transaction_version: self.transaction_version.unwrap_or(VERSION.transaction_version),
+            genesis_hash,
+            block_hash,
+        };
+        println!("0x{}", HexDisplay::from(&transaction.encode()));
+        Ok(())
+    }
+}
+
+/// Decode a hex encoded transaction given as is or as the path of a file holding it, which
+/// transactions too long for the command line need.
+fn parse_transaction(value: &str) -> Result<Vec<u8>, Error> {
+    if Path::new(value).is_file() {
+        let value = fs::read_to_string(value)?;
+        parse_hex("the transaction", value.trim()).map_err(Error::Input)
+    } else {
+        parse_hex("the transaction", value).map_err(Error::Input)
+    }
+}
+
+fn sign<P: Pair>(
+    suri: &str,
+    password: Option<SecretString>,
+    payload: SignedPayload,
+) -> UncheckedExtrinsic
+where
+    P::Public: Into<MultiSigner>,
+    P::Signature: Into<MultiSignature>,
+{
+    let pair = utils::pair_from_suri::<P>(suri, password);
+    let signature = payload.using_encoded(|payload| pair.sign(payload));
+    let signer = pair.public().into().into_account();
+    let (call, extra, _) = payload.deconstruct();
+    UncheckedExtrinsic::new_signed(call, Address::Id(signer), signature.into(), extra)
+}
+
+impl SignTxCmd {
+    /// Run the command.
+    pub fn run(&self) -> Result<(), Error> {
+        let encoded = parse_transaction(&self.transaction)?;
+        let transaction = UnsignedTransaction::decode(&mut &encoded[..])
+            .map_err(|e| Error::Input(format!("cannot decode the transaction: {}", e)))?;
+        // what is signed goes to stderr, for a last look before the signature is used.
+        eprintln!("Call: {:?}", transaction.call);
+        eprintln!(
+            "Nonce: {}, tip: {}, era: {:?}, spec version: {}, transaction version: {}",
+            transaction.nonce,
+            transaction.tip,
+            transaction.era,
+            transaction.spec_version,
+            transaction.transaction_version,
+        );
+
+        let suri = utils::read_uri(self.suri.as_ref())?;
+        let password = self.keystore_params.read_password()?;
+        let payload = transaction.into_signed_payload();
+        let extrinsic = with_crypto_scheme!(
+            self.crypto_scheme.scheme,
+            sign(&suri, password, payload)
+        );
+        println!("0x{}", HexDisplay::from(&extrinsic.encode()));
+        Ok(())
+    }
+}
+
+impl SubmitTxCmd {
+    /// Run the command.
+    pub fn run(&self) -> Result<(), Error> {
+        let extrinsic = parse_transaction(&self.transaction)?;
+        UncheckedExtrinsic::decode(&mut &extrinsic[..])
+            .map_err(|e| Error::Input(format!("cannot decode the transaction: {}", e)))?;
+
+        let hash = futures::executor::block_on(async {
+            let client: AuthorClient<Hash, Hash> = http::connect(&self.url).await?;
+            client.submit_extrinsic(Bytes(extrinsic)).await
+        })
+        .map_err(|e| Error::Input(format!("cannot submit to {}: {}", self.url, e)))?;
+        println!("{:?}", hash);
+        Ok(())
+    }
+}
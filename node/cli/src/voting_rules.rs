@@ -0,0 +1,141 @@
+//! The GRANDPA voting rules of a full node, composed from the command line
+//! (`--grandpa-voting-rule`).
+//!
+//! Besides the rules of `sc_finality_grandpa`, `runtime-upgrade-delay:<N>` keeps the voter from
+//! finalizing a block that changed the runtime code until `N` blocks were built on top of it, so
+//! that a broken upgrade can still be abandoned by switching to another fork.
+
+use crate::service::FullClient;
+use futures::future;
+use grandpa::{
+    BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRuleResult,
+    VotingRulesBuilder,
+};
+use node_primitives::{Block, BlockNumber};
+use sc_client_api::StorageProvider;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{well_known_keys, StorageKey};
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, Header as _},
+};
+use std::{str::FromStr, sync::Arc};
+
+/// A GRANDPA voting rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrandpaVotingRule {
+    /// `before-best:<N>`: vote for a block at least `N` blocks below the best block.
+    BeforeBestBlockBy(BlockNumber),
+    /// `three-quarters`: vote for a block at most three quarters of the way from the last
+    /// finalized block to the best block.
+    ThreeQuartersOfTheUnfinalizedChain,
+    /// `runtime-upgrade-delay:<N>`: do not vote for a block that changed the runtime code until
+    /// `N` blocks were built on top of it.
+    RuntimeUpgradeDelay(BlockNumber),
+}
+
+impl FromStr for GrandpaVotingRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (name, blocks) = match s.find(':') {
+            Some(colon) => (&s[..colon], Some(&s[colon + 1..])),
+            None => (s, None),
+        };
+        let parse_blocks = || {
+            blocks
+                .ok_or_else(|| format!("the voting rule {} needs a number of blocks", name))?
+                .parse::<BlockNumber>()
+                .map_err(|e| format!("invalid number of blocks in {}: {}", s, e))
+        };
+        match name {
+            "before-best" => Ok(GrandpaVotingRule::BeforeBestBlockBy(parse_blocks()?)),
+            "three-quarters" if blocks.is_none() => {
+                Ok(GrandpaVotingRule::ThreeQuartersOfTheUnfinalizedChain)
+            }
+            "runtime-upgrade-delay" => Ok(GrandpaVotingRule::RuntimeUpgradeDelay(parse_blocks()?)),
+            _ => Err(format!(
+                "unknown voting rule {}, expected before-best:<N>, three-quarters or \
+                 runtime-upgrade-delay:<N>",
+                s
+            )),
+        }
+    }
+}
+
+/// The voting rules in the given order, or those of `VotingRulesBuilder::default` (two blocks
+/// below the best block, and three quarters of the unfinalized chain) without any.
+pub fn voting_rules(rules: Option<&[GrandpaVotingRule]>) -> VotingRulesBuilder<Block, FullClient> {
+    let rules = match rules {
+        Some(rules) => rules,
+        None => return VotingRulesBuilder::default(),
+    };
+    rules.iter().fold(VotingRulesBuilder::new(), |builder, rule| match *rule {
+        GrandpaVotingRule::BeforeBestBlockBy(blocks) => builder.add(BeforeBestBlockBy(blocks)),
+        GrandpaVotingRule::ThreeQuartersOfTheUnfinalizedChain => {
+            builder.add(ThreeQuartersOfTheUnfinalizedChain)
+        }
+        GrandpaVotingRule::RuntimeUpgradeDelay(blocks) => builder.add(RuntimeUpgradeDelay(blocks)),
+    })
+}
+
+/// Restricts the vote to below the first unfinalized block that changed the runtime code until
+/// the best block is the given number of blocks past it.
+#[derive(Clone)]
+pub struct RuntimeUpgradeDelay(BlockNumber);
+
+impl VotingRule<Block, FullClient> for RuntimeUpgradeDelay {
+    fn restrict_vote(
+        &self,
+        backend: Arc<FullClient>,
+        base: &<Block as BlockT>::Header,
+        best_target: &<Block as BlockT>::Header,
+        current_target: &<Block as BlockT>::Header,
+    ) -> VotingRuleResult<Block> {
+        let code_hash = |hash| {
+            backend
+                .storage_hash(&BlockId::Hash(hash), &StorageKey(well_known_keys::CODE.to_vec()))
+                .ok()
+        };
+        // walk down to the base, so that the lowest upgrade is the one the vote stays below.
+        let mut restricted = None;
+        let mut header = current_target.clone();
+        while header.number() > base.number() {
+            let parent = match backend.header(BlockId::Hash(*header.parent_hash())) {
+                Ok(Some(parent)) => parent,
+                _ => break,
+            };
+            let upgraded = code_hash(header.hash()) != code_hash(parent.hash());
+            if upgraded && *best_target.number() < header.number() + self.0 {
+                restricted = Some((parent.hash(), *parent.number()));
+            }
+            header = parent;
+        }
+        Box::pin(future::ready(restricted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GrandpaVotingRule;
+
+    #[test]
+    fn parses_voting_rules() {
+        assert_eq!("before-best:5".parse(), Ok(GrandpaVotingRule::BeforeBestBlockBy(5)));
+        assert_eq!(
+            "three-quarters".parse(),
+            Ok(GrandpaVotingRule::ThreeQuartersOfTheUnfinalizedChain)
+        );
+        assert_eq!(
+            "runtime-upgrade-delay:100".parse(),
+            Ok(GrandpaVotingRule::RuntimeUpgradeDelay(100))
+        );
+    }
+
+    #[test]
+    fn refuses_invalid_voting_rules() {
+        for rule in &["three-quarters:5", "before-best", "before-best:five", "half-way"] {
+            assert!(rule.parse::<GrandpaVotingRule>().is_err(), "{} should be refused", rule);
+        }
+    }
+}
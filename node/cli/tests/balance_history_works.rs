@@ -0,0 +1,83 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use std::convert::TryInto;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+pub mod common;
+
+const RPC_PORT: &str = "9959";
+
+const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+#[test]
+fn balance_history_works() {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"))
+        .args(&[
+            "--dev",
+            "--tmp",
+            "--pruning",
+            "archive",
+            "--rpc-port",
+            RPC_PORT,
+        ])
+        .spawn()
+        .unwrap();
+
+    // Let it finalize some blocks.
+    thread::sleep(Duration::from_secs(30));
+    assert!(
+        cmd.try_wait().unwrap().is_none(),
+        "the process should still be running"
+    );
+
+    let at_number = common::rpc(
+        RPC_PORT,
+        "balances_accountAt",
+        &format!(r#"["{}", {{"number": 1}}]"#, ALICE),
+    );
+    let at_genesis = common::rpc(
+        RPC_PORT,
+        "balances_accountAt",
+        &format!(r#"["{}", {{"timestamp": 0}}, 0]"#, ALICE),
+    );
+    let in_the_future = common::rpc(
+        RPC_PORT,
+        "balances_accountAt",
+        &format!(r#"["{}", {{"timestamp": 99999999999999}}]"#, ALICE),
+    );
+
+    kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+    assert!(common::wait_for(&mut cmd, 40)
+        .map(|x| x.success())
+        .unwrap_or_default());
+
+    assert!(at_number.contains(r#""blockNumber":1,"#), "{}", at_number);
+    assert!(at_number.contains(r#""free":"#));
+    assert!(at_number.contains(r#""asset":null"#));
+    assert!(at_genesis.contains(r#""blockNumber":0,"#), "{}", at_genesis);
+    assert!(at_genesis.contains(r#""timestamp":0,"#));
+    assert!(at_genesis.contains(r#""asset":{"id":0,"#));
+    assert!(in_the_future.contains(r#""error""#));
+}
@@ -0,0 +1,82 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Runs in a headless browser against a dev node listening for WebSocket connections with a known
+// peer id, from the checkpoint of that node. The node is started once to finalize a few blocks,
+// stopped to export the checkpoint, and started again:
+//
+// coinnet-node --dev -d /tmp/coinnet-checkpoint
+// coinnet-node export-checkpoint --dev -d /tmp/coinnet-checkpoint --output /tmp/checkpoint.json
+// coinnet-node --dev -d /tmp/coinnet-checkpoint \
+//     --node-key 0000000000000000000000000000000000000000000000000000000000000001 \
+//     --listen-addr /ip4/127.0.0.1/tcp/30333/ws
+//
+// CHECKPOINT=/tmp/checkpoint.json wasm-pack test --headless --chrome --release \
+//     --no-default-features --features browser -- --test browser_checkpoint_works
+
+#![cfg(target_arch = "wasm32")]
+
+use codec::Decode;
+use coinnet_node::{start_client, subscribe_finalized_heads};
+use futures::{channel::mpsc, StreamExt};
+use node_primitives::Header;
+use serde_json::Value;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const BOOT_NODE: &str =
+	"/ip4/127.0.0.1/tcp/30333/ws/p2p/12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp";
+
+const CHECKPOINT: &str = include_str!(env!("CHECKPOINT"));
+
+fn json(value: JsValue) -> Value {
+	value.into_serde().unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn light_client_starts_from_checkpoint() {
+	let mut spec: Value = serde_json::from_str(CHECKPOINT).unwrap();
+	let header = spec["lightSyncCheckpoint"]["finalizedBlockHeader"].as_str().unwrap();
+	let header = sp_core::bytes::from_hex(header).unwrap();
+	let checkpoint = u64::from(Header::decode(&mut &header[..]).unwrap().number);
+	spec["bootNodes"] = serde_json::json!([BOOT_NODE]);
+	let mut client = start_client(Some(spec.to_string()), "info".into()).await.unwrap();
+
+	// the light client is at the checkpoint before syncing anything.
+	let request = serde_json::json!({
+		"jsonrpc": "2.0", "id": 1, "method": "chain_getHeader", "params": [],
+	});
+	let response = JsFuture::from(client.rpc_send(&request.to_string())).await.unwrap();
+	let response: Value = serde_json::from_str(&response.as_string().unwrap()).unwrap();
+	let best = response["result"]["number"].as_str().unwrap();
+	assert_eq!(u64::from_str_radix(best.trim_start_matches("0x"), 16).unwrap(), checkpoint);
+
+	// and follows the finalization of the dev node from there.
+	let (heads_tx, mut heads) = mpsc::unbounded();
+	let on_head = Closure::wrap(Box::new(move |head: JsValue| {
+		let _ = heads_tx.unbounded_send(json(head));
+	}) as Box<dyn FnMut(JsValue)>);
+	subscribe_finalized_heads(&mut client, on_head.into_js_value().unchecked_into());
+	let first = heads.next().await.unwrap()["number"].as_u64().unwrap();
+	assert!(first >= checkpoint);
+	let next = heads.next().await.unwrap()["number"].as_u64().unwrap();
+	assert!(next > first);
+}
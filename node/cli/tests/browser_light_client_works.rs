@@ -0,0 +1,84 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+// Runs in a headless browser against a dev node listening for WebSocket connections with a known
+// peer id, started beforehand:
+//
+// coinnet-node --dev --tmp --node-key 0000000000000000000000000000000000000000000000000000000000000001 \
+//     --listen-addr /ip4/127.0.0.1/tcp/30333/ws
+//
+// wasm-pack test --headless --chrome --release --no-default-features --features browser \
+//     -- --test browser_light_client_works
+
+#![cfg(target_arch = "wasm32")]
+
+use futures::{channel::mpsc, StreamExt};
+use coinnet_node::{
+	chain_spec, get_asset_balances, get_balance, start_client, submit_extrinsic,
+	subscribe_finalized_heads,
+};
+use serde_json::Value;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const BOOT_NODE: &str =
+	"/ip4/127.0.0.1/tcp/30333/ws/p2p/12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp";
+
+const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+fn json(value: JsValue) -> Value {
+	value.into_serde().unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn light_client_helpers_work() {
+	let spec = chain_spec::development_config().as_json(false).unwrap();
+	let mut spec: Value = serde_json::from_str(&spec).unwrap();
+	spec["bootNodes"] = serde_json::json!([BOOT_NODE]);
+	let mut client = start_client(Some(spec.to_string()), "info".into()).await.unwrap();
+
+	// the light client follows the finalization of the dev node.
+	let (heads_tx, mut heads) = mpsc::unbounded();
+	let on_head = Closure::wrap(Box::new(move |head: JsValue| {
+		let _ = heads_tx.unbounded_send(json(head));
+	}) as Box<dyn FnMut(JsValue)>);
+	subscribe_finalized_heads(&mut client, on_head.into_js_value().unchecked_into());
+	loop {
+		let head = heads.next().await.unwrap();
+		assert!(head["hash"].as_str().unwrap().starts_with("0x"));
+		if head["number"].as_u64().unwrap() > 0 {
+			break;
+		}
+	}
+
+	let balance = json(JsFuture::from(get_balance(&mut client, ALICE.into())).await.unwrap());
+	assert!(balance["free"].as_str().unwrap().parse::<u128>().unwrap() > 0);
+
+	let assets = json(
+		JsFuture::from(get_asset_balances(&mut client, ALICE.into(), vec![1])).await.unwrap(),
+	);
+	assert_eq!(assets[0]["id"], 1);
+	assert_eq!(assets[0]["balance"], "0");
+	assert_eq!(assets[0]["symbol"], Value::Null);
+
+	assert!(JsFuture::from(get_balance(&mut client, "not an address".into())).await.is_err());
+	assert!(JsFuture::from(submit_extrinsic(&mut client, "0x00".into())).await.is_err());
+}
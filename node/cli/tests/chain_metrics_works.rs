@@ -0,0 +1,66 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use std::convert::TryInto;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+pub mod common;
+
+const PROMETHEUS_PORT: &str = "9655";
+
+#[test]
+fn chain_metrics_works() {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"))
+        .args(&["--dev", "--tmp", "--prometheus-port", PROMETHEUS_PORT])
+        .spawn()
+        .unwrap();
+
+    // Let it finalize some blocks.
+    thread::sleep(Duration::from_secs(30));
+    assert!(
+        cmd.try_wait().unwrap().is_none(),
+        "the process should still be running"
+    );
+
+    let mut stream = TcpStream::connect(format!("127.0.0.1:{}", PROMETHEUS_PORT)).unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut metrics = String::new();
+    stream.read_to_string(&mut metrics).unwrap();
+
+    kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+    assert!(common::wait_for(&mut cmd, 40)
+        .map(|x| x.success())
+        .unwrap_or_default());
+
+    // every finalized block has a timestamp inherent.
+    assert!(metrics.contains("coinnet_extrinsics_total{pallet=\"Timestamp\"}"));
+    assert!(metrics.contains("coinnet_block_extrinsics{pallet=\"Timestamp\"} 1"));
+    assert!(metrics.contains("coinnet_fee_multiplier "));
+    assert!(metrics.contains("coinnet_treasury_balance "));
+    assert!(metrics.contains("coinnet_active_era 0"));
+}
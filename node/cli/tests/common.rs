@@ -23,6 +23,9 @@ use nix::sys::signal::{kill, Signal::SIGINT};
 use nix::unistd::Pid;
 use std::{convert::TryInto, process::Command};
 use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    panic,
     path::Path,
     process::{Child, ExitStatus},
     thread,
@@ -53,25 +56,65 @@ pub fn wait_for(child: &mut Child, secs: usize) -> Option<ExitStatus> {
 
 /// Run the node for a while (30 seconds)
 pub fn run_dev_node_for_a_while(base_path: &Path) {
-    let mut cmd = Command::new(cargo_bin("coinnet-node"));
+    run_dev_node_with_args_for_a_while(base_path, &[])
+}
 
-    let mut cmd = cmd
-        .args(&["--dev"])
-        .arg("-d")
-        .arg(base_path)
-        .spawn()
-        .unwrap();
+/// Run the node with the given extra `args` for a while (30 seconds)
+pub fn run_dev_node_with_args_for_a_while(base_path: &Path, args: &[&str]) {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"));
+    cmd.args(&["--dev"]).args(args).arg("-d").arg(base_path);
 
     // Let it produce some blocks.
-    thread::sleep(Duration::from_secs(30));
+    run_node_for_a_while(&mut cmd, 30, || ())
+}
+
+/// Start the node of `cmd`, let it run for `secs` seconds, call `f` while it is still running
+/// and stop it, also when `f` panics.
+///
+/// Returns what `f` returned.
+pub fn run_node_for_a_while<T>(cmd: &mut Command, secs: u64, f: impl FnOnce() -> T) -> T {
+    let mut child = cmd.spawn().unwrap();
+    thread::sleep(Duration::from_secs(secs));
     assert!(
-        cmd.try_wait().unwrap().is_none(),
+        child.try_wait().unwrap().is_none(),
         "the process should still be running"
     );
 
-    // Stop the process
-    kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
-    assert!(wait_for(&mut cmd, 40)
-        .map(|x| x.success())
-        .unwrap_or_default());
+    let output = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    stop(&mut child);
+    output.unwrap_or_else(|e| panic::resume_unwind(e))
+}
+
+/// Interrupt the node `child` runs and check that it exits gracefully.
+pub fn stop(child: &mut Child) {
+    kill(Pid::from_raw(child.id().try_into().unwrap()), SIGINT).unwrap();
+    assert!(wait_for(child, 40).map(|x| x.success()).unwrap_or_default());
+}
+
+/// Call the RPC `method` of the node listening on `port` over HTTP.
+///
+/// Returns the whole HTTP response.
+pub fn rpc(port: &str, method: &str, params: &str) -> String {
+    let body = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"{}","params":{}}}"#,
+        method, params
+    );
+    let mut stream = TcpStream::connect(format!("127.0.0.1:{}", port)).unwrap();
+    write!(
+        stream,
+        "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// The result of an RPC call, without the HTTP headers of the response.
+pub fn result(response: String) -> serde_json::Value {
+    let body = response.split("\r\n\r\n").last().unwrap();
+    serde_json::from_str::<serde_json::Value>(body).unwrap()["result"].take()
 }
@@ -0,0 +1,90 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use std::convert::TryInto;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+pub mod common;
+
+const RPC_PORT: &str = "9958";
+
+const TRANSFER: &str = r#"{"pallet": "Balances", "call": "transfer", "args": {"dest": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", "value": "1000000000000"}}"#;
+
+#[test]
+fn dry_run_detailed_works() {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"))
+        .args(&["--dev", "--tmp", "--rpc-port", RPC_PORT])
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_secs(10));
+    assert!(
+        cmd.try_wait().unwrap().is_none(),
+        "the process should still be running"
+    );
+
+    // a transfer from Alice, signed for the genesis block of this dev chain.
+    let genesis = common::rpc(RPC_PORT, "chain_getBlockHash", "[0]");
+    let genesis = genesis
+        .split("\"result\":\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap()
+        .to_string();
+    let build = Command::new(cargo_bin("coinnet-node"))
+        .args(&["tx", "build", "--call", TRANSFER, "--nonce", "0"])
+        .args(&["--genesis-hash", &genesis])
+        .output()
+        .unwrap();
+    assert!(build.status.success());
+    let unsigned = String::from_utf8(build.stdout).unwrap();
+    let sign = Command::new(cargo_bin("coinnet-node"))
+        .args(&["tx", "sign", unsigned.trim(), "--suri", "//Alice"])
+        .output()
+        .unwrap();
+    assert!(sign.status.success());
+    let signed = String::from_utf8(sign.stdout).unwrap();
+
+    let dry_run = common::rpc(
+        RPC_PORT,
+        "system_dryRunDetailed",
+        &format!(r#"["{}"]"#, signed.trim()),
+    );
+    let not_an_extrinsic = common::rpc(RPC_PORT, "system_dryRunDetailed", r#"["0x00"]"#);
+
+    kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+    assert!(common::wait_for(&mut cmd, 40)
+        .map(|x| x.success())
+        .unwrap_or_default());
+
+    assert!(
+        dry_run.contains(r#""result":{"Ok":{"Ok":null}}"#),
+        "{}",
+        dry_run
+    );
+    assert!(dry_run.contains(r#""event":"Transfer""#));
+    assert!(dry_run.contains(r#""event":"ExtrinsicSuccess""#));
+    assert!(dry_run.contains(r#""changes":[{"key":"#));
+    assert!(not_an_extrinsic.contains(r#""error""#));
+}
@@ -0,0 +1,85 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use std::convert::TryInto;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+pub mod common;
+
+const RPC_PORT: &str = "9957";
+
+/// `Balances.transfer` of 1000 to Alice.
+const TRANSFER: &str =
+    "0x060000d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27da10f";
+
+#[test]
+fn estimate_fee_works() {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"))
+        .args(&["--dev", "--tmp", "--rpc-port", RPC_PORT])
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_secs(10));
+    assert!(
+        cmd.try_wait().unwrap().is_none(),
+        "the process should still be running"
+    );
+
+    let next = common::rpc(
+        RPC_PORT,
+        "payment_estimateFee",
+        &format!(r#"["{}", null, 0]"#, TRANSFER),
+    );
+    let ahead = common::rpc(
+        RPC_PORT,
+        "payment_estimateFee",
+        &format!(r#"["{}", 200, 100, 1000]"#, TRANSFER),
+    );
+    let too_far = common::rpc(
+        RPC_PORT,
+        "payment_estimateFee",
+        &format!(r#"["{}", null, 1000000]"#, TRANSFER),
+    );
+    let not_a_call = common::rpc(RPC_PORT, "payment_estimateFee", r#"["0xffff", null, 0]"#);
+
+    kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+    assert!(common::wait_for(&mut cmd, 40)
+        .map(|x| x.success())
+        .unwrap_or_default());
+
+    for field in &[
+        "\"low\"",
+        "\"expected\"",
+        "\"high\"",
+        "\"baseFee\"",
+        "\"adjustedWeightFee\"",
+    ] {
+        assert!(next.contains(field), "{} in {}", field, next);
+    }
+    assert!(next.contains(r#""class":"normal""#));
+    assert!(ahead.contains(r#""len":200"#));
+    assert!(ahead.contains(r#""tip":"0x3e8""#));
+    assert!(too_far.contains(r#""error""#));
+    assert!(not_a_call.contains(r#""error""#));
+}
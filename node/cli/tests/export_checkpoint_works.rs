@@ -0,0 +1,80 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use codec::Decode;
+use node_primitives::Header;
+use sp_runtime::traits::Header as _;
+use std::{fs, process::Command};
+use tempfile::tempdir;
+
+pub mod common;
+
+const RPC_PORT: &str = "9966";
+
+/// A SCALE-encoded value of the checkpoint.
+fn decode<T: Decode>(value: &serde_json::Value) -> T {
+    let encoded = sp_core::bytes::from_hex(value.as_str().unwrap()).unwrap();
+    T::decode(&mut &encoded[..]).unwrap()
+}
+
+#[test]
+fn export_checkpoint_works() {
+    let base_path = tempdir().expect("could not create a temp dir");
+
+    common::run_dev_node_for_a_while(base_path.path());
+
+    let spec_path = base_path.path().join("checkpoint.json");
+    let status = Command::new(cargo_bin("coinnet-node"))
+        .args(&["export-checkpoint", "--dev", "-d"])
+        .arg(base_path.path())
+        .arg("--output")
+        .arg(&spec_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let spec: serde_json::Value = serde_json::from_slice(&fs::read(&spec_path).unwrap()).unwrap();
+    let checkpoint = &spec["lightSyncCheckpoint"];
+    let header: Header = decode(&checkpoint["finalizedBlockHeader"]);
+    assert!(*header.number() > 0, "a block should have been finalized");
+    assert!(checkpoint["babeEpochChanges"].is_string());
+    assert!(checkpoint["grandpaAuthoritySet"].is_string());
+
+    // a light client starts from the checkpoint.
+    let mut light = Command::new(cargo_bin("coinnet-node"));
+    light
+        .args(&["--light", "--tmp", "--rpc-port", RPC_PORT, "--chain"])
+        .arg(&spec_path);
+    let (finalized_head, finalized, best) = common::run_node_for_a_while(&mut light, 10, || {
+        let finalized_head = common::result(common::rpc(RPC_PORT, "chain_getFinalizedHead", "[]"));
+        let finalized = common::result(common::rpc(
+            RPC_PORT,
+            "chain_getHeader",
+            &format!("[{}]", finalized_head),
+        ));
+        let best = common::result(common::rpc(RPC_PORT, "chain_getHeader", "[]"));
+        (finalized_head, finalized, best)
+    });
+
+    assert_eq!(finalized_head, format!("{:?}", header.hash()));
+    assert_eq!(finalized["number"], format!("{:#x}", header.number()));
+    assert_eq!(best["number"], format!("{:#x}", header.number()));
+}
@@ -0,0 +1,129 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use assert_cmd::cargo::cargo_bin;
+use std::{fs, path::Path, process::Command};
+use tempfile::tempdir;
+
+const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+const ALICE_STASH: &str = "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY";
+const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+const ALICE_GRANDPA: &str = "0x88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee";
+
+fn network_config(stash: &str, bond: &str) -> String {
+    format!(
+        r#"
+name = "Generated Testnet"
+id = "generated_testnet"
+chain_type = "Local"
+sudo = "{alice}"
+technical_committee = ["{alice}"]
+
+[properties]
+tokenSymbol = "1CN"
+tokenDecimals = 12
+
+[[validators]]
+stash = "{stash}"
+controller = "{alice}"
+bond = "{bond}"
+grandpa = "{grandpa}"
+babe = "{alice}"
+im_online = "{alice}"
+authority_discovery = "{alice}"
+
+[[endowments]]
+account = "{alice_stash}"
+balance = "10000000000000000000"
+
+[[endowments]]
+account = "{bob}"
+balance = "10000000000000000"
+
+[[council]]
+account = "{bob}"
+stake = 1000000000000
+
+[[assets]]
+id = 1
+owner = "{alice}"
+is_sufficient = true
+min_balance = 1
+name = "Dollar"
+symbol = "USD"
+decimals = 6
+balances = [{{ account = "{alice}", balance = 1000000 }}]
+"#,
+        alice = ALICE,
+        alice_stash = ALICE_STASH,
+        bob = BOB,
+        grandpa = ALICE_GRANDPA,
+        stash = stash,
+        bond = bond,
+    )
+}
+
+fn generate_spec(config: &str, output: &Path) -> std::process::Output {
+    let config_path = output.join("network.toml");
+    fs::write(&config_path, config).unwrap();
+
+    Command::new(cargo_bin("coinnet-node"))
+        .args(&["generate-spec", "--config"])
+        .arg(&config_path)
+        .arg("--output")
+        .arg(output)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn generate_spec_works() {
+    let output = tempdir().expect("could not create a temp dir");
+
+    let result = generate_spec(
+        &network_config(ALICE_STASH, "10000000000000000"),
+        output.path(),
+    );
+    assert!(result.status.success());
+
+    let spec: serde_json::Value = serde_json::from_slice(
+        &fs::read(output.path().join("generated_testnet.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(spec["id"], "generated_testnet");
+    assert_eq!(spec["properties"]["tokenSymbol"], "1CN");
+    assert!(spec["genesis"]["runtime"].is_object());
+
+    let raw: serde_json::Value = serde_json::from_slice(
+        &fs::read(output.path().join("generated_testnet-raw.json")).unwrap(),
+    )
+    .unwrap();
+    assert!(raw["genesis"]["raw"]["top"].is_object());
+}
+
+#[test]
+fn generate_spec_rejects_invalid_configs() {
+    let output = tempdir().expect("could not create a temp dir");
+
+    // Bob is endowed with less than the bond.
+    let result = generate_spec(&network_config(BOB, "100000000000000000"), output.path());
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("validators[0].bond"));
+    assert!(!output.path().join("generated_testnet.json").exists());
+}
@@ -0,0 +1,63 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+
+pub mod common;
+
+const RPC_PORT: &str = "9965";
+
+/// Run a dev node with the given extra arguments for a while and return whether it kept the
+/// justification of the last block it finalized.
+fn finalized_block_has_justification(args: &[&str]) -> bool {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"));
+    cmd.args(&["--dev", "--tmp", "--rpc-port", RPC_PORT])
+        .args(args);
+
+    // Let it produce and finalize some blocks.
+    let block = common::run_node_for_a_while(&mut cmd, 30, || {
+        let finalized_head = common::result(common::rpc(RPC_PORT, "chain_getFinalizedHead", "[]"));
+        common::result(common::rpc(
+            RPC_PORT,
+            "chain_getBlock",
+            &format!("[{}]", finalized_head),
+        ))
+    });
+
+    assert_ne!(
+        block["block"]["header"]["number"], "0x0",
+        "a block should have been finalized"
+    );
+    !block["justifications"].is_null()
+}
+
+#[test]
+fn grandpa_justification_period_works() {
+    // the voter keeps the justification of a block it finalizes when the block is in another
+    // period than the last finalized block, which every block is with a period of one block.
+    assert!(finalized_block_has_justification(&[
+        "--grandpa-justification-period",
+        "1"
+    ]));
+
+    // no block of the first 512 needs one with the default period.
+    assert!(!finalized_block_has_justification(&[]));
+}
@@ -0,0 +1,235 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::fs::File;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+pub mod network;
+
+const RPC_PORT: &str = "9961";
+
+/// The block number of a header.
+fn number(header: serde_json::Value) -> u64 {
+    let number = header["number"].as_str().unwrap();
+    u64::from_str_radix(number.trim_start_matches("0x"), 16).unwrap()
+}
+
+/// Run a dev node with the given voting rules for a while and return its best and finalized
+/// block numbers.
+fn best_and_finalized(rules: &[&str]) -> (u64, u64) {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"));
+    cmd.args(&["--dev", "--tmp", "--rpc-port", RPC_PORT]);
+    for rule in rules {
+        cmd.args(&["--grandpa-voting-rule", rule]);
+    }
+
+    // Let it produce some blocks.
+    let (best, finalized) = common::run_node_for_a_while(&mut cmd, 30, || {
+        let finalized_head = common::result(common::rpc(RPC_PORT, "chain_getFinalizedHead", "[]"));
+        let finalized = common::rpc(
+            RPC_PORT,
+            "chain_getHeader",
+            &format!("[{}]", finalized_head),
+        );
+        (common::rpc(RPC_PORT, "chain_getHeader", "[]"), finalized)
+    });
+
+    (
+        number(common::result(best)),
+        number(common::result(finalized)),
+    )
+}
+
+/// The spec version of the runtime of the dev node at a block.
+fn spec_version_at(number: u64) -> u64 {
+    let hash = common::result(common::rpc(
+        RPC_PORT,
+        "chain_getBlockHash",
+        &format!("[{}]", number),
+    ));
+    let version = common::rpc(RPC_PORT, "state_getRuntimeVersion", &format!("[{}]", hash));
+    common::result(version)["specVersion"].as_u64().unwrap()
+}
+
+/// Upgrade the runtime of a dev node voting with `runtime-upgrade-delay:<delay>`, and check that
+/// the upgrade is only finalized once `delay` blocks were built on it.
+fn upgrade_delay_holds_back_finality(delay: u64) {
+    let spec_version = node_runtime::VERSION.spec_version;
+    let upgrade = network::build_runtime("upgrade-test-runtime");
+    let dir = tempdir().unwrap();
+    let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+
+    let mut cmd = Command::new(cargo_bin("coinnet-node"));
+    cmd.args(&["--dev", "--tmp", "--rpc-port", RPC_PORT])
+        .args(&["--grandpa-voting-rule", "three-quarters"])
+        .args(&[
+            "--grandpa-voting-rule",
+            &format!("runtime-upgrade-delay:{}", delay),
+        ]);
+    common::run_node_for_a_while(&mut cmd, 10, || {
+        // Alice, the sudo key of the dev chain, enacts the upgrade.
+        let url = format!("http://127.0.0.1:{}", RPC_PORT);
+        let genesis_hash = common::result(common::rpc(RPC_PORT, "chain_getBlockHash", "[0]"));
+        let genesis_hash = genesis_hash.as_str().unwrap();
+        let steps: [&[&str]; 4] = [
+            &[
+                "runtime-upgrade",
+                "--wasm",
+                upgrade.to_str().unwrap(),
+                "--origin",
+                "sudo",
+            ],
+            &[
+                "tx",
+                "build",
+                "--call",
+                &path("0"),
+                "--nonce",
+                "0",
+                "--genesis-hash",
+                genesis_hash,
+            ],
+            &["tx", "sign", &path("1"), "--suri", "//Alice"],
+            &["tx", "submit", &path("2"), "--url", &url],
+        ];
+        for (step, args) in steps.iter().enumerate() {
+            let status = Command::new(cargo_bin("coinnet-node"))
+                .args(*args)
+                .stdout(File::create(path(&step.to_string())).unwrap())
+                .status()
+                .unwrap();
+            assert!(status.success(), "{} failed", args[0]);
+        }
+
+        // find the block that changed the runtime.
+        let mut best = 0;
+        for _ in 0..60 {
+            thread::sleep(Duration::from_secs(1));
+            best = number(common::result(common::rpc(
+                RPC_PORT,
+                "chain_getHeader",
+                "[]",
+            )));
+            if spec_version_at(best) > spec_version as u64 {
+                break;
+            }
+        }
+        assert!(
+            spec_version_at(best) > spec_version as u64,
+            "the runtime should have been upgraded"
+        );
+        let mut upgraded = best;
+        while spec_version_at(upgraded - 1) > spec_version as u64 {
+            upgraded -= 1;
+        }
+
+        // the upgrade stays unfinalized until `delay` blocks were built on it.
+        let finalized = || {
+            let finalized_head =
+                common::result(common::rpc(RPC_PORT, "chain_getFinalizedHead", "[]"));
+            number(common::result(common::rpc(
+                RPC_PORT,
+                "chain_getHeader",
+                &format!("[{}]", finalized_head),
+            )))
+        };
+        loop {
+            let finalized = finalized();
+            best = number(common::result(common::rpc(
+                RPC_PORT,
+                "chain_getHeader",
+                "[]",
+            )));
+            if best >= upgraded + delay {
+                break;
+            }
+            assert!(
+                finalized < upgraded,
+                "#{} finalized at #{}, the upgrade in #{}",
+                finalized,
+                best,
+                upgraded
+            );
+            thread::sleep(Duration::from_secs(1));
+        }
+        let mut finalized_upgrade = false;
+        for _ in 0..60 {
+            if finalized() >= upgraded {
+                finalized_upgrade = true;
+                break;
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+        assert!(
+            finalized_upgrade,
+            "the upgrade in #{} should be finalized",
+            upgraded
+        );
+    });
+}
+
+#[test]
+fn grandpa_voting_rules_work() {
+    // by default the voter stays two blocks below the best block.
+    let (best, finalized) = best_and_finalized(&[]);
+    assert!(best >= 6, "best block #{}", best);
+    assert!(
+        best - finalized <= 4,
+        "#{} finalized at #{}",
+        finalized,
+        best
+    );
+
+    let (best, finalized) = best_and_finalized(&["before-best:5"]);
+    assert!(best >= 6, "best block #{}", best);
+    assert!(
+        best - finalized >= 5,
+        "#{} finalized at #{}",
+        finalized,
+        best
+    );
+
+    // without a runtime upgrade, the delay does not hold finality back.
+    let (best, finalized) = best_and_finalized(&["three-quarters", "runtime-upgrade-delay:10"]);
+    assert!(best >= 6, "best block #{}", best);
+    assert!(
+        best - finalized <= 4,
+        "#{} finalized at #{}",
+        finalized,
+        best
+    );
+
+    // with one, it does.
+    upgrade_delay_holds_back_finality(10);
+}
+
+#[test]
+fn unknown_voting_rule_is_refused() {
+    let status = Command::new(cargo_bin("coinnet-node"))
+        .args(&["--dev", "--tmp", "--grandpa-voting-rule", "half-way"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
@@ -0,0 +1,67 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#![cfg(unix)]
+
+use rusqlite::{params, Connection};
+use tempfile::tempdir;
+
+pub mod common;
+
+#[test]
+fn index_db_works() {
+    let base_path = tempdir().expect("could not create a temp dir");
+    let index_db = base_path.path().join("index.db");
+    let index_db_arg = index_db.to_str().unwrap();
+
+    // the first run leaves blocks behind for the second to backfill.
+    common::run_dev_node_with_args_for_a_while(base_path.path(), &["--pruning", "archive"]);
+    common::run_dev_node_with_args_for_a_while(
+        base_path.path(),
+        &["--pruning", "archive", "--index-db", index_db_arg],
+    );
+
+    let db = Connection::open(&index_db).unwrap();
+    let count = |sql: &str| db.query_row(sql, params![], |row| row.get::<_, u32>(0)).unwrap();
+
+    let blocks = count("SELECT COUNT(*) FROM blocks");
+    assert!(blocks > 1);
+    // blocks are indexed from genesis without gaps.
+    assert_eq!(count("SELECT MAX(number) FROM blocks") + 1, blocks);
+    assert!(count("SELECT COUNT(*) FROM blocks WHERE finalized = 1") > 0);
+
+    // every block after genesis starts with the timestamp inherent, which succeeds.
+    assert_eq!(
+        count(
+            "SELECT COUNT(*) FROM extrinsics
+             WHERE idx = 0 AND pallet = 'Timestamp' AND name = 'set' AND success = 1
+             AND signer IS NULL"
+        ),
+        blocks - 1
+    );
+    assert_eq!(
+        count("SELECT COUNT(*) FROM blocks WHERE number > 0 AND timestamp IS NULL"),
+        0
+    );
+    assert_eq!(
+        count(
+            "SELECT COUNT(*) FROM events
+             WHERE phase = 'extrinsic' AND pallet = 'System' AND name = 'ExtrinsicSuccess'"
+        ),
+        count("SELECT COUNT(*) FROM extrinsics WHERE success = 1")
+    );
+}
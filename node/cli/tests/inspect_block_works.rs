@@ -0,0 +1,56 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+use tempfile::tempdir;
+
+pub mod common;
+
+#[test]
+fn inspect_block_works() {
+    let base_path = tempdir().expect("could not create a temp dir");
+
+    common::run_dev_node_for_a_while(base_path.path());
+
+    let output = Command::new(cargo_bin("coinnet-node"))
+        .args(&["inspect-block", "--dev", "--pruning", "archive", "-d"])
+        .arg(base_path.path())
+        .args(&["1", "--output-type", "Json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let block: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(block["number"], 1);
+    // every block starts with the timestamp inherent.
+    let timestamp = &block["extrinsics"][0];
+    assert_eq!(timestamp["call"]["pallet"], "Timestamp");
+    assert_eq!(timestamp["call"]["call"], "set");
+    assert!(timestamp["signed"].is_null());
+    assert_eq!(timestamp["events"][0]["event"], "ExtrinsicSuccess");
+
+    let status = Command::new(cargo_bin("coinnet-node"))
+        .args(&["inspect-block", "--dev", "--pruning", "archive", "-d"])
+        .arg(base_path.path())
+        .arg("1")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
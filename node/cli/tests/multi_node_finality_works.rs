@@ -0,0 +1,55 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+pub mod common;
+pub mod network;
+
+use network::{inspect_key, Network};
+
+#[test]
+fn finality_survives_a_restarted_authority() {
+    let mut network = Network::start(4);
+    network.wait_for_blocks(3, 120);
+    network.wait_for_finality(1, 120);
+
+    // three of four GRANDPA voters are enough to finalize.
+    network.kill(3);
+    let (finalized, _) = network.finalized(0);
+    network.wait_for_finality(finalized + 3, 120);
+
+    // a transaction submitted to one node reaches the others.
+    let charlie = inspect_key("//Charlie", "Sr25519").1;
+    let call = format!(
+        r#"{{"pallet": "Balances", "call": "transfer", "args": {{"dest": "{}", "value": "1000000000000"}}}}"#,
+        charlie
+    );
+    let bob = inspect_key("//Bob", "Sr25519").1;
+    let nonce = network.rpc(2, "system_accountNextIndex", &format!(r#"["{}"]"#, bob));
+    assert!(network.submit(1, "//Bob", &call).starts_with("0x"));
+    network.wait_until(60, "the transfer", |network| {
+        network.rpc(2, "system_accountNextIndex", &format!(r#"["{}"]"#, bob)) != nonce
+    });
+
+    // the restarted node catches up, and votes again.
+    network.restart(3);
+    let (finalized, _) = network.finalized(0);
+    network.wait_for_finality(finalized + 3, 180);
+    network.assert_converged(60);
+}
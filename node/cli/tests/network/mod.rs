@@ -0,0 +1,450 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! A local test network of authority nodes, for tests of what a single `--dev` node cannot show:
+//! GRANDPA finality among several voters, nodes leaving and coming back, staking elections and
+//! runtime upgrades seen by every node.
+//!
+//! `Network::start(n)` generates a chain spec with `generate-spec`, in which the first `n`
+//! well-known accounts (Alice, Bob, ...) are the validators, with their `//<Name>//stash` account
+//! as stash, and starts a node for each with `--<name>` on its own ports. Tests using it declare
+//! `pub mod common;` and `pub mod network;`. `build_runtime` builds the runtime with a feature
+//! for tests, such as `upgrade-test-runtime` for a runtime to upgrade to, or
+//! `election-test-runtime` for eras short enough to wait for, which
+//! `Network::start_with_runtime` starts the chain with.
+
+#![cfg(unix)]
+
+use super::common;
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use serde_json::Value;
+use sp_core::hexdisplay::HexDisplay;
+use std::{
+    convert::TryInto,
+    fs,
+    io::{Read, Write},
+    net::TcpStream,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+
+/// The names of the authorities, as the keyring flags of the node know them.
+const NAMES: [&str; 6] = ["alice", "bob", "charlie", "dave", "eve", "ferdie"];
+
+/// The ports of the first node, those of the others follow.
+const P2P_PORT: u16 = 30400;
+const RPC_PORT: u16 = 9970;
+const WS_PORT: u16 = 9980;
+
+/// The peer id of the first node, whose node key is `0x00..01`, for the others to boot from.
+const BOOT_NODE_ID: &str = "12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp";
+
+/// A node of the network.
+pub struct Node {
+    /// The keyring name of the authority of the node.
+    pub name: &'static str,
+    /// The HTTP RPC port of the node.
+    pub rpc_port: u16,
+    index: u16,
+    base_path: PathBuf,
+    process: Option<Child>,
+}
+
+/// Authority nodes running on localhost, stopped when dropped.
+pub struct Network {
+    dir: TempDir,
+    spec: PathBuf,
+    nodes: Vec<Node>,
+    /// Whether the nodes run the runtime of the chain spec, rather than their native one.
+    wasm_execution: bool,
+}
+
+/// The public key and SS58 address of a secret URI, as `key inspect` prints them.
+pub fn inspect_key(suri: &str, scheme: &str) -> (String, String) {
+    let output = Command::new(cargo_bin("coinnet-node"))
+        .args(&[
+            "key",
+            "inspect",
+            "--output-type",
+            "Json",
+            "--scheme",
+            scheme,
+            suri,
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let key: Value = serde_json::from_slice(&output.stdout).unwrap();
+    (
+        key["publicKey"].as_str().unwrap().into(),
+        key["ss58Address"].as_str().unwrap().into(),
+    )
+}
+
+/// The secret URI of the account of an authority, e.g. `//Alice`.
+pub fn suri(name: &str) -> String {
+    format!("//{}{}", name[..1].to_uppercase(), &name[1..])
+}
+
+/// The `generate-spec` config of a network of the given authorities.
+fn network_config(names: &[&str]) -> String {
+    let address = |suri: &str| inspect_key(suri, "Sr25519").1;
+    let first = address(&suri(names[0]));
+    let mut config = format!(
+        "name = \"Test Network\"\nid = \"test_network\"\nchain_type = \"Local\"\n\
+         sudo = \"{first}\"\ntechnical_committee = [\"{first}\"]\n\n\
+         [properties]\ntokenSymbol = \"1CN\"\ntokenDecimals = 12\n",
+        first = first,
+    );
+    for name in names {
+        let controller = address(&suri(name));
+        let stash = address(&format!("{}//stash", suri(name)));
+        let grandpa = inspect_key(&suri(name), "Ed25519").0;
+        config += &format!(
+            "\n[[validators]]\nstash = \"{stash}\"\ncontroller = \"{controller}\"\n\
+             bond = \"10000000000000000\"\ngrandpa = \"{grandpa}\"\nbabe = \"{controller}\"\n\
+             im_online = \"{controller}\"\nauthority_discovery = \"{controller}\"\n\n\
+             [[endowments]]\naccount = \"{stash}\"\nbalance = \"10000000000000000000\"\n\n\
+             [[endowments]]\naccount = \"{controller}\"\nbalance = \"10000000000000000000\"\n",
+            stash = stash,
+            controller = controller,
+            grandpa = grandpa,
+        );
+    }
+    config
+}
+
+/// Call the RPC `method` of the node listening on `port`, and return the result, or `None` if
+/// the node is not listening or answered with an error.
+pub fn try_rpc(port: u16, method: &str, params: &str) -> Option<Value> {
+    let body = format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"{}","params":{}}}"#,
+        method, params
+    );
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).ok()?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .ok()?;
+    write!(
+        stream,
+        "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+    .ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    let body = response.split("\r\n\r\n").last()?;
+    let mut response: Value = serde_json::from_str(body).ok()?;
+    match response.get("result") {
+        Some(_) => Some(response["result"].take()),
+        None => None,
+    }
+}
+
+/// The number of a block header.
+pub fn number(header: &Value) -> u64 {
+    let number = header["number"].as_str().unwrap();
+    u64::from_str_radix(number.trim_start_matches("0x"), 16).unwrap()
+}
+
+/// Build the runtime with one of its features for tests, such as `upgrade-test-runtime`, and
+/// return the path of its compressed WASM. The build has its own target directory, kept between
+/// runs.
+pub fn build_runtime(feature: &str) -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../target")
+        .join(feature);
+    let status = Command::new(env!("CARGO"))
+        .args(&["build", "-p", "node_runtime", "--features"])
+        .arg(format!("node_runtime/{}", feature))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .env_remove("SKIP_WASM_BUILD")
+        .status()
+        .unwrap();
+    assert!(status.success(), "the runtime should build");
+    target_dir.join("debug/wbuild/node_runtime/node_runtime.compact.compressed.wasm")
+}
+
+impl Network {
+    /// Generate the chain spec of a network of `authorities` authorities, and start their nodes.
+    pub fn start(authorities: usize) -> Self {
+        Self::start_with_runtime(authorities, None)
+    }
+
+    /// Like `start`, with the genesis code of the chain spec replaced by the WASM at `runtime`,
+    /// as `build_runtime` builds it. The nodes then execute it rather than their native runtime,
+    /// which has the same spec version but not the same constants.
+    pub fn start_with_runtime(authorities: usize, runtime: Option<&Path>) -> Self {
+        assert!(authorities >= 1 && authorities <= NAMES.len());
+        let dir = tempfile::tempdir().expect("could not create a temp dir");
+        let config = dir.path().join("network.toml");
+        fs::write(&config, network_config(&NAMES[..authorities])).unwrap();
+        let status = Command::new(cargo_bin("coinnet-node"))
+            .args(&["generate-spec", "--config"])
+            .arg(&config)
+            .arg("--output")
+            .arg(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let spec = dir.path().join("test_network-raw.json");
+        if let Some(runtime) = runtime {
+            let mut raw: Value = serde_json::from_slice(&fs::read(&spec).unwrap()).unwrap();
+            let code = format!("0x{}", HexDisplay::from(&fs::read(runtime).unwrap()));
+            // the hex of the well-known `:code` key.
+            raw["genesis"]["raw"]["top"]["0x3a636f6465"] = Value::String(code);
+            fs::write(&spec, serde_json::to_vec(&raw).unwrap()).unwrap();
+        }
+
+        let nodes = NAMES[..authorities]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Node {
+                name,
+                rpc_port: RPC_PORT + index as u16,
+                index: index as u16,
+                base_path: dir.path().join(name),
+                process: None,
+            })
+            .collect();
+        let mut network = Network {
+            dir,
+            spec,
+            nodes,
+            wasm_execution: runtime.is_some(),
+        };
+        for node in 0..authorities {
+            network.restart(node);
+        }
+        network
+    }
+
+    /// The raw chain spec of the network.
+    pub fn spec(&self) -> &Path {
+        &self.spec
+    }
+
+    /// The directory the chain spec and the base paths of the nodes are in.
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The nodes of the network, running or not.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Whether a node is running.
+    pub fn is_running(&mut self, node: usize) -> bool {
+        match &mut self.nodes[node].process {
+            Some(process) => process.try_wait().unwrap().is_none(),
+            None => false,
+        }
+    }
+
+    /// The nodes that were started and not killed.
+    fn running(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|node| node.process.is_some())
+    }
+
+    /// Call the RPC `method` of a node, which must answer.
+    pub fn rpc(&self, node: usize, method: &str, params: &str) -> Value {
+        try_rpc(self.nodes[node].rpc_port, method, params)
+            .unwrap_or_else(|| panic!("{} did not answer {}", self.nodes[node].name, method))
+    }
+
+    /// The best block number of a node.
+    pub fn best_number(&self, node: usize) -> u64 {
+        number(&self.rpc(node, "chain_getHeader", "[]"))
+    }
+
+    /// The number and hash of the last block a node finalized.
+    pub fn finalized(&self, node: usize) -> (u64, String) {
+        let hash = self.rpc(node, "chain_getFinalizedHead", "[]");
+        let header = self.rpc(node, "chain_getHeader", &format!("[{}]", hash));
+        (number(&header), hash.as_str().unwrap().into())
+    }
+
+    /// Start a node, again if it was killed, with the same database and keys.
+    pub fn restart(&mut self, node: usize) {
+        let boot_node = format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", P2P_PORT, BOOT_NODE_ID);
+        let Node {
+            name,
+            index,
+            base_path,
+            ..
+        } = &self.nodes[node];
+        let process = Command::new(cargo_bin("coinnet-node"))
+            .arg("--chain")
+            .arg(&self.spec)
+            .arg("-d")
+            .arg(base_path)
+            .args(&[
+                "--validator",
+                &format!("--{}", name),
+                "--no-prometheus",
+                "--no-mdns",
+            ])
+            .args(&["--port", &(P2P_PORT + index).to_string()])
+            .args(&["--rpc-port", &(RPC_PORT + index).to_string()])
+            .args(&["--ws-port", &(WS_PORT + index).to_string()])
+            .args(&["--node-key", &format!("{:064x}", index + 1)])
+            .args(&["--bootnodes", &boot_node])
+            .args(if self.wasm_execution {
+                &["--execution", "wasm"][..]
+            } else {
+                &[]
+            })
+            .spawn()
+            .unwrap();
+        self.nodes[node].process = Some(process);
+    }
+
+    /// Stop a node with SIGINT, as an operator would, and wait for it to exit.
+    pub fn kill(&mut self, node: usize) {
+        if let Some(mut process) = self.nodes[node].process.take() {
+            kill(Pid::from_raw(process.id().try_into().unwrap()), SIGINT).unwrap();
+            assert!(common::wait_for(&mut process, 40)
+                .map(|x| x.success())
+                .unwrap_or_default());
+        }
+    }
+
+    /// Wait until `condition` holds, polling every second, and panic after `secs` seconds.
+    pub fn wait_until(&self, secs: u64, what: &str, condition: impl Fn(&Self) -> bool) {
+        let start = Instant::now();
+        while !condition(self) {
+            assert!(
+                start.elapsed() < Duration::from_secs(secs),
+                "timed out after {} seconds waiting for {}",
+                secs,
+                what
+            );
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    /// Wait until every running node has a best block of at least `number`.
+    pub fn wait_for_blocks(&self, number: u64, secs: u64) {
+        self.wait_until(secs, &format!("block #{}", number), |network| {
+            network.running().all(|node| {
+                try_rpc(node.rpc_port, "chain_getHeader", "[]")
+                    .map_or(false, |header| self::number(&header) >= number)
+            })
+        });
+    }
+
+    /// Wait until every running node finalized a block of at least `number`.
+    pub fn wait_for_finality(&self, number: u64, secs: u64) {
+        self.wait_until(secs, &format!("finality of block #{}", number), |network| {
+            network.running().all(|node| {
+                try_rpc(node.rpc_port, "chain_getFinalizedHead", "[]")
+                    .and_then(|hash| {
+                        try_rpc(node.rpc_port, "chain_getHeader", &format!("[{}]", hash))
+                    })
+                    .map_or(false, |header| self::number(&header) >= number)
+            })
+        });
+    }
+
+    /// Wait until the running nodes agree on the finalized chain: each has the same block at the
+    /// lowest height any of them finalized.
+    pub fn assert_converged(&self, secs: u64) {
+        self.wait_until(secs, "the nodes to converge", |network| {
+            let finalized = network
+                .running()
+                .map(|node| {
+                    try_rpc(node.rpc_port, "chain_getFinalizedHead", "[]")
+                        .and_then(|hash| {
+                            try_rpc(node.rpc_port, "chain_getHeader", &format!("[{}]", hash))
+                        })
+                        .map(|header| number(&header))
+                })
+                .collect::<Option<Vec<_>>>();
+            let lowest = match finalized.and_then(|finalized| finalized.into_iter().min()) {
+                Some(lowest) => lowest,
+                None => return false,
+            };
+            let hashes = network
+                .running()
+                .map(|node| {
+                    try_rpc(
+                        node.rpc_port,
+                        "chain_getBlockHash",
+                        &format!("[{}]", lowest),
+                    )
+                })
+                .collect::<Vec<_>>();
+            hashes
+                .iter()
+                .all(|hash| hash.is_some() && *hash == hashes[0])
+        });
+    }
+
+    /// Build, sign and submit a transaction of `call`, in the JSON of `tx build`, from the
+    /// account of `suri` through a node, and return its hash.
+    pub fn submit(&self, node: usize, suri: &str, call: &str) -> String {
+        let tx = |args: &[&str]| {
+            let output = Command::new(cargo_bin("coinnet-node"))
+                .arg("tx")
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "tx {} failed", args[0]);
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+        let account = inspect_key(suri, "Sr25519").1;
+        let nonce = self.rpc(
+            node,
+            "system_accountNextIndex",
+            &format!(r#"["{}"]"#, account),
+        );
+        let genesis_hash = self.rpc(node, "chain_getBlockHash", "[0]");
+        let unsigned = tx(&[
+            "build",
+            "--call",
+            call,
+            "--nonce",
+            &nonce.to_string(),
+            "--genesis-hash",
+            genesis_hash.as_str().unwrap(),
+        ]);
+        let signed = tx(&["sign", &unsigned, "--suri", suri]);
+        let url = format!("http://127.0.0.1:{}", self.nodes[node].rpc_port);
+        tx(&["submit", &signed, "--url", &url])
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        for node in 0..self.nodes.len() {
+            if let Some(mut process) = self.nodes[node].process.take() {
+                let _ = kill(Pid::from_raw(process.id().try_into().unwrap()), SIGINT);
+                let _ = common::wait_for(&mut process, 40);
+            }
+        }
+    }
+}
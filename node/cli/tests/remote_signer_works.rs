@@ -0,0 +1,93 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+
+const SIGNER: &str = "127.0.0.1:9962";
+const RPC_PORT: &str = "9963";
+const SECRET: &str = "remote signer test secret";
+
+/// Insert a key of Alice in the keystore of the signer.
+fn insert_key(keystore: &Path, key_type: &str, scheme: &str) {
+    let status = Command::new(cargo_bin("coinnet-node"))
+        .args(&["key", "insert", "--chain", "dev", "--suri", "//Alice"])
+        .args(&["--key-type", key_type, "--scheme", scheme])
+        .arg("--keystore-path")
+        .arg(keystore)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn node(secret: &str) -> Command {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"));
+    cmd.args(&[
+        "--chain",
+        "dev",
+        "--validator",
+        "--tmp",
+        "--rpc-port",
+        RPC_PORT,
+    ])
+    .args(&["--keystore-uri", &format!("tcp://{}", SIGNER)])
+    .env("COINNET_SIGNER_SECRET", secret);
+    cmd
+}
+
+#[test]
+fn remote_signer_works() {
+    let keystore = tempdir().expect("could not create a temp dir");
+    insert_key(keystore.path(), "babe", "Sr25519");
+    insert_key(keystore.path(), "gran", "Ed25519");
+
+    let mut signer = Command::new(cargo_bin("coinnet-remote-signer"))
+        .args(&["--listen", SIGNER, "--keystore-path"])
+        .arg(keystore.path())
+        .env("COINNET_SIGNER_SECRET", SECRET)
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_secs(2));
+
+    // the signer does not answer a node without the secret, which then does not start.
+    let mut refused = node("another secret").spawn().unwrap();
+    assert!(common::wait_for(&mut refused, 20)
+        .map(|x| !x.success())
+        .unwrap_or_default());
+
+    // the node keystore is empty: the blocks and votes are all signed by the signer.
+    let finalized = common::run_node_for_a_while(&mut node(SECRET), 30, || {
+        let finalized_head = common::result(common::rpc(RPC_PORT, "chain_getFinalizedHead", "[]"));
+        common::result(common::rpc(
+            RPC_PORT,
+            "chain_getHeader",
+            &format!("[{}]", finalized_head),
+        ))
+    });
+    signer.kill().unwrap();
+
+    assert_ne!(finalized["number"], "0x0");
+}
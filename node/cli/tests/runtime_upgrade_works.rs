@@ -0,0 +1,115 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+pub mod common;
+pub mod network;
+
+use assert_cmd::cargo::cargo_bin;
+use network::{build_runtime, Network};
+use sp_core::{hexdisplay::HexDisplay, twox_128};
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Run a subcommand of the node, writing its standard output to `output`.
+fn run(args: &[&str], output: &Path) -> Output {
+    Command::new(cargo_bin("coinnet-node"))
+        .args(args)
+        .stdout(File::create(output).unwrap())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn runtime_upgrade_works() {
+    let spec_version = node_runtime::VERSION.spec_version;
+    let upgrade = build_runtime("upgrade-test-runtime");
+    let upgrade = upgrade.to_str().unwrap();
+
+    let mut network = Network::start(3);
+    network.wait_for_finality(1, 120);
+    let dir = network.dir().to_path_buf();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+    let url = format!("http://127.0.0.1:{}", network.nodes()[0].rpc_port);
+
+    // the runtime cannot replace itself.
+    fs::write(path("current.wasm"), node_runtime::WASM_BINARY.unwrap()).unwrap();
+    let output = run(
+        &[
+            "runtime-upgrade",
+            "--wasm",
+            &path("current.wasm"),
+            "--url",
+            &url,
+        ],
+        &dir.join("refused.json"),
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("spec version"));
+
+    // Alice, the sudo key of the network, enacts the upgrade, which removes the key.
+    let output = run(
+        &[
+            "runtime-upgrade",
+            "--wasm",
+            upgrade,
+            "--url",
+            &url,
+            "--origin",
+            "sudo",
+        ],
+        &dir.join("upgrade.json"),
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("removes its sudo key"));
+    assert!(network
+        .submit(0, "//Alice", &path("upgrade.json"))
+        .starts_with("0x"));
+
+    // every node runs the new runtime, and goes on building and finalizing blocks with it.
+    network.wait_until(120, "the upgrade", |network| {
+        (0..3).all(|node| {
+            network.rpc(node, "state_getRuntimeVersion", "[]")["specVersion"] == spec_version + 1
+        })
+    });
+    let sudo_key = [twox_128(b"Sudo"), twox_128(b"Key")].concat();
+    let sudo_key = format!(r#"["0x{}"]"#, HexDisplay::from(&sudo_key));
+    assert!(network.rpc(0, "state_getStorage", &sudo_key).is_null());
+    let (finalized, _) = network.finalized(0);
+    network.wait_for_finality(finalized + 3, 120);
+    network.assert_converged(60);
+
+    // the chain now runs the new runtime, which cannot replace itself either.
+    let output = run(
+        &["runtime-upgrade", "--wasm", upgrade, "--url", &url],
+        &dir.join("refused.json"),
+    );
+    assert!(!output.status.success());
+    for node in 0..3 {
+        assert!(
+            network.is_running(node),
+            "the nodes should still be running"
+        );
+    }
+}
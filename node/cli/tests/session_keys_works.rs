@@ -0,0 +1,63 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn session_keys(args: &[&str]) -> String {
+    let output = Command::new(cargo_bin("coinnet-node"))
+        .arg("session-keys")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn session_keys_works() {
+    let keystore = tempdir().expect("could not create a temp dir");
+
+    let keys = session_keys(&[
+        "generate",
+        "--dev",
+        "--keystore-path",
+        keystore.path().to_str().unwrap(),
+    ]);
+    // four 32 byte public keys
+    assert!(keys.starts_with("0x"));
+    assert_eq!(keys.len(), 2 + 4 * 64);
+    // one file per key
+    assert_eq!(std::fs::read_dir(keystore.path()).unwrap().count(), 4);
+
+    let inspected: serde_json::Value =
+        serde_json::from_str(&session_keys(&["inspect", &keys, "--output-type", "Json"])).unwrap();
+    let key_types = inspected
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|key| key["keyType"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(key_types, vec!["gran", "babe", "imon", "audi"]);
+    assert_eq!(inspected[0]["publicKey"], &keys[..66]);
+
+    let payload = session_keys(&["set-keys-payload", &keys]);
+    // the keys are in the call, followed by the empty proof
+    assert!(payload.ends_with(&format!("{}00", &keys[2..])));
+}
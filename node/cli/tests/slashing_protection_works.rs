@@ -0,0 +1,77 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::{fs, path::Path, process::Command};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn slashing_protection(args: &[&str], base_path: &Path) -> bool {
+    Command::new(cargo_bin("coinnet-node"))
+        .arg("slashing-protection")
+        .args(args)
+        .arg("-d")
+        .arg(base_path)
+        .status()
+        .unwrap()
+        .success()
+}
+
+#[test]
+fn slashing_protection_works() {
+    let base_path = tempdir().expect("could not create a temp dir");
+
+    common::run_dev_node_for_a_while(base_path.path());
+    assert!(base_path
+        .path()
+        .join("chains/dev/slashing_protection.sqlite")
+        .exists());
+
+    let export = base_path.path().join("export.json");
+    assert!(slashing_protection(
+        &["export", "--dev", "--output", export.to_str().unwrap()],
+        base_path.path(),
+    ));
+    let exported: serde_json::Value = serde_json::from_slice(&fs::read(&export).unwrap()).unwrap();
+    assert_eq!(exported["chain"], "dev");
+    assert!(exported["lastSlot"].as_u64().is_some());
+    assert!(!exported["votes"].as_array().unwrap().is_empty());
+
+    // the database moves to another machine of the same chain only.
+    let other_path = tempdir().expect("could not create a temp dir");
+    assert!(slashing_protection(
+        &["import", export.to_str().unwrap(), "--dev"],
+        other_path.path(),
+    ));
+    assert!(!slashing_protection(
+        &["import", export.to_str().unwrap(), "--chain", "local"],
+        other_path.path(),
+    ));
+
+    let reexport = other_path.path().join("export.json");
+    assert!(slashing_protection(
+        &["export", "--dev", "--output", reexport.to_str().unwrap()],
+        other_path.path(),
+    ));
+    let reexported: serde_json::Value =
+        serde_json::from_slice(&fs::read(&reexport).unwrap()).unwrap();
+    assert_eq!(reexported, exported);
+}
@@ -0,0 +1,98 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+pub mod common;
+pub mod network;
+
+use codec::{Decode, Encode};
+use network::{build_runtime, inspect_key, suri, Network};
+use node_primitives::{AccountId, Balance};
+use pallet_staking::{ActiveEraInfo, Exposure};
+use sp_core::{hexdisplay::HexDisplay, twox_128, twox_64};
+
+/// The storage value at `key` of a node, decoded, or `None` if there is none.
+fn storage<T: Decode>(network: &Network, node: usize, key: &[u8]) -> Option<T> {
+    let params = format!(r#"["0x{}"]"#, HexDisplay::from(&key));
+    let value = network.rpc(node, "state_getStorage", &params);
+    let encoded = sp_core::bytes::from_hex(value.as_str()?).unwrap();
+    Some(T::decode(&mut &encoded[..]).unwrap())
+}
+
+/// The key of a `twox_64_concat` hashed map key.
+fn twox_64_concat(key: &[u8]) -> Vec<u8> {
+    [&twox_64(key)[..], key].concat()
+}
+
+#[test]
+fn staking_elections_work() {
+    let runtime = build_runtime("election-test-runtime");
+    let network = Network::start_with_runtime(3, Some(&runtime));
+    let stashes = network
+        .nodes()
+        .iter()
+        .map(|node| {
+            let public = inspect_key(&format!("{}//stash", suri(node.name)), "Sr25519").0;
+            let public = sp_core::bytes::from_hex(&public).unwrap();
+            AccountId::decode(&mut &public[..]).unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    network.wait_for_blocks(1, 120);
+
+    // with one-minute epochs and two-session eras, the first election ends era 0 after two
+    // minutes.
+    let active_era = [twox_128(b"Staking"), twox_128(b"ActiveEra")].concat();
+    network.wait_until(600, "era 1", |network| {
+        (0..3).all(|node| {
+            storage::<ActiveEraInfo>(network, node, &active_era).map_or(false, |era| era.index >= 1)
+        })
+    });
+
+    // every node has the exposures the election gave every validator, and the validators of the
+    // session are the elected ones.
+    let validators = [twox_128(b"Session"), twox_128(b"Validators")].concat();
+    for node in 0..3 {
+        let era = storage::<ActiveEraInfo>(&network, node, &active_era)
+            .unwrap()
+            .index;
+        for stash in &stashes {
+            let key = [
+                &twox_128(b"Staking")[..],
+                &twox_128(b"ErasStakers")[..],
+                &twox_64_concat(&era.encode()),
+                &twox_64_concat(stash.as_ref()),
+            ]
+            .concat();
+            let exposure = storage::<Exposure<AccountId, Balance>>(&network, node, &key)
+                .unwrap_or_else(|| panic!("no exposure of {} in era {}", stash, era));
+            assert!(
+                exposure.total > 0,
+                "{} should be exposed in era {}",
+                stash,
+                era
+            );
+        }
+        let validators = storage::<Vec<AccountId>>(&network, node, &validators).unwrap();
+        for stash in &stashes {
+            assert!(validators.contains(stash), "{} should validate", stash);
+        }
+    }
+    network.assert_converged(60);
+}
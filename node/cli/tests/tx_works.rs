@@ -0,0 +1,83 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use assert_cmd::cargo::cargo_bin;
+use std::process::{Command, Output};
+
+const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+const GENESIS_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+fn tx(args: &[&str]) -> Output {
+    Command::new(cargo_bin("coinnet-node"))
+        .arg("tx")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn tx_build_and_sign_works() {
+    let call = format!(
+        r#"{{"pallet": "Balances", "call": "transfer", "args": {{"dest": "{}", "value": "1000000000000"}}}}"#,
+        BOB
+    );
+    let unsigned = stdout(tx(&[
+        "build",
+        "--call",
+        &call,
+        "--nonce",
+        "3",
+        "--genesis-hash",
+        GENESIS_HASH,
+    ]));
+    assert!(unsigned.starts_with("0x"));
+
+    let signed = stdout(tx(&["sign", &unsigned, "--suri", "//Alice"]));
+    assert!(signed.starts_with("0x"));
+
+    // mortal transactions need the block their era starts at.
+    let mortal = tx(&[
+        "build",
+        "--call",
+        &call,
+        "--nonce",
+        "3",
+        "--genesis-hash",
+        GENESIS_HASH,
+        "--era-period",
+        "64",
+    ]);
+    assert!(!mortal.status.success());
+}
+
+#[test]
+fn tx_build_rejects_invalid_calls() {
+    for call in &[
+        r#"{"pallet": "Nothing", "call": "transfer"}"#,
+        r#"{"pallet": "Balances", "call": "transfer", "args": {"dest": "not an address", "value": 1}}"#,
+        r#"{"pallet": "Balances", "call": "transfer", "args": {"value": 1}}"#,
+    ] {
+        let output = tx(&["build", "--call", call, "--nonce", "0", "--genesis-hash", GENESIS_HASH]);
+        assert!(!output.status.success());
+    }
+}
@@ -0,0 +1,140 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use serde_json::Value;
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+use std::process::Command;
+
+pub mod common;
+
+const RPC_PORT: &str = "9956";
+
+const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+/// An address in the SS58 format of the dev chain, whose spec sets none.
+fn dev_address(address: &str) -> String {
+    AccountId32::from_ss58check(address)
+        .unwrap()
+        .to_ss58check_with_version(Ss58AddressFormat::Custom(85))
+}
+
+fn tx(args: &[&str]) -> String {
+    let output = Command::new(cargo_bin("coinnet-node"))
+        .arg("tx")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "tx {} failed", args[0]);
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// Submit a transfer of `value` from Alice to Bob with the given nonce, and return its hash.
+fn submit_transfer(nonce: u64, value: u64) -> String {
+    let genesis_hash = common::result(common::rpc(RPC_PORT, "chain_getBlockHash", "[0]"));
+    let call = format!(
+        r#"{{"pallet": "Balances", "call": "transfer", "args": {{"dest": "{}", "value": "{}"}}}}"#,
+        BOB, value
+    );
+    let unsigned = tx(&[
+        "build",
+        "--call",
+        &call,
+        "--nonce",
+        &nonce.to_string(),
+        "--genesis-hash",
+        genesis_hash.as_str().unwrap(),
+    ]);
+    let signed = tx(&["sign", &unsigned, "--suri", "//Alice"]);
+    tx(&[
+        "submit",
+        &signed,
+        "--url",
+        &format!("http://127.0.0.1:{}", RPC_PORT),
+    ])
+}
+
+/// Check a transfer of `txpool_content` against the one submitted.
+fn assert_transfer(transaction: &Value, hash: &str, nonce: u64, value: u64) {
+    assert_eq!(transaction["hash"], hash);
+    assert_eq!(transaction["nonce"], nonce);
+    assert_eq!(transaction["tip"], 0);
+    assert_eq!(transaction["call"]["pallet"], "Balances");
+    assert_eq!(transaction["call"]["call"], "transfer");
+    assert_eq!(transaction["call"]["args"]["dest"], dev_address(BOB));
+    assert_eq!(transaction["call"]["args"]["value"], value);
+}
+
+#[test]
+fn txpool_rpc_works() {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"));
+    cmd.args(&[
+        "--dev",
+        "--tmp",
+        "--rpc-port",
+        RPC_PORT,
+        "--rpc-methods",
+        "Safe",
+    ]);
+    common::run_node_for_a_while(&mut cmd, 10, || {
+        let empty_content = common::rpc(RPC_PORT, "txpool_content", "[]");
+        let empty_inspect = common::rpc(RPC_PORT, "txpool_inspect", "[]");
+        assert!(empty_content.contains(r#""result":{"ready":{},"future":{}}"#));
+        assert!(empty_inspect.contains(r#""result":{"ready":{},"future":{}}"#));
+
+        // the nonce after Alice's next one leaves a gap, so the transaction waits in the
+        // future set.
+        let future_hash = submit_transfer(10, 2_000_000_000_000);
+        // a ready transaction may be included before the pool is queried, then the next one
+        // is tried.
+        let mut ready = None;
+        for _ in 0..3 {
+            let alice = format!(r#"["{}"]"#, ALICE);
+            let nonce = common::result(common::rpc(RPC_PORT, "system_accountNextIndex", &alice));
+            let nonce = nonce.as_u64().unwrap();
+            let hash = submit_transfer(nonce, 1_000_000_000_000);
+            let content = common::result(common::rpc(RPC_PORT, "txpool_content", "[]"));
+            if content["ready"][dev_address(ALICE)][0]["hash"] == hash {
+                ready = Some((hash, nonce, content));
+                break;
+            }
+        }
+        let (hash, nonce, content) = ready.expect("the transfer should have been ready");
+        let ready = &content["ready"][dev_address(ALICE)][0];
+        assert_transfer(ready, &hash, nonce, 1_000_000_000_000);
+        assert!(ready["priority"].as_u64().unwrap() > 0);
+        let future = &content["future"][dev_address(ALICE)][0];
+        assert_transfer(future, &future_hash, 10, 2_000_000_000_000);
+        assert!(future["priority"].is_null());
+
+        let inspect = common::result(common::rpc(RPC_PORT, "txpool_inspect", "[]"));
+        let future_line = inspect["future"][dev_address(ALICE)][0].as_str().unwrap();
+        assert!(future_line.starts_with(&future_hash), "{}", future_line);
+        assert!(future_line.contains("nonce 10"), "{}", future_line);
+
+        // removing transactions is unsafe.
+        let remove = common::rpc(
+            RPC_PORT,
+            "txpool_remove",
+            &format!(r#"["{}"]"#, future_hash),
+        );
+        assert!(remove.contains(r#""error""#));
+    });
+}
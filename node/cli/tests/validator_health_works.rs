@@ -0,0 +1,64 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use std::convert::TryInto;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+pub mod common;
+
+const RPC_PORT: &str = "9960";
+
+#[test]
+fn validator_health_works() {
+    let mut cmd = Command::new(cargo_bin("coinnet-node"))
+        .args(&[
+            "--dev",
+            "--tmp",
+            "--validator-health",
+            "--rpc-port",
+            RPC_PORT,
+        ])
+        .spawn()
+        .unwrap();
+
+    // Let it produce some blocks.
+    thread::sleep(Duration::from_secs(20));
+    assert!(
+        cmd.try_wait().unwrap().is_none(),
+        "the process should still be running"
+    );
+
+    let health = common::rpc(RPC_PORT, "validator_health", "[]");
+
+    kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+    assert!(common::wait_for(&mut cmd, 40)
+        .map(|x| x.success())
+        .unwrap_or_default());
+
+    // the dev chain registers the keys of Alice, which `--dev` puts in the keystore.
+    assert!(health.contains(r#""sessionKeysMatch":true"#));
+    assert!(health.contains(r#""isVoter":true"#));
+    assert!(health.contains(r#""foreignBlocks":0"#));
+}
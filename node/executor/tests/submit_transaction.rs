@@ -17,13 +17,18 @@
 
 use std::sync::Arc;
 use node_runtime::{
-	Executive, Indices, Runtime, UncheckedExtrinsic,
+	Address, Call, Executive, Indices, Runtime, StakingPayout, UncheckedExtrinsic,
+	constants::{currency::DOLLARS, time::HOURS},
 };
+use node_primitives::{AccountId, BlockNumber};
 use sp_application_crypto::AppKey;
 use sp_core::{
 	offchain::{
+		OffchainDbExt,
+		OffchainWorkerExt,
+		StorageKind,
 		TransactionPoolExt,
-		testing::TestTransactionPoolExt,
+		testing::{TestOffchainExt, TestTransactionPoolExt},
 	},
 };
 use sp_keystore::{KeystoreExt, SyncCryptoStore, testing::KeyStore};
@@ -34,7 +39,9 @@ use frame_system::{
 		SendSignedTransaction,
 	}
 };
-use codec::Decode;
+use frame_support::{StorageDoubleMap, StorageMap, StorageValue, traits::OffchainWorker};
+use pallet_staking::EraIndex;
+use codec::{Decode, Encode};
 
 pub mod common;
 use self::common::*;
@@ -265,3 +272,138 @@ fn submitted_transaction_should_be_valid() {
 		assert_eq!(res.propagate, true);
 	});
 }
+
+/// Bond `stash` as a validator elected in the eras before `current_era`, with the rewards of
+/// `claimed` eras claimed.
+fn bond_validator(stash: &AccountId, current_era: EraIndex, claimed: Vec<EraIndex>) {
+	<pallet_staking::Bonded<Runtime>>::insert(stash, stash);
+	<pallet_staking::Ledger<Runtime>>::insert(stash, pallet_staking::StakingLedger {
+		stash: stash.clone(),
+		total: 1_000 * DOLLARS,
+		active: 1_000 * DOLLARS,
+		unlocking: vec![],
+		claimed_rewards: claimed,
+	});
+	for era in 0..current_era {
+		<pallet_staking::ErasStakers<Runtime>>::insert(era, stash, pallet_staking::Exposure {
+			total: 1_000 * DOLLARS,
+			own: 1_000 * DOLLARS,
+			others: vec![],
+		});
+		<pallet_staking::ErasValidatorReward<Runtime>>::insert(era, 1_000 * DOLLARS);
+	}
+	pallet_staking::CurrentEra::put(current_era);
+}
+
+/// The payouts in the pool, as the signer and the stash and era paid out.
+fn payouts(transactions: &[Vec<u8>]) -> Vec<(Address, AccountId, EraIndex)> {
+	transactions.iter().map(|tx| {
+		let tx = UncheckedExtrinsic::decode(&mut &**tx).unwrap();
+		match tx.function {
+			Call::Staking(pallet_staking::Call::payout_stakers(stash, era)) =>
+				(tx.signature.unwrap().0, stash, era),
+			call => panic!("Unexpected call: {:?}", call),
+		}
+	}).collect()
+}
+
+fn run_payout_worker(block: BlockNumber) {
+	frame_system::Pallet::<Runtime>::set_block_number(block);
+	<StakingPayout as OffchainWorker<BlockNumber>>::offchain_worker(block);
+}
+
+#[test]
+fn should_submit_payouts_of_local_stashes() {
+	let mut t = new_test_ext(compact_code_unwrap(), false);
+	let (pool, state) = TestTransactionPoolExt::new();
+	t.register_extension(TransactionPoolExt::new(pool));
+	let (offchain, _) = TestOffchainExt::new();
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+
+	let keystore = KeyStore::new();
+	let stash = keystore.sr25519_generate_new(
+		pallet_staking_payout::KEY_TYPE,
+		Some(&format!("{}/hunter1", PHRASE))
+	).unwrap();
+	// a key whose account is not bonded pays nothing out.
+	keystore.sr25519_generate_new(
+		pallet_staking_payout::KEY_TYPE,
+		Some(&format!("{}/hunter2", PHRASE))
+	).unwrap();
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	t.execute_with(|| {
+		let stash = AccountId::from(stash);
+		bond_validator(&stash, 3, vec![1]);
+
+		// the worker is opt-in.
+		run_payout_worker(10);
+		assert_eq!(state.read().transactions.len(), 0);
+
+		sp_io::offchain::local_storage_set(
+			StorageKind::PERSISTENT,
+			pallet_staking_payout::ENABLED_KEY,
+			&true.encode(),
+		);
+		run_payout_worker(11);
+		let signer = Address::Id(stash.clone());
+		assert_eq!(payouts(&state.read().transactions), vec![
+			(signer.clone(), stash.clone(), 0),
+			(signer.clone(), stash.clone(), 2),
+		]);
+
+		// the payouts are not submitted again before they had time to be included.
+		run_payout_worker(12);
+		assert_eq!(state.read().transactions.len(), 2);
+
+		run_payout_worker(11 + HOURS);
+		assert_eq!(state.read().transactions.len(), 4);
+
+		// the payouts have different nonces.
+		let s = state.read();
+		fn nonce(tx: UncheckedExtrinsic) -> frame_system::CheckNonce<Runtime> {
+			let extra = tx.signature.unwrap().2;
+			extra.4
+		}
+		let nonce1 = nonce(UncheckedExtrinsic::decode(&mut &*s.transactions[0]).unwrap());
+		let nonce2 = nonce(UncheckedExtrinsic::decode(&mut &*s.transactions[1]).unwrap());
+		assert!(
+			nonce1 != nonce2,
+			"Transactions should have different nonces. Got: {:?}", nonce1
+		);
+	});
+}
+
+#[test]
+fn should_limit_payouts_per_run() {
+	let mut t = new_test_ext(compact_code_unwrap(), false);
+	let (pool, state) = TestTransactionPoolExt::new();
+	t.register_extension(TransactionPoolExt::new(pool));
+	let (offchain, _) = TestOffchainExt::new();
+	t.register_extension(OffchainDbExt::new(offchain.clone()));
+	t.register_extension(OffchainWorkerExt::new(offchain));
+
+	let keystore = KeyStore::new();
+	let stash = keystore.sr25519_generate_new(
+		pallet_staking_payout::KEY_TYPE,
+		Some(&format!("{}/hunter1", PHRASE))
+	).unwrap();
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	t.execute_with(|| {
+		let stash = AccountId::from(stash);
+		bond_validator(&stash, 20, vec![]);
+		sp_io::offchain::local_storage_set(
+			StorageKind::PERSISTENT,
+			pallet_staking_payout::ENABLED_KEY,
+			&true.encode(),
+		);
+
+		run_payout_worker(10);
+		let eras = payouts(&state.read().transactions).into_iter()
+			.map(|(_, _, era)| era)
+			.collect::<Vec<_>>();
+		assert_eq!(eras, (0..8).collect::<Vec<_>>());
+	});
+}
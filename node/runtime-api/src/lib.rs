@@ -0,0 +1,38 @@
+//! Runtime API definitions of the node that no single pallet provides.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+use sp_runtime::{traits::Block as BlockT, ApplyExtrinsicResult, FixedU128, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// What applying an extrinsic did.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct DryRun<Event, Balance> {
+	/// The validity of the extrinsic and, if it was applied, the outcome of its dispatch.
+	pub result: ApplyExtrinsicResult,
+	/// The weight the extrinsic actually used, zero if it was not applied.
+	pub weight: u64,
+	/// The fee the extrinsic paid, tip included.
+	pub fee: Balance,
+	/// The events the extrinsic emitted.
+	pub events: Vec<Event>,
+}
+
+sp_api::decl_runtime_apis! {
+	pub trait DryRunApi<Event, Balance> where
+		Event: Codec,
+		Balance: Codec,
+	{
+		/// Apply `extrinsic` on top of the state like `BlockBuilder::apply_extrinsic` does. The
+		/// caller is expected to discard the changes.
+		fn dry_run(extrinsic: <Block as BlockT>::Extrinsic) -> DryRun<Event, Balance>;
+	}
+
+	pub trait FeeMultiplierApi {
+		/// The fee multiplier after `blocks` blocks after the next one, if each of them has
+		/// `weight` of normal dispatches, as `FeeMultiplierUpdate` moves it from the one of the
+		/// next block.
+		fn estimate_multiplier(weight: u64, blocks: u32) -> FixedU128;
+	}
+}
@@ -63,7 +63,11 @@ pub mod time {
 
 	// NOTE: Currently it is not possible to change the epoch duration after the chain has started.
 	//       Attempting to do so will brick block production.
+	#[cfg(not(feature = "election-test-runtime"))]
 	pub const EPOCH_DURATION_IN_BLOCKS: BlockNumber = 10 * MINUTES;
+	// short enough for the integration tests of the node to wait for eras.
+	#[cfg(feature = "election-test-runtime")]
+	pub const EPOCH_DURATION_IN_BLOCKS: BlockNumber = MINUTES;
 	pub const EPOCH_DURATION_IN_SLOTS: u64 = {
 		const SLOT_FILL_RATE: f64 = MILLISECS_PER_BLOCK as f64 / SLOT_DURATION as f64;
 
@@ -17,8 +17,17 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use frame_support::traits::{OnUnbalanced, Currency};
-use crate::{Balances, Authorship, NegativeImbalance};
+use frame_support::{
+	storage::{migration, StorageValue},
+	traits::{OnUnbalanced, Currency, OnRuntimeUpgrade},
+	weights::{Weight, constants::RocksDbWeight},
+};
+use crate::{Balances, Authorship, NegativeImbalance, AccountId};
+
+// The version of the storage the runtime migrates itself, outside of its pallets. Migrations
+// cannot go by `frame_system::LastRuntimeUpgrade`, which the executive moves to the new runtime
+// before running them.
+frame_support::generate_storage_alias!(Runtime, StorageVersion => Value<u16>);
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -27,6 +36,33 @@ impl OnUnbalanced<NegativeImbalance> for Author {
 	}
 }
 
+/// Hands the chain over to governance by removing the sudo key, once.
+///
+/// It came with the spec version 8 release. Every origin that used to be `EnsureRoot` only is
+/// also reachable from the council or the technical committee, and Root itself stays reachable
+/// through referenda. Once the key is gone `Sudo::key` falls back to the all-zero account, which
+/// nobody holds a secret for, so neither `sudo` nor `set_key` can be dispatched again.
+///
+/// The first upgrade of a chain to a runtime with it removes the key and moves the runtime's
+/// storage version to 1. Later upgrades leave the sudo storage alone, so that a key governance
+/// sets again with `System.set_storage`, e.g. for an emergency, survives them.
+pub struct SunsetSudo;
+impl OnRuntimeUpgrade for SunsetSudo {
+	fn on_runtime_upgrade() -> Weight {
+		if StorageVersion::get().unwrap_or_default() >= 1 {
+			return RocksDbWeight::get().reads(1);
+		}
+		StorageVersion::put(1);
+		match migration::take_storage_value::<AccountId>(b"Sudo", b"Key", &[]) {
+			Some(key) => {
+				log::info!(target: "runtime", "🌅 Sudo key {:?} removed, governance takes over", key);
+				RocksDbWeight::get().reads_writes(2, 2)
+			}
+			None => RocksDbWeight::get().reads_writes(2, 1),
+		}
+	}
+}
+
 #[cfg(test)]
 mod multiplier_tests {
 	use sp_runtime::{assert_eq_error_rate, FixedPointNumber, traits::{Convert, One, Zero}};
@@ -23,6 +23,7 @@
 #![recursion_limit = "256"]
 
 use codec::{Decode, Encode};
+use coinnet_runtime_api::DryRun;
 use frame_support::{
     construct_runtime, parameter_types,
     traits::{
@@ -66,8 +67,8 @@ use sp_runtime::transaction_validity::{
     TransactionPriority, TransactionSource, TransactionValidity,
 };
 use sp_runtime::{
-    create_runtime_str, generic, impl_opaque_keys, ApplyExtrinsicResult, FixedPointNumber, Perbill,
-    Percent, Permill, Perquintill,
+    create_runtime_str, generic, impl_opaque_keys, ApplyExtrinsicResult, FixedPointNumber, FixedU128,
+    Perbill, Percent, Permill, Perquintill,
 };
 use sp_std::prelude::*;
 #[cfg(any(feature = "std", test))]
@@ -80,13 +81,17 @@ pub use frame_system::Call as SystemCall;
 #[cfg(any(feature = "std", test))]
 pub use pallet_balances::Call as BalancesCall;
 #[cfg(any(feature = "std", test))]
+pub use pallet_claims::EthereumAddress;
+#[cfg(any(feature = "std", test))]
+pub use pallet_session::Call as SessionCall;
+#[cfg(any(feature = "std", test))]
 pub use pallet_staking::StakerStatus;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::Author;
+use impls::{Author, SunsetSudo};
 
 /// Constant values used within the runtime.
 pub mod constants;
@@ -116,8 +121,12 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // and set impl_version to 0. If only runtime
     // implementation changes and behavior does not, then leave spec_version as
     // is and increment impl_version.
-    spec_version: 6,
-    impl_version: 2,
+    #[cfg(not(feature = "upgrade-test-runtime"))]
+    spec_version: 16,
+    // the runtime the integration tests upgrade a test chain to.
+    #[cfg(feature = "upgrade-test-runtime")]
+    spec_version: 17,
+    impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
     transaction_version: 2,
 };
@@ -140,15 +149,18 @@ pub fn native_version() -> NativeVersion {
 
 type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
 
+/// The shares of transaction fees and tips that go to the treasury and to the block author.
+pub const FEE_SPLIT: (u32, u32) = (80, 20);
+
 pub struct DealWithFees;
 impl OnUnbalanced<NegativeImbalance> for DealWithFees {
     fn on_unbalanceds<B>(mut fees_then_tips: impl Iterator<Item = NegativeImbalance>) {
         if let Some(fees) = fees_then_tips.next() {
             // for fees, 80% to treasury, 20% to author
-            let mut split = fees.ration(80, 20);
+            let mut split = fees.ration(FEE_SPLIT.0, FEE_SPLIT.1);
             if let Some(tips) = fees_then_tips.next() {
                 // for tips, if any, 80% to treasury, 20% to author (though this can be anything)
-                tips.ration_merge_into(80, 20, &mut split);
+                tips.ration_merge_into(FEE_SPLIT.0, FEE_SPLIT.1, &mut split);
             }
             Treasury::on_unbalanced(split.0);
             Author::on_unbalanced(split.1);
@@ -284,6 +296,7 @@ impl InstanceFilter<Call> for ProxyType {
                     | Call::TechnicalCommittee(..)
                     | Call::Elections(..)
                     | Call::Treasury(..)
+                    | Call::AssetTreasury(..)
             ),
             ProxyType::Staking => matches!(c, Call::Staking(..)),
         }
@@ -326,7 +339,7 @@ impl pallet_scheduler::Config for Runtime {
     type PalletsOrigin = OriginCaller;
     type Call = Call;
     type MaximumWeight = MaximumSchedulerWeight;
-    type ScheduleOrigin = EnsureRoot<AccountId>;
+    type ScheduleOrigin = EnsureRootOrHalfCouncil;
     type MaxScheduledPerBlock = MaxScheduledPerBlock;
     type WeightInfo = pallet_scheduler::weights::SubstrateWeight<Runtime>;
 }
@@ -460,6 +473,8 @@ impl pallet_session::historical::Config for Runtime {
     type FullIdentificationOf = pallet_staking::ExposureOf<Runtime>;
 }
 
+// The curve `Inflation` starts from. Era payouts read the parameters from storage instead, this is
+// kept to check that the on-chain defaults match what the chain was launched with.
 pallet_staking_reward_curve::build! {
     const REWARD_CURVE: PiecewiseLinear<'static> = curve!(
         min_inflation: 0_025_000,
@@ -471,8 +486,14 @@ pallet_staking_reward_curve::build! {
     );
 }
 
+#[cfg(not(feature = "election-test-runtime"))]
+const SESSIONS_PER_ERA: sp_staking::SessionIndex = 6;
+// with the one-minute epochs of the feature, an era every two minutes.
+#[cfg(feature = "election-test-runtime")]
+const SESSIONS_PER_ERA: sp_staking::SessionIndex = 2;
+
 parameter_types! {
-    pub const SessionsPerEra: sp_staking::SessionIndex = 6;
+    pub const SessionsPerEra: sp_staking::SessionIndex = SESSIONS_PER_ERA;
     pub const BondingDuration: pallet_staking::EraIndex = 24 * 7;
     pub const SlashDeferDuration: pallet_staking::EraIndex = 6 * 7; // 1/4 the bonding duration.
     pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
@@ -498,13 +519,28 @@ impl pallet_staking::Config for Runtime {
         pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>,
     >;
     type SessionInterface = Self;
-    type EraPayout = pallet_staking::ConvertCurve<RewardCurve>;
+    type EraPayout = Inflation;
     type NextNewSession = Session;
     type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
     type ElectionProvider = ElectionProviderMultiPhase;
     type WeightInfo = pallet_staking::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+    pub const MaxInflation: Perbill = Perbill::from_percent(20);
+}
+
+impl pallet_inflation::Config for Runtime {
+    type Event = Event;
+    /// A super-majority of the council, or a referendum, can change the inflation curve.
+    type UpdateOrigin = EnsureOneOf<
+        AccountId,
+        EnsureRoot<AccountId>,
+        pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>,
+    >;
+    type MaxInflation = MaxInflation;
+}
+
 parameter_types! {
     // phase durations. 1/4 of the last session for each.
     pub const SignedPhase: u32 = EPOCH_DURATION_IN_BLOCKS / 4;
@@ -610,7 +646,12 @@ impl pallet_democracy::Config for Runtime {
         EnsureRoot<AccountId>,
         pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, TechnicalCollective>,
     >;
-    type BlacklistOrigin = EnsureRoot<AccountId>;
+    // A unanimous technical committee or Root can blacklist a proposal permanently.
+    type BlacklistOrigin = EnsureOneOf<
+        AccountId,
+        EnsureRoot<AccountId>,
+        pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, TechnicalCollective>,
+    >;
     // Any single technical committee member may veto a coming council proposal, however they can
     // only do it once and it lasts only for the cool-off period.
     type VetoOrigin = pallet_collective::EnsureMember<AccountId, TechnicalCollective>;
@@ -758,6 +799,12 @@ impl pallet_treasury::Config for Runtime {
     type MaxApprovals = MaxApprovals;
 }
 
+/// Asset spends reuse the treasury's origins, bond, spend period and account.
+impl pallet_asset_treasury::Config for Runtime {
+    type Event = Event;
+    type Assets = Assets;
+}
+
 impl pallet_bounties::Config for Runtime {
     type Event = Event;
     type BountyDepositBase = BountyDepositBase;
@@ -865,15 +912,7 @@ where
             // so the actual block number is `n`.
             .saturating_sub(1);
         let era = Era::mortal(period, current_block);
-        let extra = (
-            frame_system::CheckSpecVersion::<Runtime>::new(),
-            frame_system::CheckTxVersion::<Runtime>::new(),
-            frame_system::CheckGenesis::<Runtime>::new(),
-            frame_system::CheckEra::<Runtime>::from(era),
-            frame_system::CheckNonce::<Runtime>::from(nonce),
-            frame_system::CheckWeight::<Runtime>::new(),
-            pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
-        );
+        let extra = signed_extra(era, nonce, tip);
         let raw_payload = SignedPayload::new(call, extra)
             .map_err(|e| {
                 log::warn!("Unable to create signed payload: {:?}", e);
@@ -899,6 +938,36 @@ where
     type OverarchingCall = Call;
 }
 
+parameter_types! {
+    pub const MaxOraclePairs: u32 = 32;
+    pub const OracleFeedPeriod: BlockNumber = MINUTES;
+    pub const MaxPriceAge: BlockNumber = 10 * MINUTES;
+}
+
+/// Validators feed prices with the `orcl` oracle key they registered; the council picks the pairs.
+impl pallet_oracle::Config for Runtime {
+    type Event = Event;
+    type AuthorityId = pallet_oracle::crypto::OracleAuthId;
+    type AssetId = u32;
+    type Feeders = pallet_oracle::SessionValidators<Runtime>;
+    type UpdateOrigin = EnsureRootOrHalfCouncil;
+    type MaxPairs = MaxOraclePairs;
+    type FeedPeriod = OracleFeedPeriod;
+    type MaxPriceAge = MaxPriceAge;
+}
+
+parameter_types! {
+    pub const StakingPayoutPeriod: BlockNumber = HOURS;
+    pub const MaxPayoutsPerRun: u32 = 8;
+}
+
+/// Validators opting in pay out the rewards of the stash whose `stsh` key is in their keystore.
+impl pallet_staking_payout::Config for Runtime {
+    type AuthorityId = pallet_staking_payout::crypto::StashAuthId;
+    type PayoutPeriod = StakingPayoutPeriod;
+    type MaxPayoutsPerRun = MaxPayoutsPerRun;
+}
+
 impl pallet_im_online::Config for Runtime {
     type AuthorityId = ImOnlineId;
     type Event = Event;
@@ -999,6 +1068,16 @@ impl pallet_vesting::Config for Runtime {
     type WeightInfo = pallet_vesting::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+    pub Prefix: &'static [u8] = b"Pay 1CN to the 1COIN account:";
+}
+
+impl pallet_claims::Config for Runtime {
+    type Event = Event;
+    type VestingSchedule = Vesting;
+    type Prefix = Prefix;
+}
+
 impl pallet_mmr::Config for Runtime {
     const INDEXING_PREFIX: &'static [u8] = b"mmr";
     type Hashing = <Runtime as frame_system::Config>::Hashing;
@@ -1020,7 +1099,7 @@ impl pallet_lottery::Config for Runtime {
     type Currency = Balances;
     type Randomness = RandomnessCollectiveFlip;
     type Event = Event;
-    type ManagerOrigin = EnsureRoot<AccountId>;
+    type ManagerOrigin = EnsureRootOrHalfCouncil;
     type MaxCalls = MaxCalls;
     type ValidateCall = Lottery;
     type MaxGenerateRandom = MaxGenerateRandom;
@@ -1040,7 +1119,7 @@ impl pallet_assets::Config for Runtime {
     type Balance = u64;
     type AssetId = u32;
     type Currency = Balances;
-    type ForceOrigin = EnsureRoot<AccountId>;
+    type ForceOrigin = EnsureRootOrHalfCouncil;
     type AssetDeposit = AssetDeposit;
     type MetadataDepositBase = MetadataDepositBase;
     type MetadataDepositPerByte = MetadataDepositPerByte;
@@ -1051,6 +1130,11 @@ impl pallet_assets::Config for Runtime {
     type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
 }
 
+impl pallet_genesis_assets::Config for Runtime {
+    type GenesisAssetId = u32;
+    type GenesisBalance = u64;
+}
+
 /// Configure the pallet-template in pallets/template.
 impl pallet_template::Config for Runtime {
     type Event = Event;
@@ -1099,6 +1183,12 @@ construct_runtime!(
         Mmr: pallet_mmr::{Pallet, Storage},
         Lottery: pallet_lottery::{Pallet, Call, Storage, Event<T>},
         TemplateModule: pallet_template::{Pallet, Call, Storage, Event<T>},
+        Inflation: pallet_inflation::{Pallet, Call, Storage, Event},
+        AssetTreasury: pallet_asset_treasury::{Pallet, Call, Storage, Event<T>},
+        Oracle: pallet_oracle::{Pallet, Call, Storage, Event<T>},
+        Claims: pallet_claims::{Pallet, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
+        GenesisAssets: pallet_genesis_assets::{Pallet, Config<T>},
+        StakingPayout: pallet_staking_payout::{Pallet},
     }
 );
 
@@ -1130,6 +1220,20 @@ pub type SignedExtra = (
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
+
+/// The `SignedExtra` of a transaction with the given era, nonce and tip. The remaining
+/// extensions take what they check from the chain.
+pub fn signed_extra(era: Era, nonce: Index, tip: Balance) -> SignedExtra {
+    (
+        frame_system::CheckSpecVersion::<Runtime>::new(),
+        frame_system::CheckTxVersion::<Runtime>::new(),
+        frame_system::CheckGenesis::<Runtime>::new(),
+        frame_system::CheckEra::<Runtime>::from(era),
+        frame_system::CheckNonce::<Runtime>::from(nonce),
+        frame_system::CheckWeight::<Runtime>::new(),
+        pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+    )
+}
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
@@ -1139,7 +1243,7 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPallets,
-    (),
+    SunsetSudo,
 >;
 
 /// MMR helper types.
@@ -1360,6 +1464,74 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_oracle_runtime_api::OracleApi<Block, u32, BlockNumber> for Runtime {
+        fn price(base: u32, quote: u32) -> Option<FixedU128> {
+            Oracle::price(base, quote)
+        }
+
+        fn last_price(base: u32, quote: u32) -> Option<(FixedU128, BlockNumber)> {
+            Oracle::last_price(base, quote)
+        }
+    }
+
+    impl coinnet_runtime_api::DryRunApi<Block, Event, Balance> for Runtime {
+        fn dry_run(extrinsic: <Block as BlockT>::Extrinsic) -> DryRun<Event, Balance> {
+            use frame_support::weights::{GetDispatchInfo, PostDispatchInfo};
+
+            let len = extrinsic.encoded_size() as u32;
+            let info = extrinsic.get_dispatch_info();
+            // of the signed extensions, only `ChargeTransactionPayment` encodes more than its era
+            // and nonce: the tip.
+            let tip = extrinsic.signature.as_ref().map(|(_, _, extra)| {
+                codec::Compact::<Balance>::decode(&mut &extra.6.encode()[..]).map_or(0, |tip| tip.0)
+            });
+            let first_event = System::event_count() as usize;
+            let result = Executive::apply_extrinsic(extrinsic);
+            let events: Vec<Event> =
+                System::events().into_iter().skip(first_event).map(|record| record.event).collect();
+            let dispatched = events.iter().find_map(|event| match event {
+                Event::frame_system(frame_system::Event::ExtrinsicSuccess(info))
+                | Event::frame_system(frame_system::Event::ExtrinsicFailed(_, info)) => Some(*info),
+                _ => None,
+            });
+            let (weight, fee) = match (dispatched, tip) {
+                (Some(dispatched), Some(tip)) => {
+                    let post_info = PostDispatchInfo {
+                        actual_weight: Some(dispatched.weight),
+                        pays_fee: dispatched.pays_fee,
+                    };
+                    let fee = TransactionPayment::compute_actual_fee(len, &info, &post_info, tip);
+                    (dispatched.weight, fee)
+                }
+                (Some(dispatched), None) => (dispatched.weight, 0),
+                (None, _) => (0, 0),
+            };
+            DryRun { result, weight, fee, events }
+        }
+    }
+
+    impl coinnet_runtime_api::FeeMultiplierApi<Block> for Runtime {
+        fn estimate_multiplier(weight: Weight, blocks: u32) -> Multiplier {
+            use frame_support::storage::migration;
+            use sp_runtime::traits::Convert;
+            type FeeMultiplierUpdate =
+                <Runtime as pallet_transaction_payment::Config>::FeeMultiplierUpdate;
+
+            // the update reads the weight of the block it ends, and the state of the call is
+            // discarded.
+            migration::put_storage_value(
+                b"System",
+                b"BlockWeight",
+                &[],
+                frame_system::ConsumedWeight::default(),
+            );
+            System::register_extra_weight_unchecked(weight, DispatchClass::Normal);
+            (0..blocks).fold(TransactionPayment::next_fee_multiplier(), |multiplier, _| {
+                FeeMultiplierUpdate::convert(multiplier)
+            })
+        }
+    }
+
     impl pallet_mmr::primitives::MmrApi<
         Block,
         mmr::Hash,
@@ -1475,6 +1647,7 @@ impl_runtime_apis! {
             add_benchmark!(params, batches, pallet_utility, Utility);
             add_benchmark!(params, batches, pallet_vesting, Vesting);
             add_benchmark!(params, batches, pallet_template, TemplateModule);
+            add_benchmark!(params, batches, pallet_inflation, Inflation);
 
             if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
             Ok(batches)
@@ -1497,4 +1670,110 @@ mod tests {
 
         is_submit_signed_transaction::<Runtime>();
     }
+
+    fn council(ayes: u32, seats: u32) -> Origin {
+        pallet_collective::RawOrigin::<AccountId, CouncilCollective>::Members(ayes, seats).into()
+    }
+
+    fn technical_committee(ayes: u32, seats: u32) -> Origin {
+        pallet_collective::RawOrigin::<AccountId, TechnicalCollective>::Members(ayes, seats).into()
+    }
+
+    fn dispatches(call: Call, origin: Origin) -> bool {
+        use sp_runtime::traits::Dispatchable;
+        sp_io::TestExternalities::default().execute_with(|| call.dispatch(origin).is_ok())
+    }
+
+    #[test]
+    fn sunset_sudo_removes_the_key() {
+        use frame_support::{storage::migration, traits::OnRuntimeUpgrade};
+        use sp_runtime::traits::Dispatchable;
+
+        let alice = AccountId::from([1u8; 32]);
+        sp_io::TestExternalities::default().execute_with(|| {
+            migration::put_storage_value(b"Sudo", b"Key", &[], alice.clone());
+            assert_eq!(Sudo::key(), alice);
+
+            SunsetSudo::on_runtime_upgrade();
+            assert_eq!(Sudo::key(), AccountId::default());
+            let remark = Call::System(frame_system::Call::remark(vec![]));
+            let sudo = Call::Sudo(pallet_sudo::Call::sudo(Box::new(remark)));
+            assert!(sudo.dispatch(Origin::signed(alice.clone())).is_err());
+            let set_key = Call::Sudo(pallet_sudo::Call::set_key(alice.clone().into()));
+            assert!(set_key.dispatch(Origin::signed(alice.clone())).is_err());
+
+            // later upgrades keep a key governance sets again.
+            migration::put_storage_value(b"Sudo", b"Key", &[], alice.clone());
+            SunsetSudo::on_runtime_upgrade();
+            assert_eq!(Sudo::key(), alice);
+        });
+    }
+
+    #[test]
+    fn asset_force_origin_is_reachable_through_council() {
+        let owner = AccountId::from([1u8; 32]);
+        let call = Call::Assets(pallet_assets::Call::force_create(1, owner.clone().into(), true, 1));
+
+        assert!(dispatches(call.clone(), Origin::root()));
+        assert!(dispatches(call.clone(), council(2, 3)));
+        assert!(!dispatches(call.clone(), council(1, 2)));
+        assert!(!dispatches(call, Origin::signed(owner)));
+    }
+
+    #[test]
+    fn schedule_origin_is_reachable_through_council() {
+        let remark = Call::System(frame_system::Call::remark(vec![]));
+        let call = Call::Scheduler(pallet_scheduler::Call::schedule(10, None, 0, Box::new(remark)));
+
+        assert!(dispatches(call.clone(), Origin::root()));
+        assert!(dispatches(call.clone(), council(2, 3)));
+        assert!(!dispatches(call.clone(), council(1, 2)));
+        assert!(!dispatches(call, Origin::signed(AccountId::default())));
+    }
+
+    #[test]
+    fn blacklist_origin_is_reachable_through_technical_committee() {
+        let call = Call::Democracy(pallet_democracy::Call::blacklist(Hash::default(), None));
+
+        assert!(dispatches(call.clone(), Origin::root()));
+        assert!(dispatches(call.clone(), technical_committee(3, 3)));
+        assert!(!dispatches(call.clone(), technical_committee(2, 3)));
+        assert!(!dispatches(call, council(3, 3)));
+    }
+
+    #[test]
+    fn lottery_manager_origin_is_reachable_through_council() {
+        let call = Call::Lottery(pallet_lottery::Call::set_calls(vec![]));
+
+        assert!(dispatches(call.clone(), Origin::root()));
+        assert!(dispatches(call.clone(), council(2, 3)));
+        assert!(!dispatches(call.clone(), council(1, 2)));
+        assert!(!dispatches(call, Origin::signed(AccountId::default())));
+    }
+
+    #[test]
+    fn default_inflation_matches_reward_curve() {
+        use constants::currency::DOLLARS;
+        use pallet_staking::EraPayout;
+
+        const YEAR: u64 = 1000 * 3600 * 24 * 36525 / 100;
+        let issuance: Balance = 1_000_000_000 * DOLLARS;
+
+        sp_io::TestExternalities::default().execute_with(|| {
+            for percent in (0..=100).step_by(5) {
+                let staked = issuance / 100 * percent;
+                let (curve, curve_rest) =
+                    pallet_staking::ConvertCurve::<RewardCurve>::era_payout(staked, issuance, YEAR);
+                let (stored, stored_rest) = Inflation::era_payout(staked, issuance, YEAR);
+
+                // `REWARD_CURVE` is only precise to half a percent of its maximum.
+                let tolerance = issuance / 2_000;
+                assert!(
+                    curve.max(stored) - curve.min(stored) < tolerance,
+                    "{}% staked: {} vs {}", percent, curve, stored,
+                );
+                assert!(curve_rest.max(stored_rest) - curve_rest.min(stored_rest) < tolerance);
+            }
+        });
+    }
 }
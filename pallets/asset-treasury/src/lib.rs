@@ -0,0 +1,281 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # Asset Treasury Pallet
+//!
+//! Lets the treasury pay proposals in assets of the assets pallet, next to the native spends of
+//! `pallet_treasury`.
+//!
+//! Asset spends follow the life cycle of treasury proposals and reuse the treasury's
+//! configuration: a proposer reserves the treasury's `ProposalBondMinimum`, the treasury's
+//! `RejectOrigin` can reject the proposal (slashing the bond to `OnSlash`) and its `ApproveOrigin`
+//! can approve it. Approved spends are paid from the treasury account's asset balances every
+//! `SpendPeriod`. A spend the treasury cannot cover yet stays approved until it can.
+
+pub use pallet::*;
+
+use codec::{Decode, Encode};
+use frame_support::{
+	traits::{
+		tokens::fungibles::{Inspect, Transfer},
+		Currency, Get, OnUnbalanced, ReservableCurrency,
+	},
+	weights::Weight,
+};
+use sp_runtime::{
+	traits::{Saturating, Zero},
+	RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// An index of an asset spend proposal.
+pub type ProposalIndex = u32;
+
+type BalanceOf<T> = <<T as pallet_treasury::Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+type NegativeImbalanceOf<T> = <<T as pallet_treasury::Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::NegativeImbalance;
+type AssetIdOf<T> =
+	<<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::AssetId;
+type AssetBalanceOf<T> =
+	<<T as Config>::Assets as Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// A spending proposal paid in an asset.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct AssetProposal<AccountId, AssetId, AssetBalance, Balance> {
+	/// The account proposing it.
+	pub proposer: AccountId,
+	/// The asset the proposal is paid in.
+	pub asset_id: AssetId,
+	/// The amount of the asset to be paid.
+	pub value: AssetBalance,
+	/// The account to whom the payment should be made if the proposal is accepted.
+	pub beneficiary: AccountId,
+	/// The amount of native currency held on deposit (reserved) for making this proposal.
+	pub bond: Balance,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResult, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::StaticLookup;
+
+	/// The treasury whose account, origins, bond and spend period are used is the one configured
+	/// through `pallet_treasury::Config`.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_treasury::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The assets the treasury can spend.
+		type Assets: Transfer<Self::AccountId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Number of asset spend proposals that have been made.
+	#[pallet::storage]
+	#[pallet::getter(fn proposal_count)]
+	pub type ProposalCount<T> = StorageValue<_, ProposalIndex, ValueQuery>;
+
+	/// Asset spend proposals that have been made.
+	#[pallet::storage]
+	#[pallet::getter(fn proposals)]
+	pub type Proposals<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		ProposalIndex,
+		AssetProposal<T::AccountId, AssetIdOf<T>, AssetBalanceOf<T>, BalanceOf<T>>,
+	>;
+
+	/// Proposal indices that have been approved but not yet awarded.
+	#[pallet::storage]
+	#[pallet::getter(fn approvals)]
+	pub type Approvals<T> = StorageValue<_, Vec<ProposalIndex>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		AssetIdOf<T> = "AssetId",
+		AssetBalanceOf<T> = "AssetBalance",
+		BalanceOf<T> = "Balance"
+	)]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// New asset spend proposal. [proposal_index]
+		Proposed(ProposalIndex),
+		/// A proposal was rejected; the bond was slashed. [proposal_index, slashed]
+		Rejected(ProposalIndex, BalanceOf<T>),
+		/// A proposal was paid. [proposal_index, asset_id, award, beneficiary]
+		Awarded(ProposalIndex, AssetIdOf<T>, AssetBalanceOf<T>, T::AccountId),
+		/// The treasury could not pay an approved proposal this period. [proposal_index]
+		Postponed(ProposalIndex),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Proposer's balance is too low to reserve the bond.
+		InsufficientProposersBalance,
+		/// No proposal at that index.
+		InvalidIndex,
+		/// The proposal is already approved.
+		AlreadyApproved,
+		/// Too many approvals in the queue.
+		TooManyApprovals,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Pay the approved asset spends at the end of every spend period, like the treasury does
+		/// for native spends.
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			if (n % <T as pallet_treasury::Config>::SpendPeriod::get()).is_zero() {
+				Self::spend_funds()
+			} else {
+				0
+			}
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Put forward a suggestion for spending `value` of `asset_id`. The treasury's
+		/// `ProposalBondMinimum` is reserved and slashed if the proposal is rejected, or returned
+		/// once it is paid.
+		#[pallet::weight(20_000 + T::DbWeight::get().reads_writes(2, 3))]
+		pub fn propose_asset_spend(
+			origin: OriginFor<T>,
+			asset_id: AssetIdOf<T>,
+			value: AssetBalanceOf<T>,
+			beneficiary: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let proposer = ensure_signed(origin)?;
+			let beneficiary = T::Lookup::lookup(beneficiary)?;
+
+			let bond = <T as pallet_treasury::Config>::ProposalBondMinimum::get();
+			<T as pallet_treasury::Config>::Currency::reserve(&proposer, bond)
+				.map_err(|_| Error::<T>::InsufficientProposersBalance)?;
+
+			let index = Self::proposal_count();
+			<ProposalCount<T>>::put(index + 1);
+			<Proposals<T>>::insert(index, AssetProposal {
+				proposer,
+				asset_id,
+				value,
+				beneficiary,
+				bond,
+			});
+
+			Self::deposit_event(Event::Proposed(index));
+			Ok(())
+		}
+
+		/// Reject a proposed spend. The original deposit will be slashed.
+		///
+		/// The dispatch origin of this call must be the treasury's `RejectOrigin`.
+		#[pallet::weight(20_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn reject_asset_spend(
+			origin: OriginFor<T>,
+			#[pallet::compact] proposal_id: ProposalIndex,
+		) -> DispatchResult {
+			<T as pallet_treasury::Config>::RejectOrigin::ensure_origin(origin)?;
+
+			let proposal = <Proposals<T>>::take(proposal_id).ok_or(Error::<T>::InvalidIndex)?;
+			let imbalance: NegativeImbalanceOf<T> =
+				<T as pallet_treasury::Config>::Currency::slash_reserved(
+					&proposal.proposer,
+					proposal.bond,
+				).0;
+			<T as pallet_treasury::Config>::OnSlash::on_unbalanced(imbalance);
+			<Approvals<T>>::mutate(|approvals| approvals.retain(|index| *index != proposal_id));
+
+			Self::deposit_event(Event::Rejected(proposal_id, proposal.bond));
+			Ok(())
+		}
+
+		/// Approve a proposal. At a later time, the proposal will be paid from the treasury
+		/// account's balance of the asset and the original deposit will be returned.
+		///
+		/// The dispatch origin of this call must be the treasury's `ApproveOrigin`.
+		#[pallet::weight(20_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn approve_asset_spend(
+			origin: OriginFor<T>,
+			#[pallet::compact] proposal_id: ProposalIndex,
+		) -> DispatchResult {
+			<T as pallet_treasury::Config>::ApproveOrigin::ensure_origin(origin)?;
+
+			ensure!(<Proposals<T>>::contains_key(proposal_id), Error::<T>::InvalidIndex);
+			<Approvals<T>>::try_mutate(|approvals| -> DispatchResult {
+				ensure!(!approvals.contains(&proposal_id), Error::<T>::AlreadyApproved);
+				ensure!(
+					approvals.len() < <T as pallet_treasury::Config>::MaxApprovals::get() as usize,
+					Error::<T>::TooManyApprovals
+				);
+				approvals.push(proposal_id);
+				Ok(())
+			})
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Pay every approved proposal the treasury account holds enough of the asset for.
+	fn spend_funds() -> Weight {
+		let account = pallet_treasury::Pallet::<T>::account_id();
+		let mut weight = T::DbWeight::get().reads_writes(1, 1);
+
+		let mut approvals = Self::approvals();
+		approvals.retain(|index| {
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(3, 4));
+
+			let proposal = match <Proposals<T>>::get(index) {
+				Some(proposal) => proposal,
+				None => return false,
+			};
+			if T::Assets::balance(proposal.asset_id, &account) < proposal.value {
+				Self::deposit_event(Event::Postponed(*index));
+				return true;
+			}
+			match T::Assets::transfer(
+				proposal.asset_id,
+				&account,
+				&proposal.beneficiary,
+				proposal.value,
+				false,
+			) {
+				Ok(_) => {
+					<T as pallet_treasury::Config>::Currency::unreserve(
+						&proposal.proposer,
+						proposal.bond,
+					);
+					<Proposals<T>>::remove(index);
+					Self::deposit_event(Event::Awarded(
+						*index,
+						proposal.asset_id,
+						proposal.value,
+						proposal.beneficiary,
+					));
+					false
+				}
+				Err(_) => {
+					Self::deposit_event(Event::Postponed(*index));
+					true
+				}
+			}
+		});
+		<Approvals<T>>::put(approvals);
+
+		weight
+	}
+}
@@ -0,0 +1,116 @@
+use crate::{mock::*, AssetProposal, Error};
+use frame_support::{assert_noop, assert_ok, traits::OnInitialize};
+use sp_runtime::DispatchError;
+
+const ASSET: u32 = 7;
+
+fn treasury() -> u64 {
+	Treasury::account_id()
+}
+
+/// Create a sufficient asset owned by account 1 and give the treasury `amount` of it.
+fn fund_treasury(amount: u64) {
+	assert_ok!(Assets::force_create(Origin::root(), ASSET, 1, true, 1));
+	assert_ok!(Assets::mint(Origin::signed(1), ASSET, treasury(), amount));
+}
+
+#[test]
+fn propose_reserves_the_treasury_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::propose_asset_spend(Origin::signed(1), ASSET, 50, 3));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(AssetTreasury::proposal_count(), 1);
+		assert_eq!(
+			AssetTreasury::proposals(0),
+			Some(AssetProposal { proposer: 1, asset_id: ASSET, value: 50, beneficiary: 3, bond: 10 })
+		);
+	});
+}
+
+#[test]
+fn propose_fails_without_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AssetTreasury::propose_asset_spend(Origin::signed(4), ASSET, 50, 3),
+			Error::<Test>::InsufficientProposersBalance
+		);
+	});
+}
+
+#[test]
+fn approve_and_reject_need_the_treasury_origins() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::propose_asset_spend(Origin::signed(1), ASSET, 50, 3));
+		assert_noop!(AssetTreasury::approve_asset_spend(Origin::signed(1), 0), DispatchError::BadOrigin);
+		assert_noop!(AssetTreasury::reject_asset_spend(Origin::signed(1), 0), DispatchError::BadOrigin);
+	});
+}
+
+#[test]
+fn reject_slashes_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::propose_asset_spend(Origin::signed(1), ASSET, 50, 3));
+		assert_ok!(AssetTreasury::approve_asset_spend(Origin::root(), 0));
+		assert_ok!(AssetTreasury::reject_asset_spend(Origin::root(), 0));
+
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 90);
+		assert_eq!(AssetTreasury::proposals(0), None);
+		assert!(AssetTreasury::approvals().is_empty());
+		assert_noop!(AssetTreasury::reject_asset_spend(Origin::root(), 0), Error::<Test>::InvalidIndex);
+	});
+}
+
+#[test]
+fn approve_checks_index_duplicates_and_queue_length() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(AssetTreasury::approve_asset_spend(Origin::root(), 0), Error::<Test>::InvalidIndex);
+		for _ in 0..3 {
+			assert_ok!(AssetTreasury::propose_asset_spend(Origin::signed(1), ASSET, 50, 3));
+		}
+		assert_ok!(AssetTreasury::approve_asset_spend(Origin::root(), 0));
+		assert_noop!(AssetTreasury::approve_asset_spend(Origin::root(), 0), Error::<Test>::AlreadyApproved);
+		assert_ok!(AssetTreasury::approve_asset_spend(Origin::root(), 1));
+		assert_noop!(AssetTreasury::approve_asset_spend(Origin::root(), 2), Error::<Test>::TooManyApprovals);
+	});
+}
+
+#[test]
+fn approved_spend_is_paid_at_the_spend_period() {
+	new_test_ext().execute_with(|| {
+		fund_treasury(100);
+		assert_ok!(AssetTreasury::propose_asset_spend(Origin::signed(1), ASSET, 60, 3));
+		assert_ok!(AssetTreasury::approve_asset_spend(Origin::root(), 0));
+
+		// not a spend period yet.
+		AssetTreasury::on_initialize(1);
+		assert_eq!(Assets::balance(ASSET, 3), 0);
+
+		AssetTreasury::on_initialize(2);
+		assert_eq!(Assets::balance(ASSET, 3), 60);
+		assert_eq!(Assets::balance(ASSET, treasury()), 40);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 100);
+		assert_eq!(AssetTreasury::proposals(0), None);
+		assert!(AssetTreasury::approvals().is_empty());
+	});
+}
+
+#[test]
+fn unaffordable_spend_stays_approved() {
+	new_test_ext().execute_with(|| {
+		fund_treasury(50);
+		assert_ok!(AssetTreasury::propose_asset_spend(Origin::signed(1), ASSET, 60, 3));
+		assert_ok!(AssetTreasury::approve_asset_spend(Origin::root(), 0));
+
+		AssetTreasury::on_initialize(2);
+		assert_eq!(Assets::balance(ASSET, 3), 0);
+		assert_eq!(AssetTreasury::approvals(), vec![0]);
+		assert_eq!(Balances::reserved_balance(1), 10);
+
+		assert_ok!(Assets::mint(Origin::signed(1), ASSET, treasury(), 10));
+		AssetTreasury::on_initialize(4);
+		assert_eq!(Assets::balance(ASSET, 3), 60);
+		assert!(AssetTreasury::approvals().is_empty());
+	});
+}
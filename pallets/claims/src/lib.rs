@@ -0,0 +1,358 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # Claims Pallet
+//!
+//! Lets holders of Ethereum addresses claim the 1CN allotted to them, e.g. by the snapshot of an
+//! ERC-20 token, in the spirit of Polkadot's `claims` module.
+//!
+//! Claims are set in the genesis config or with the root `mint_claim` call, optionally with a
+//! vesting schedule that is handed to the `VestingSchedule` (usually `pallet_vesting`) of the
+//! claiming account. To claim, the holder signs the message
+//! `Prefix ++ hex(SCALE-encoded destination account)` with `personal_sign` (the Ethereum signed
+//! message format) and submits it in an unsigned `claim` transaction, so the destination account
+//! does not need any funds to pay fees. Invalid signatures are rejected by `ValidateUnsigned`
+//! before they reach a block.
+
+pub use pallet::*;
+
+use codec::{Decode, Encode};
+use frame_support::traits::{Currency, Get, VestingSchedule};
+use sp_io::{crypto::secp256k1_ecdsa_recover, hashing::keccak_256};
+use sp_runtime::{
+	traits::{CheckedSub, Saturating},
+	RuntimeDebug,
+};
+use sp_std::prelude::*;
+#[cfg(feature = "std")]
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+type CurrencyOf<T> = <<T as Config>::VestingSchedule as VestingSchedule<
+	<T as frame_system::Config>::AccountId,
+>>::Currency;
+type BalanceOf<T> = <CurrencyOf<T> as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// A vesting schedule of a claim: `(locked, per_block, starting_block)`.
+pub type VestingOf<T> = (BalanceOf<T>, BalanceOf<T>, <T as frame_system::Config>::BlockNumber);
+
+/// An Ethereum address (i.e. 20 bytes, used to represent an Ethereum account).
+///
+/// This gets serialized to the 0x-prefixed hex representation.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+pub struct EthereumAddress(pub [u8; 20]);
+
+#[cfg(feature = "std")]
+impl Serialize for EthereumAddress {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&sp_core::bytes::to_hex(&self.0[..], false))
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for EthereumAddress {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let base_string = String::deserialize(deserializer)?;
+		let offset = if base_string.starts_with("0x") { 2 } else { 0 };
+		let s = &base_string[offset..];
+		if s.len() != 40 {
+			return Err(serde::de::Error::custom(
+				"Bad length of Ethereum address (should be 42 including '0x')",
+			));
+		}
+		let raw = sp_core::bytes::from_hex(s)
+			.map_err(|e| serde::de::Error::custom(format!("{:?}", e)))?;
+		let mut r = Self::default();
+		r.0.copy_from_slice(&raw);
+		Ok(r)
+	}
+}
+
+/// A 65-byte recoverable secp256k1 signature, as produced by Ethereum wallets.
+#[derive(Encode, Decode, Clone)]
+pub struct EcdsaSignature(pub [u8; 65]);
+
+impl PartialEq for EcdsaSignature {
+	fn eq(&self, other: &Self) -> bool {
+		&self.0[..] == &other.0[..]
+	}
+}
+
+impl sp_std::fmt::Debug for EcdsaSignature {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+		write!(f, "EcdsaSignature({:?})", &self.0[..])
+	}
+}
+
+/// Custom validity errors of unsigned `claim` transactions.
+#[repr(u8)]
+pub enum ValidityError {
+	/// The Ethereum signature is invalid.
+	InvalidEthereumSignature = 0,
+	/// The signer has no claim.
+	SignerHasNoClaim = 1,
+}
+
+impl From<ValidityError> for u8 {
+	fn from(err: ValidityError) -> Self {
+		err as u8
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResult, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Where the vesting schedules of claims go; its currency is the one claims are paid in.
+		type VestingSchedule: VestingSchedule<Self::AccountId, Moment = Self::BlockNumber>;
+
+		/// The chain-specific text signed messages start with, so that a signature cannot be
+		/// replayed on another chain.
+		#[pallet::constant]
+		type Prefix: Get<&'static [u8]>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The amount each Ethereum address can claim.
+	#[pallet::storage]
+	#[pallet::getter(fn claims)]
+	pub type Claims<T: Config> = StorageMap<_, Identity, EthereumAddress, BalanceOf<T>>;
+
+	/// The sum of all unclaimed claims.
+	#[pallet::storage]
+	#[pallet::getter(fn total)]
+	pub type Total<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	/// The vesting schedule of a claim, as `(locked, per_block, starting_block)`. The locked
+	/// part of the claim is released by the vesting schedule of the claiming account.
+	#[pallet::storage]
+	#[pallet::getter(fn vesting)]
+	pub type Vesting<T: Config> = StorageMap<_, Identity, EthereumAddress, VestingOf<T>>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The claimable amount of each Ethereum address.
+		pub claims: Vec<(EthereumAddress, BalanceOf<T>)>,
+		/// The vesting schedules of some of the claims.
+		pub vesting: Vec<(EthereumAddress, VestingOf<T>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			GenesisConfig { claims: vec![], vesting: vec![] }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			let mut total = BalanceOf::<T>::default();
+			for (address, value) in &self.claims {
+				<Claims<T>>::insert(address, value);
+				total = total.saturating_add(*value);
+			}
+			<Total<T>>::put(total);
+			for (address, schedule) in &self.vesting {
+				<Vesting<T>>::insert(address, schedule);
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", BalanceOf<T> = "Balance")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Someone claimed some 1CN. [who, ethereum_address, amount]
+		Claimed(T::AccountId, EthereumAddress, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Invalid Ethereum signature.
+		InvalidEthereumSignature,
+		/// Ethereum address has no claim.
+		SignerHasNoClaim,
+		/// There's not enough in the pot to pay out some unvested amount. Generally implies a
+		/// logic error.
+		PotUnderflow,
+		/// The account already has a vested balance, so it cannot receive another vesting
+		/// schedule.
+		VestedBalanceExists,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Make a claim to collect 1CN into `dest`.
+		///
+		/// The dispatch origin for this call must be _None_. Unsigned validation ensures that the
+		/// signature is valid and that the signer has a claim.
+		///
+		/// - `dest`: The account the claimed 1CN are deposited into.
+		/// - `ethereum_signature`: The signature of an Ethereum signed message matching the
+		///   format described above.
+		#[pallet::weight(100_000_000 + T::DbWeight::get().reads_writes(6, 6))]
+		pub fn claim(
+			origin: OriginFor<T>,
+			dest: T::AccountId,
+			ethereum_signature: EcdsaSignature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+
+			let data = dest.using_encoded(to_ascii_hex);
+			let signer = Self::eth_recover(&ethereum_signature, &data, &[][..])
+				.ok_or(Error::<T>::InvalidEthereumSignature)?;
+
+			Self::process_claim(signer, dest)
+		}
+
+		/// Add a new claim, or replace an existing one, for `who`.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// - `value`: The amount of 1CN that `who` can claim.
+		/// - `vesting_schedule`: An optional `(locked, per_block, starting_block)` vesting
+		///   schedule of the claim.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 3))]
+		pub fn mint_claim(
+			origin: OriginFor<T>,
+			who: EthereumAddress,
+			value: BalanceOf<T>,
+			vesting_schedule: Option<VestingOf<T>>,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+
+			let previous = <Claims<T>>::get(&who).unwrap_or_default();
+			<Total<T>>::mutate(|total| *total = total.saturating_sub(previous).saturating_add(value));
+			<Claims<T>>::insert(who, value);
+			match vesting_schedule {
+				Some(schedule) => <Vesting<T>>::insert(who, schedule),
+				None => <Vesting<T>>::remove(who),
+			}
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			const PRIORITY: u64 = 100;
+
+			let signer = match call {
+				Call::claim(account, ethereum_signature) => {
+					let data = account.using_encoded(to_ascii_hex);
+					Self::eth_recover(ethereum_signature, &data, &[][..])
+				}
+				_ => return Err(InvalidTransaction::Call.into()),
+			};
+
+			let signer = signer.ok_or(InvalidTransaction::Custom(
+				ValidityError::InvalidEthereumSignature.into(),
+			))?;
+			ensure!(
+				<Claims<T>>::contains_key(&signer),
+				InvalidTransaction::Custom(ValidityError::SignerHasNoClaim.into())
+			);
+
+			ValidTransaction::with_tag_prefix("Claims")
+				.priority(PRIORITY)
+				.and_provides(signer)
+				.longevity(TransactionLongevity::max_value())
+				.propagate(true)
+				.build()
+		}
+	}
+}
+
+/// Converts the given binary data into ASCII-encoded hex. It will be twice the length.
+fn to_ascii_hex(data: &[u8]) -> Vec<u8> {
+	let mut r = Vec::with_capacity(data.len() * 2);
+	let mut push_nibble = |n| r.push(if n < 10 { b'0' + n } else { b'a' - 10 + n });
+	for &b in data.iter() {
+		push_nibble(b / 16);
+		push_nibble(b % 16);
+	}
+	r
+}
+
+impl<T: Config> Pallet<T> {
+	/// Constructs the message that Ethereum RPC's `personal_sign` and `eth_sign` would sign.
+	fn ethereum_signable_message(what: &[u8], extra: &[u8]) -> Vec<u8> {
+		let prefix = T::Prefix::get();
+		let mut l = prefix.len() + what.len() + extra.len();
+		let mut rev = Vec::new();
+		while l > 0 {
+			rev.push(b'0' + (l % 10) as u8);
+			l /= 10;
+		}
+		let mut v = b"\x19Ethereum Signed Message:\n".to_vec();
+		v.extend(rev.into_iter().rev());
+		v.extend_from_slice(&prefix[..]);
+		v.extend_from_slice(what);
+		v.extend_from_slice(extra);
+		v
+	}
+
+	/// Attempts to recover the Ethereum address from a message signature signed by using
+	/// the Ethereum RPC's `personal_sign` and `eth_sign`.
+	fn eth_recover(s: &EcdsaSignature, what: &[u8], extra: &[u8]) -> Option<EthereumAddress> {
+		let msg = keccak_256(&Self::ethereum_signable_message(what, extra));
+		let mut res = EthereumAddress::default();
+		res.0.copy_from_slice(&keccak_256(&secp256k1_ecdsa_recover(&s.0, &msg).ok()?[..])[12..]);
+		Some(res)
+	}
+
+	/// Pay the claim of `signer` into `dest`, with its vesting schedule if it has one.
+	fn process_claim(signer: EthereumAddress, dest: T::AccountId) -> sp_runtime::DispatchResult {
+		let balance_due = <Claims<T>>::get(&signer).ok_or(Error::<T>::SignerHasNoClaim)?;
+		let new_total = Self::total().checked_sub(&balance_due).ok_or(Error::<T>::PotUnderflow)?;
+
+		let vesting = <Vesting<T>>::get(&signer);
+		if vesting.is_some() && T::VestingSchedule::vesting_balance(&dest).is_some() {
+			return Err(Error::<T>::VestedBalanceExists.into());
+		}
+
+		// We first need to deposit the balance to ensure that the account exists.
+		CurrencyOf::<T>::deposit_creating(&dest, balance_due);
+
+		// Check if this claim should have a vesting schedule.
+		if let Some(vs) = vesting {
+			// This can only fail if the account already has a vesting schedule,
+			// but this is checked above.
+			T::VestingSchedule::add_vesting_schedule(&dest, vs.0, vs.1, vs.2)
+				.expect("No other vesting schedule exists, as checked above; qed");
+		}
+
+		<Total<T>>::put(new_total);
+		<Claims<T>>::remove(&signer);
+		<Vesting<T>>::remove(&signer);
+
+		Self::deposit_event(Event::Claimed(dest, signer, balance_due));
+		Ok(())
+	}
+}
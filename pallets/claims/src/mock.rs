@@ -0,0 +1,111 @@
+use crate as pallet_claims;
+use frame_support::{parameter_types, traits::GenesisBuild};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, Identity, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Vesting: pallet_vesting::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Claims: pallet_claims::{Pallet, Call, Storage, Config<T>, Event<T>, ValidateUnsigned},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<u64>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+    type OnSetCode = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = ();
+    type Balance = u64;
+    type DustRemoval = ();
+    type Event = Event;
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const MinVestedTransfer: u64 = 1;
+}
+
+impl pallet_vesting::Config for Test {
+    type Event = Event;
+    type Currency = Balances;
+    type BlockNumberToBalance = Identity;
+    type MinVestedTransfer = MinVestedTransfer;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub Prefix: &'static [u8] = b"Pay 1CN to the test account:";
+}
+
+impl pallet_claims::Config for Test {
+    type Event = Event;
+    type VestingSchedule = Vesting;
+    type Prefix = Prefix;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext(claims: pallet_claims::GenesisConfig<Test>) -> sp_io::TestExternalities {
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    pallet_vesting::GenesisConfig::<Test> {
+        vesting: vec![(3, 0, 10, 0)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(3, 100)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    claims.assimilate_storage(&mut t).unwrap();
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
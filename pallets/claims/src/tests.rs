@@ -0,0 +1,164 @@
+use crate::{
+	mock::*, to_ascii_hex, EcdsaSignature, Error, EthereumAddress, GenesisConfig, ValidityError,
+};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::VestingSchedule, unsigned::ValidateUnsigned};
+use sp_io::hashing::keccak_256;
+use sp_runtime::{
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	DispatchError,
+};
+
+fn alice() -> secp256k1::SecretKey {
+	secp256k1::SecretKey::parse(&keccak_256(b"Alice")).unwrap()
+}
+
+fn bob() -> secp256k1::SecretKey {
+	secp256k1::SecretKey::parse(&keccak_256(b"Bob")).unwrap()
+}
+
+fn eth(secret: &secp256k1::SecretKey) -> EthereumAddress {
+	let public = secp256k1::PublicKey::from_secret_key(secret);
+	let mut address = EthereumAddress::default();
+	address.0.copy_from_slice(&keccak_256(&public.serialize()[1..65])[12..]);
+	address
+}
+
+/// Sign a claim of `secret` into `dest` the way an Ethereum wallet's `personal_sign` would.
+fn sig(secret: &secp256k1::SecretKey, dest: u64) -> EcdsaSignature {
+	let what = dest.using_encoded(to_ascii_hex);
+	let msg = keccak_256(&Claims::ethereum_signable_message(&what, &[][..]));
+	let (sig, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&msg), secret);
+	let mut r = [0u8; 65];
+	r[0..64].copy_from_slice(&sig.serialize()[..]);
+	r[64] = recovery_id.serialize();
+	EcdsaSignature(r)
+}
+
+fn genesis() -> GenesisConfig<Test> {
+	GenesisConfig {
+		claims: vec![(eth(&alice()), 100), (eth(&bob()), 200)],
+		vesting: vec![(eth(&bob()), (50, 10, 1))],
+	}
+}
+
+#[test]
+fn genesis_sets_claims_and_total() {
+	new_test_ext(genesis()).execute_with(|| {
+		assert_eq!(Claims::total(), 300);
+		assert_eq!(Claims::claims(eth(&alice())), Some(100));
+		assert_eq!(Claims::vesting(eth(&bob())), Some((50, 10, 1)));
+	});
+}
+
+#[test]
+fn claiming_pays_the_destination() {
+	new_test_ext(genesis()).execute_with(|| {
+		assert_ok!(Claims::claim(Origin::none(), 42, sig(&alice(), 42)));
+		assert_eq!(Balances::free_balance(42), 100);
+		assert_eq!(Vesting::vesting_balance(&42), None);
+		assert_eq!(Claims::total(), 200);
+		assert_eq!(Claims::claims(eth(&alice())), None);
+
+		assert_noop!(
+			Claims::claim(Origin::none(), 42, sig(&alice(), 42)),
+			Error::<Test>::SignerHasNoClaim
+		);
+	});
+}
+
+#[test]
+fn claiming_with_vesting_locks_the_vested_part() {
+	new_test_ext(genesis()).execute_with(|| {
+		assert_ok!(Claims::claim(Origin::none(), 42, sig(&bob(), 42)));
+		assert_eq!(Balances::free_balance(42), 200);
+		assert_eq!(Vesting::vesting_balance(&42), Some(50));
+		assert_eq!(Claims::vesting(eth(&bob())), None);
+	});
+}
+
+#[test]
+fn vesting_claim_needs_an_account_without_vesting() {
+	new_test_ext(genesis()).execute_with(|| {
+		assert_noop!(
+			Claims::claim(Origin::none(), 3, sig(&bob(), 3)),
+			Error::<Test>::VestedBalanceExists
+		);
+		// claims without vesting are fine.
+		assert_ok!(Claims::claim(Origin::none(), 3, sig(&alice(), 3)));
+	});
+}
+
+#[test]
+fn claim_must_be_unsigned_and_signed_for_the_destination() {
+	new_test_ext(genesis()).execute_with(|| {
+		assert_noop!(
+			Claims::claim(Origin::signed(42), 42, sig(&alice(), 42)),
+			DispatchError::BadOrigin
+		);
+		// signed for 42, but claimed into 43: recovers some other address.
+		assert_noop!(
+			Claims::claim(Origin::none(), 43, sig(&alice(), 42)),
+			Error::<Test>::SignerHasNoClaim
+		);
+		assert_noop!(
+			Claims::claim(Origin::none(), 42, EcdsaSignature([0; 65])),
+			Error::<Test>::InvalidEthereumSignature
+		);
+	});
+}
+
+#[test]
+fn mint_claim_is_root_only_and_keeps_the_total() {
+	new_test_ext(genesis()).execute_with(|| {
+		let charlie = secp256k1::SecretKey::parse(&keccak_256(b"Charlie")).unwrap();
+		assert_noop!(
+			Claims::mint_claim(Origin::signed(42), eth(&charlie), 10, None),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Claims::mint_claim(Origin::root(), eth(&charlie), 10, Some((5, 1, 1))));
+		assert_eq!(Claims::total(), 310);
+		// replacing a claim replaces its amount and vesting.
+		assert_ok!(Claims::mint_claim(Origin::root(), eth(&bob()), 20, None));
+		assert_eq!(Claims::total(), 130);
+		assert_eq!(Claims::vesting(eth(&bob())), None);
+
+		assert_ok!(Claims::claim(Origin::none(), 42, sig(&charlie, 42)));
+		assert_eq!(Balances::free_balance(42), 10);
+		assert_eq!(Vesting::vesting_balance(&42), Some(5));
+	});
+}
+
+#[test]
+fn validate_unsigned_rejects_bad_claims() {
+	new_test_ext(genesis()).execute_with(|| {
+		let source = TransactionSource::External;
+		assert!(Claims::validate_unsigned(source, &crate::Call::claim(42, sig(&alice(), 42))).is_ok());
+		assert_eq!(
+			Claims::validate_unsigned(source, &crate::Call::claim(42, EcdsaSignature([0; 65]))),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(
+				ValidityError::InvalidEthereumSignature.into()
+			))),
+		);
+		let charlie = secp256k1::SecretKey::parse(&keccak_256(b"Charlie")).unwrap();
+		assert_eq!(
+			Claims::validate_unsigned(source, &crate::Call::claim(42, sig(&charlie, 42))),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(
+				ValidityError::SignerHasNoClaim.into()
+			))),
+		);
+		assert_eq!(
+			Claims::validate_unsigned(source, &crate::Call::mint_claim(eth(&charlie), 1, None)),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Call)),
+		);
+	});
+}
+
+#[test]
+fn ethereum_address_serializes_as_hex() {
+	let address = EthereumAddress([0xab; 20]);
+	let json = serde_json::to_string(&address).unwrap();
+	assert_eq!(json, "\"0xabababababababababababababababababababab\"");
+	assert_eq!(serde_json::from_str::<EthereumAddress>(&json).unwrap(), address);
+	assert!(serde_json::from_str::<EthereumAddress>("\"0xabab\"").is_err());
+}
@@ -0,0 +1,105 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # Genesis Assets Pallet
+//!
+//! `pallet_assets` has no genesis config, so networks could only get their assets from
+//! governance calls after launch. This pallet adds one: at genesis it creates the listed assets
+//! with `force_create`, describes them with `force_set_metadata` and mints the initial balances.
+//! It has no storage and no calls of its own.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{
+		pallet_prelude::*,
+		traits::tokens::fungibles::Mutate,
+	};
+	use frame_system::{pallet_prelude::*, RawOrigin};
+	use sp_runtime::traits::{MaybeSerializeDeserialize, StaticLookup};
+	use sp_std::prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_assets::Config {
+		/// The asset id of `pallet_assets`, as written in the genesis config.
+		type GenesisAssetId: Member
+			+ Parameter
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ Into<<Self as pallet_assets::Config>::AssetId>;
+
+		/// The asset balance of `pallet_assets`, as written in the genesis config.
+		type GenesisBalance: Member
+			+ Parameter
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ Into<<Self as pallet_assets::Config>::Balance>;
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// The assets to create, as `(id, owner, is_sufficient, min_balance)`.
+		pub assets: Vec<(T::GenesisAssetId, T::AccountId, bool, T::GenesisBalance)>,
+		/// The metadata of the assets, as `(id, name, symbol, decimals)`.
+		pub metadata: Vec<(T::GenesisAssetId, Vec<u8>, Vec<u8>, u8)>,
+		/// The balances to mint, as `(id, account, balance)`.
+		pub accounts: Vec<(T::GenesisAssetId, T::AccountId, T::GenesisBalance)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			GenesisConfig { assets: vec![], metadata: vec![], accounts: vec![] }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (id, owner, is_sufficient, min_balance) in &self.assets {
+				pallet_assets::Pallet::<T>::force_create(
+					RawOrigin::Root.into(),
+					(*id).into(),
+					T::Lookup::unlookup(owner.clone()),
+					*is_sufficient,
+					(*min_balance).into(),
+				)
+				.expect("genesis assets must have unique ids and a non-zero min balance");
+			}
+			for (id, name, symbol, decimals) in &self.metadata {
+				pallet_assets::Pallet::<T>::force_set_metadata(
+					RawOrigin::Root.into(),
+					(*id).into(),
+					name.clone(),
+					symbol.clone(),
+					*decimals,
+					false,
+				)
+				.expect("genesis metadata must be of created assets and within the string limit");
+			}
+			for (id, who, amount) in &self.accounts {
+				<pallet_assets::Pallet<T> as Mutate<T::AccountId>>::mint_into(
+					(*id).into(),
+					who,
+					(*amount).into(),
+				)
+				.expect("genesis balances must be of created assets and reach their min balance");
+			}
+		}
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {}
+}
@@ -0,0 +1,38 @@
+use crate::{mock::*, GenesisConfig};
+use frame_support::traits::tokens::fungibles::Inspect;
+
+fn genesis() -> GenesisConfig<Test> {
+	GenesisConfig {
+		assets: vec![(7, 1, true, 5), (8, 2, false, 1)],
+		metadata: vec![(7, b"Dollar".to_vec(), b"USD".to_vec(), 6)],
+		accounts: vec![(7, 3, 50), (8, 2, 20)],
+	}
+}
+
+#[test]
+fn genesis_creates_and_mints_assets() {
+	new_test_ext(genesis()).execute_with(|| {
+		assert_eq!(<Assets as Inspect<u64>>::minimum_balance(7), 5);
+		assert_eq!(<Assets as Inspect<u64>>::minimum_balance(8), 1);
+		// sufficient assets can be minted into accounts without native balance.
+		assert_eq!(<Assets as Inspect<u64>>::balance(7, &3), 50);
+		assert_eq!(<Assets as Inspect<u64>>::balance(8, &2), 20);
+		assert_eq!(<Assets as Inspect<u64>>::total_issuance(7), 50);
+	});
+}
+
+#[test]
+#[should_panic(expected = "genesis assets must have unique ids")]
+fn duplicate_asset_ids_are_rejected() {
+	let mut config = genesis();
+	config.assets.push((7, 2, true, 1));
+	new_test_ext(config);
+}
+
+#[test]
+#[should_panic(expected = "genesis balances must be of created assets")]
+fn balances_below_the_min_balance_are_rejected() {
+	let mut config = genesis();
+	config.accounts.push((7, 4, 1));
+	new_test_ext(config);
+}
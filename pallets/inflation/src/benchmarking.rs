@@ -0,0 +1,28 @@
+//! Benchmarking setup for pallet-inflation
+
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+#[allow(unused)]
+use crate::Pallet as Inflation;
+
+benchmarks! {
+	set_parameters {
+		let parameters = InflationParameters {
+			min_inflation: Perbill::from_percent(1),
+			max_inflation: Perbill::from_percent(8),
+			ideal_stake: Perbill::from_percent(60),
+			falloff: Perbill::from_percent(10),
+		};
+	}: _(RawOrigin::Root, parameters)
+	verify {
+		assert_eq!(Parameters::<T>::get(), parameters);
+	}
+}
+
+impl_benchmark_test_suite!(
+	Inflation,
+	crate::mock::new_test_ext(),
+	crate::mock::Test,
+);
@@ -0,0 +1,246 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # Inflation Pallet
+//!
+//! Keeps the parameters of the NPoS inflation curve in storage so that they can be changed by
+//! governance without a runtime upgrade, and implements [`pallet_staking::EraPayout`] on top of
+//! them.
+//!
+//! The curve is the same one `pallet_staking_reward_curve::build!` computes at compile time:
+//! the yearly inflation paid to stakers grows linearly from `min_inflation` (nothing staked) to
+//! `max_inflation` (staking rate at `ideal_stake`), and past the ideal stake it decays back
+//! towards `min_inflation`, halving its distance every `falloff`. Whatever is not paid to stakers
+//! out of `max_inflation` is handed to the `RewardRemainder` of the staking pallet.
+
+pub use pallet::*;
+
+use codec::{Decode, Encode};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, SaturatedConversion},
+	PerThing, Perbill, Perquintill, RuntimeDebug,
+};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+/// Same value as `pallet_staking::inflation` uses: a year of 365.25 days.
+const MILLISECONDS_PER_YEAR: u64 = 1000 * 3600 * 24 * 36525 / 100;
+
+/// The smallest falloff accepted; `pallet_staking_reward_curve` rejects anything below it too.
+pub const MIN_FALLOFF: Perbill = Perbill::from_percent(1);
+
+/// `2^(-1/2^i)` for `i` in `1..=20`, in parts per quintillion.
+const HALVING_ROOTS: [u128; 20] = [
+	707_106_781_186_547_524,
+	840_896_415_253_714_543,
+	917_004_043_204_671_232,
+	957_603_280_698_573_647,
+	978_572_062_087_700_135,
+	989_228_013_193_975_484,
+	994_599_423_483_633_176,
+	997_296_056_085_470_126,
+	998_647_112_890_970_174,
+	999_323_327_502_650_752,
+	999_661_606_496_243_684,
+	999_830_788_931_929_063,
+	999_915_390_886_613_498,
+	999_957_694_548_431_133,
+	999_978_847_050_491_930,
+	999_989_423_469_314_464,
+	999_994_711_720_674_283,
+	999_997_355_856_841_395,
+	999_998_677_927_546_760,
+	999_999_338_963_554_895,
+];
+
+const ACCURACY: u128 = 1_000_000_000_000_000_000;
+
+/// The parameters of the inflation curve.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct InflationParameters {
+	/// Yearly inflation when nothing is staked, and the asymptote past the ideal stake.
+	pub min_inflation: Perbill,
+	/// Yearly inflation when exactly `ideal_stake` of the issuance is staked.
+	pub max_inflation: Perbill,
+	/// The staking rate the curve is built to reward the most.
+	pub ideal_stake: Perbill,
+	/// How far past `ideal_stake` the rate has to go for the inflation above the minimum to halve.
+	pub falloff: Perbill,
+}
+
+impl Default for InflationParameters {
+	/// The curve the runtime shipped with as a compile-time `REWARD_CURVE`.
+	fn default() -> Self {
+		InflationParameters {
+			min_inflation: Perbill::from_parts(25_000_000),
+			max_inflation: Perbill::from_percent(10),
+			ideal_stake: Perbill::from_percent(50),
+			falloff: Perbill::from_percent(5),
+		}
+	}
+}
+
+fn perquintill_parts(p: Perbill) -> u128 {
+	p.deconstruct() as u128 * 1_000_000_000
+}
+
+/// `2^(-num/den)` in parts per quintillion.
+fn pow2_neg(num: u128, den: u128) -> u128 {
+	if den == 0 {
+		return 0;
+	}
+	let whole = num / den;
+	if whole >= 64 {
+		return 0;
+	}
+	let mut rem = num % den;
+	let mut result = ACCURACY;
+	for root in HALVING_ROOTS.iter() {
+		rem *= 2;
+		if rem >= den {
+			rem -= den;
+			result = result * root / ACCURACY;
+		}
+	}
+	result >> whole
+}
+
+impl InflationParameters {
+	/// The yearly inflation paid to stakers when `stake` of the total issuance is staked.
+	pub fn inflation_for(&self, stake: Perquintill) -> Perquintill {
+		let min = perquintill_parts(self.min_inflation);
+		let max = perquintill_parts(self.max_inflation);
+		let ideal = perquintill_parts(self.ideal_stake);
+		let falloff = perquintill_parts(self.falloff);
+		let x = stake.deconstruct() as u128;
+
+		let delta = max.saturating_sub(min);
+		let variable = if x <= ideal {
+			if ideal == 0 { 0 } else { delta * x / ideal }
+		} else {
+			delta * pow2_neg(x - ideal, falloff) / ACCURACY
+		};
+
+		Perquintill::from_parts((min + variable).min(ACCURACY) as u64)
+	}
+
+	/// Split the payout of an era of `era_duration_millis` into the part that goes to stakers and
+	/// the remainder, as `pallet_staking::ConvertCurve` does for a compile-time curve.
+	pub fn era_payout<Balance: AtLeast32BitUnsigned + Clone>(
+		&self,
+		total_staked: Balance,
+		total_issuance: Balance,
+		era_duration_millis: u64,
+	) -> (Balance, Balance) {
+		let staked: u128 = total_staked.saturated_into();
+		let issuance: u128 = total_issuance.saturated_into();
+		let portion = Perquintill::from_rational(era_duration_millis, MILLISECONDS_PER_YEAR);
+
+		let stake = if issuance == 0 {
+			Perquintill::zero()
+		} else {
+			Perquintill::from_rational(staked.min(issuance), issuance)
+		};
+		let max_inflation = Perquintill::from_parts(perquintill_parts(self.max_inflation) as u64);
+		let payout = portion * (self.inflation_for(stake) * issuance);
+		let maximum = portion * (max_inflation * issuance);
+
+		(payout.saturated_into(), maximum.saturating_sub(payout).saturated_into())
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResult, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type Event: From<Event> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The origin allowed to change the inflation parameters.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Upper bound for `max_inflation`, so that a single bad proposal cannot debase the token.
+		#[pallet::constant]
+		type MaxInflation: Get<Perbill>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The parameters of the inflation curve currently used for era payouts.
+	#[pallet::storage]
+	#[pallet::getter(fn parameters)]
+	pub type Parameters<T> = StorageValue<_, InflationParameters, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event {
+		/// The inflation curve was changed. [new_parameters]
+		ParametersUpdated(InflationParameters),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// `min_inflation` is greater than `max_inflation`.
+		MinAboveMax,
+		/// `max_inflation` is greater than the configured `MaxInflation`.
+		InflationTooHigh,
+		/// `ideal_stake` must be strictly between zero and one hundred percent.
+		InvalidIdealStake,
+		/// `falloff` is smaller than `MIN_FALLOFF`.
+		FalloffTooSmall,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Replace the parameters of the inflation curve. They apply from the next era payout on.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn set_parameters(
+			origin: OriginFor<T>,
+			parameters: InflationParameters,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ensure!(parameters.min_inflation <= parameters.max_inflation, Error::<T>::MinAboveMax);
+			ensure!(parameters.max_inflation <= T::MaxInflation::get(), Error::<T>::InflationTooHigh);
+			ensure!(
+				!parameters.ideal_stake.is_zero() && parameters.ideal_stake < Perbill::one(),
+				Error::<T>::InvalidIdealStake
+			);
+			ensure!(parameters.falloff >= MIN_FALLOFF, Error::<T>::FalloffTooSmall);
+
+			<Parameters<T>>::put(parameters);
+			Self::deposit_event(Event::ParametersUpdated(parameters));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config, Balance: AtLeast32BitUnsigned + Clone> pallet_staking::EraPayout<Balance>
+	for Pallet<T>
+{
+	fn era_payout(
+		total_staked: Balance,
+		total_issuance: Balance,
+		era_duration_millis: u64,
+	) -> (Balance, Balance) {
+		Self::parameters().era_payout(total_staked, total_issuance, era_duration_millis)
+	}
+}
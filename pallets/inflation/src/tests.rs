@@ -0,0 +1,142 @@
+use crate::{mock::*, Error, InflationParameters};
+use frame_support::{assert_noop, assert_ok};
+use pallet_staking::EraPayout;
+use sp_runtime::{DispatchError, PerThing, Perbill, Perquintill};
+
+const YEAR: u64 = 1000 * 3600 * 24 * 36525 / 100;
+
+fn assert_close(actual: Perquintill, expected: Perquintill) {
+	let diff = actual.deconstruct().max(expected.deconstruct())
+		- actual.deconstruct().min(expected.deconstruct());
+	// within 0.001 percentage points.
+	assert!(diff < 10_000_000_000_000, "{:?} is not close to {:?}", actual, expected);
+}
+
+#[test]
+fn default_parameters_match_the_shipped_curve() {
+	new_test_ext().execute_with(|| {
+		let params = Inflation::parameters();
+		assert_eq!(params, InflationParameters::default());
+
+		// nothing staked: minimum inflation.
+		assert_close(params.inflation_for(Perquintill::zero()), Perquintill::from_rational(25u64, 1000));
+		// half way to the ideal stake: half way between min and max.
+		assert_close(
+			params.inflation_for(Perquintill::from_percent(25)),
+			Perquintill::from_rational(625u64, 10_000),
+		);
+		// at the ideal stake: maximum inflation.
+		assert_close(params.inflation_for(Perquintill::from_percent(50)), Perquintill::from_percent(10));
+		// one falloff past the ideal stake: the variable part is halved.
+		assert_close(
+			params.inflation_for(Perquintill::from_percent(55)),
+			Perquintill::from_rational(625u64, 10_000),
+		);
+		// half a falloff past: divided by sqrt(2).
+		assert_close(
+			params.inflation_for(Perquintill::from_rational(525u64, 1000)),
+			Perquintill::from_rational(78_033_008_588_991_064u64, 1_000_000_000_000_000_000),
+		);
+		// everything staked: back to the minimum.
+		assert_close(params.inflation_for(Perquintill::one()), Perquintill::from_rational(25u64, 1000));
+	});
+}
+
+#[test]
+fn era_payout_splits_maximum_between_stakers_and_remainder() {
+	new_test_ext().execute_with(|| {
+		let issuance = 1_000_000_000u128;
+
+		// at the ideal stake stakers get everything.
+		let (stakers, rest) = Inflation::era_payout(issuance / 2, issuance, YEAR);
+		assert_eq!(stakers, 100_000_000);
+		assert_eq!(rest, 0);
+
+		// nothing staked, only the minimum goes to stakers.
+		let (stakers, rest) = Inflation::era_payout(0, issuance, YEAR);
+		assert_eq!(stakers, 25_000_000);
+		assert_eq!(rest, 75_000_000);
+
+		// a quarter of a year pays a quarter.
+		let (stakers, rest) = Inflation::era_payout(issuance / 2, issuance, YEAR / 4);
+		assert_eq!(stakers, 25_000_000);
+		assert_eq!(rest, 0);
+
+		// nothing issued, nothing paid.
+		assert_eq!(Inflation::era_payout(0u128, 0, YEAR), (0, 0));
+	});
+}
+
+#[test]
+fn set_parameters_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let params = InflationParameters {
+			min_inflation: Perbill::from_percent(1),
+			max_inflation: Perbill::from_percent(8),
+			ideal_stake: Perbill::from_percent(60),
+			falloff: Perbill::from_percent(10),
+		};
+		assert_ok!(Inflation::set_parameters(Origin::root(), params));
+		assert_eq!(Inflation::parameters(), params);
+		assert_eq!(
+			System::events().last().unwrap().event,
+			Event::pallet_inflation(crate::Event::ParametersUpdated(params)),
+		);
+
+		let (stakers, rest) = Inflation::era_payout(600u128, 1000, YEAR);
+		assert_eq!((stakers, rest), (80, 0));
+	});
+}
+
+#[test]
+fn set_parameters_requires_update_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Inflation::set_parameters(Origin::signed(1), InflationParameters::default()),
+			DispatchError::BadOrigin,
+		);
+	});
+}
+
+#[test]
+fn set_parameters_checks_bounds() {
+	new_test_ext().execute_with(|| {
+		let default = InflationParameters::default();
+		assert_noop!(
+			Inflation::set_parameters(Origin::root(), InflationParameters {
+				min_inflation: Perbill::from_percent(11),
+				..default
+			}),
+			Error::<Test>::MinAboveMax,
+		);
+		assert_noop!(
+			Inflation::set_parameters(Origin::root(), InflationParameters {
+				max_inflation: Perbill::from_percent(21),
+				..default
+			}),
+			Error::<Test>::InflationTooHigh,
+		);
+		assert_noop!(
+			Inflation::set_parameters(Origin::root(), InflationParameters {
+				ideal_stake: Perbill::zero(),
+				..default
+			}),
+			Error::<Test>::InvalidIdealStake,
+		);
+		assert_noop!(
+			Inflation::set_parameters(Origin::root(), InflationParameters {
+				ideal_stake: Perbill::one(),
+				..default
+			}),
+			Error::<Test>::InvalidIdealStake,
+		);
+		assert_noop!(
+			Inflation::set_parameters(Origin::root(), InflationParameters {
+				falloff: Perbill::from_parts(9_999_999),
+				..default
+			}),
+			Error::<Test>::FalloffTooSmall,
+		);
+	});
+}
@@ -0,0 +1,20 @@
+//! Runtime API definition for the price oracle pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_runtime::FixedU128;
+
+sp_api::decl_runtime_apis! {
+	pub trait OracleApi<AssetId, BlockNumber> where
+		AssetId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The price of one unit of `base` in `quote`, unless it is missing or stale.
+		fn price(base: AssetId, quote: AssetId) -> Option<FixedU128>;
+
+		/// The last aggregated price of the pair and the block it was aggregated in, even when
+		/// it is stale.
+		fn last_price(base: AssetId, quote: AssetId) -> Option<(FixedU128, BlockNumber)>;
+	}
+}
@@ -0,0 +1,488 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # Oracle Pallet
+//!
+//! Prices of asset pairs, fed by a set of feeders (by default the validators of the current
+//! session) and aggregated on chain.
+//!
+//! Every `FeedPeriod` blocks, the offchain worker of a feeder node fetches the price of every pair
+//! registered with `add_pair` and submits them in one `submit_prices` transaction, signed with the
+//! node's `orcl` key. That key is a hot key of its own, which a feeder registers as its oracle key
+//! with `set_key`, so that the node does not need the key of the feeder account, e.g. the stash
+//! of a validator. At the end of the block, the prices submitted for each pair are reduced to
+//! their median, which becomes the price of the pair. Prices older than `MaxPriceAge` blocks are
+//! stale: [`Pallet::price`] and the `OracleApi` runtime API do not return them.
+//!
+//! The offchain worker requests `<endpoint>/<base>/<quote>` and expects the price of one unit of
+//! `base` in `quote` as a plain decimal number, e.g. `1.25`. The endpoint is read from the
+//! persistent offchain storage of the node under [`ENDPOINT_KEY`], so each node sets its own
+//! source, e.g. with the `offchain_localStorageSet` RPC. Nodes without an endpoint do not feed.
+
+pub use pallet::*;
+
+use codec::{Decode, Encode};
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer};
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{
+	offchain::{http, Duration, StorageKind},
+	traits::{IdentifyAccount, Saturating, Zero},
+	FixedPointNumber, FixedU128, RuntimeAppPublic, RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The key type of the keys feeders sign their submissions with.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orcl");
+
+/// The key in the persistent offchain storage holding the base URL prices are fetched from.
+pub const ENDPOINT_KEY: &[u8] = b"oracle::endpoint";
+
+/// How long the offchain worker waits for a price.
+const HTTP_TIMEOUT_MS: u64 = 2_000;
+
+/// The crypto of the `orcl` keys.
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_core::sr25519::Signature as Sr25519Signature;
+	use sp_runtime::{
+		app_crypto::{app_crypto, sr25519},
+		traits::Verify,
+		MultiSignature, MultiSigner,
+	};
+
+	app_crypto!(sr25519, KEY_TYPE);
+
+	/// Signs price submissions with an `orcl` key.
+	pub struct OracleAuthId;
+
+	impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for OracleAuthId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+
+	impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature>
+		for OracleAuthId
+	{
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+}
+
+/// The accounts allowed to feed prices.
+pub trait FeederSet<AccountId> {
+	/// Whether `who` may submit prices.
+	fn is_feeder(who: &AccountId) -> bool;
+}
+
+/// The validators of the current session, as known to `pallet_session`.
+pub struct SessionValidators<T>(PhantomData<T>);
+
+impl<T: pallet_session::Config> FeederSet<T::ValidatorId> for SessionValidators<T> {
+	fn is_feeder(who: &T::ValidatorId) -> bool {
+		pallet_session::Pallet::<T>::validators().contains(who)
+	}
+}
+
+/// An aggregated price and the block it was aggregated in.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct PriceInfo<BlockNumber> {
+	/// The price of one unit of the base asset in the quote asset.
+	pub price: FixedU128,
+	/// The block the price was aggregated in.
+	pub updated_at: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{dispatch::DispatchResult, pallet_prelude::*};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: CreateSignedTransaction<Call<Self>> + frame_system::Config {
+		/// The overarching event type.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The key the offchain worker signs submissions with.
+		type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+		/// Identifies the assets of a pair, in storage and in the URL prices are fetched from.
+		type AssetId: Parameter + Member + Copy + Ord + Into<u64>;
+
+		/// The accounts allowed to submit prices.
+		type Feeders: FeederSet<Self::AccountId>;
+
+		/// The origin allowed to add and remove pairs.
+		type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The maximum number of pairs prices are fed for.
+		#[pallet::constant]
+		type MaxPairs: Get<u32>;
+
+		/// How often, in blocks, feeders submit prices. Must not be zero.
+		#[pallet::constant]
+		type FeedPeriod: Get<Self::BlockNumber>;
+
+		/// How many blocks a price stays usable after it was aggregated.
+		#[pallet::constant]
+		type MaxPriceAge: Get<Self::BlockNumber>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// The pairs prices are fed for, as `(base, quote)`.
+	#[pallet::storage]
+	#[pallet::getter(fn pairs)]
+	pub type Pairs<T: Config> = StorageValue<_, Vec<(T::AssetId, T::AssetId)>, ValueQuery>;
+
+	/// The last aggregated price of each pair.
+	#[pallet::storage]
+	#[pallet::getter(fn prices)]
+	pub type Prices<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AssetId, T::AssetId),
+		PriceInfo<T::BlockNumber>,
+	>;
+
+	/// The prices submitted in the current block, aggregated and cleared at its end.
+	#[pallet::storage]
+	#[pallet::getter(fn submissions)]
+	pub type Submissions<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		(T::AssetId, T::AssetId),
+		Vec<(T::AccountId, FixedU128)>,
+		ValueQuery,
+	>;
+
+	/// The oracle key of each feeder.
+	#[pallet::storage]
+	#[pallet::getter(fn keys)]
+	pub type Keys<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
+
+	/// The feeder each oracle key submits prices for.
+	#[pallet::storage]
+	#[pallet::getter(fn key_owner)]
+	pub type KeyOwner<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
+
+	#[pallet::event]
+	#[pallet::metadata(T::AccountId = "AccountId", T::AssetId = "AssetId")]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Prices are now fed for a pair. [base, quote]
+		PairAdded(T::AssetId, T::AssetId),
+		/// Prices are no longer fed for a pair. [base, quote]
+		PairRemoved(T::AssetId, T::AssetId),
+		/// The submissions of the block were aggregated into a new price. [base, quote, price]
+		PriceUpdated(T::AssetId, T::AssetId, FixedU128),
+		/// A feeder registered an oracle key. [feeder, key]
+		KeySet(T::AccountId, T::AccountId),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The sender is not the oracle key of a feeder.
+		NotFeeder,
+		/// Prices are not fed for the pair.
+		UnknownPair,
+		/// Prices are already fed for the pair.
+		PairExists,
+		/// More than `MaxPairs` pairs.
+		TooManyPairs,
+		/// The key is the oracle key of another feeder.
+		KeyInUse,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Aggregate the prices submitted in this block. The weight is paid by `submit_prices`.
+		fn on_finalize(now: T::BlockNumber) {
+			for (pair, submissions) in Submissions::<T>::drain() {
+				let mut prices = submissions.into_iter().map(|(_, price)| price).collect::<Vec<_>>();
+				if let Some(price) = median(&mut prices) {
+					Prices::<T>::insert(pair, PriceInfo { price, updated_at: now });
+					Self::deposit_event(Event::PriceUpdated(pair.0, pair.1, price));
+				}
+			}
+		}
+
+		/// Fetch and submit the prices of all pairs every `FeedPeriod` blocks.
+		fn offchain_worker(now: T::BlockNumber) {
+			if !(now % T::FeedPeriod::get()).is_zero() {
+				return;
+			}
+			if let Err(e) = Self::feed_prices() {
+				log::debug!(target: "runtime::oracle", "Not feeding prices: {}", e);
+			}
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Submit prices for registered pairs on behalf of the feeder of the sender's oracle key,
+		/// replacing the feeder's earlier submissions in the same block.
+		///
+		/// The dispatch origin of this call must be signed by the oracle key of a feeder.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads(1)
+				+ T::DbWeight::get().reads_writes(2, 2).saturating_mul(prices.len() as Weight)
+		)]
+		pub fn submit_prices(
+			origin: OriginFor<T>,
+			prices: Vec<((T::AssetId, T::AssetId), FixedU128)>,
+		) -> DispatchResult {
+			let key = ensure_signed(origin)?;
+			let who = Self::key_owner(&key).ok_or(Error::<T>::NotFeeder)?;
+			ensure!(T::Feeders::is_feeder(&who), Error::<T>::NotFeeder);
+			ensure!(prices.len() <= T::MaxPairs::get() as usize, Error::<T>::TooManyPairs);
+
+			let pairs = Self::pairs();
+			ensure!(prices.iter().all(|(pair, _)| pairs.contains(pair)), Error::<T>::UnknownPair);
+
+			for (pair, price) in prices {
+				Submissions::<T>::mutate(pair, |submissions| {
+					match submissions.iter_mut().find(|(feeder, _)| *feeder == who) {
+						Some(submission) => submission.1 = price,
+						None => submissions.push((who.clone(), price)),
+					}
+				});
+			}
+			Ok(())
+		}
+
+		/// Start feeding prices of `base` in `quote`.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn add_pair(
+			origin: OriginFor<T>,
+			base: T::AssetId,
+			quote: T::AssetId,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<Pairs<T>>::try_mutate(|pairs| -> DispatchResult {
+				ensure!(!pairs.contains(&(base, quote)), Error::<T>::PairExists);
+				ensure!(pairs.len() < T::MaxPairs::get() as usize, Error::<T>::TooManyPairs);
+				pairs.push((base, quote));
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::PairAdded(base, quote));
+			Ok(())
+		}
+
+		/// Stop feeding prices of `base` in `quote` and forget its last price.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 3))]
+		pub fn remove_pair(
+			origin: OriginFor<T>,
+			base: T::AssetId,
+			quote: T::AssetId,
+		) -> DispatchResult {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<Pairs<T>>::try_mutate(|pairs| -> DispatchResult {
+				let index = pairs.iter().position(|pair| *pair == (base, quote))
+					.ok_or(Error::<T>::UnknownPair)?;
+				pairs.remove(index);
+				Ok(())
+			})?;
+			<Prices<T>>::remove((base, quote));
+			<Submissions<T>>::remove((base, quote));
+
+			Self::deposit_event(Event::PairRemoved(base, quote));
+			Ok(())
+		}
+
+		/// Make `key` the oracle key of the sender, replacing its earlier one. The offchain
+		/// worker of the sender's node submits prices with it.
+		///
+		/// The dispatch origin of this call must be signed by the account feeding prices, e.g.
+		/// the stash of a validator.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 3))]
+		pub fn set_key(origin: OriginFor<T>, key: T::AccountId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				Self::key_owner(&key).map_or(true, |owner| owner == who),
+				Error::<T>::KeyInUse
+			);
+
+			if let Some(old) = <Keys<T>>::take(&who) {
+				<KeyOwner<T>>::remove(old);
+			}
+			<Keys<T>>::insert(&who, &key);
+			<KeyOwner<T>>::insert(&key, &who);
+
+			Self::deposit_event(Event::KeySet(who, key));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The price of one unit of `base` in `quote`, unless it is missing or stale.
+	pub fn price(base: T::AssetId, quote: T::AssetId) -> Option<FixedU128> {
+		let info = Self::prices((base, quote))?;
+		let age = frame_system::Pallet::<T>::block_number().saturating_sub(info.updated_at);
+		if age > T::MaxPriceAge::get() {
+			None
+		} else {
+			Some(info.price)
+		}
+	}
+
+	/// The last aggregated price of the pair and the block it was aggregated in, stale or not.
+	pub fn last_price(base: T::AssetId, quote: T::AssetId) -> Option<(FixedU128, T::BlockNumber)> {
+		Self::prices((base, quote)).map(|info| (info.price, info.updated_at))
+	}
+
+	/// Fetch the prices of all pairs and submit them from every oracle key of a feeder in the
+	/// keystore.
+	fn feed_prices() -> Result<(), &'static str> {
+		let endpoint = sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, ENDPOINT_KEY)
+			.ok_or("no endpoint configured")?;
+		let keys = Self::local_keys();
+		if keys.is_empty() {
+			return Err("no oracle key of a feeder in the keystore");
+		}
+		let signer = Signer::<T, T::AuthorityId>::all_accounts().with_filter(keys);
+
+		let mut prices = Vec::new();
+		for (base, quote) in Self::pairs() {
+			match Self::fetch_price(&endpoint, base, quote) {
+				Ok(price) => prices.push(((base, quote), price)),
+				Err(e) => log::warn!(
+					target: "runtime::oracle",
+					"Fetching the price of {:?}/{:?} failed: {:?}",
+					base,
+					quote,
+					e,
+				),
+			}
+		}
+		if prices.is_empty() {
+			return Err("no price fetched");
+		}
+
+		for (account, result) in signer.send_signed_transaction(|_| Call::submit_prices(prices.clone())) {
+			if result.is_err() {
+				log::warn!(target: "runtime::oracle", "Submitting prices from {:?} failed", account.id);
+			}
+		}
+		Ok(())
+	}
+
+	/// The `orcl` keys in the keystore that are the oracle key of a feeder.
+	fn local_keys() -> Vec<T::Public> {
+		<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| {
+				let generic: <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic =
+					key.into();
+				generic.into()
+			})
+			.filter(|public: &T::Public| {
+				Self::key_owner(public.clone().into_account())
+					.map_or(false, |feeder| T::Feeders::is_feeder(&feeder))
+			})
+			.collect()
+	}
+
+	/// Fetch the price of `base` in `quote` from `<endpoint>/<base>/<quote>`.
+	fn fetch_price(
+		endpoint: &[u8],
+		base: T::AssetId,
+		quote: T::AssetId,
+	) -> Result<FixedU128, http::Error> {
+		let mut url = endpoint.to_vec();
+		while url.last() == Some(&b'/') {
+			url.pop();
+		}
+		url.push(b'/');
+		push_decimal(&mut url, base.into());
+		url.push(b'/');
+		push_decimal(&mut url, quote.into());
+		let url = sp_std::str::from_utf8(&url).map_err(|_| http::Error::Unknown)?;
+
+		let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(HTTP_TIMEOUT_MS));
+		let pending = http::Request::get(url)
+			.deadline(deadline)
+			.send()
+			.map_err(|_| http::Error::IoError)?;
+		let response = pending.try_wait(deadline).map_err(|_| http::Error::DeadlineReached)??;
+		if response.code != 200 {
+			log::warn!(target: "runtime::oracle", "Unexpected status code: {}", response.code);
+			return Err(http::Error::Unknown);
+		}
+
+		let body = response.body().collect::<Vec<u8>>();
+		parse_price(&body).ok_or(http::Error::Unknown)
+	}
+}
+
+/// Parse a positive decimal number such as `1.25`, ignoring digits past the 18th decimal.
+pub fn parse_price(body: &[u8]) -> Option<FixedU128> {
+	let text = sp_std::str::from_utf8(body).ok()?.trim();
+	let (int, frac) = match text.find('.') {
+		Some(dot) => (&text[..dot], &text[dot + 1..]),
+		None => (text, ""),
+	};
+	if int.is_empty() && frac.is_empty() {
+		return None;
+	}
+	if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+		return None;
+	}
+
+	let int: u128 = if int.is_empty() { 0 } else { int.parse().ok()? };
+	let mut inner = int.checked_mul(FixedU128::DIV)?;
+	let mut unit = FixedU128::DIV;
+	for digit in frac.bytes() {
+		unit /= 10;
+		inner = inner.checked_add((digit - b'0') as u128 * unit)?;
+	}
+
+	if inner == 0 {
+		None
+	} else {
+		Some(FixedU128::from_inner(inner))
+	}
+}
+
+/// The median of `prices`; the mean of the two middle prices if their number is even.
+fn median(prices: &mut [FixedU128]) -> Option<FixedU128> {
+	if prices.is_empty() {
+		return None;
+	}
+	prices.sort();
+	let mid = prices.len() / 2;
+	if prices.len() % 2 == 1 {
+		return Some(prices[mid]);
+	}
+	let (low, high) = (prices[mid - 1].into_inner(), prices[mid].into_inner());
+	Some(FixedU128::from_inner(low / 2 + high / 2 + (low % 2 + high % 2) / 2))
+}
+
+fn push_decimal(buf: &mut Vec<u8>, mut n: u64) {
+	let start = buf.len();
+	loop {
+		buf.push(b'0' + (n % 10) as u8);
+		n /= 10;
+		if n == 0 {
+			break;
+		}
+	}
+	buf[start..].reverse();
+}
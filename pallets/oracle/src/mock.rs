@@ -0,0 +1,142 @@
+use crate as pallet_oracle;
+use frame_support::parameter_types;
+use frame_system as system;
+use frame_system::EnsureRoot;
+use sp_core::{sr25519::Signature, H256};
+use sp_runtime::{
+    testing::{Header, TestXt},
+    traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup, Verify},
+};
+use std::cell::RefCell;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+pub type Extrinsic = TestXt<Call, ()>;
+pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Oracle: pallet_oracle::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const SS58Prefix: u8 = 42;
+}
+
+impl system::Config for Test {
+    type BaseCallFilter = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = SS58Prefix;
+    type OnSetCode = ();
+}
+
+impl frame_system::offchain::SigningTypes for Test {
+    type Public = <Signature as Verify>::Signer;
+    type Signature = Signature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+where
+    Call: From<LocalCall>,
+{
+    type OverarchingCall = Call;
+    type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Test
+where
+    Call: From<LocalCall>,
+{
+    fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+        call: Call,
+        _public: <Signature as Verify>::Signer,
+        _account: AccountId,
+        nonce: u64,
+    ) -> Option<(Call, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+        Some((call, (nonce, ())))
+    }
+}
+
+thread_local! {
+    static FEEDERS: RefCell<Vec<AccountId>> = RefCell::new(vec![]);
+}
+
+/// Feeders set by the tests with [`set_feeders`].
+pub struct TestFeeders;
+
+impl pallet_oracle::FeederSet<AccountId> for TestFeeders {
+    fn is_feeder(who: &AccountId) -> bool {
+        FEEDERS.with(|feeders| feeders.borrow().contains(who))
+    }
+}
+
+pub fn set_feeders(feeders: Vec<AccountId>) {
+    FEEDERS.with(|f| *f.borrow_mut() = feeders);
+}
+
+parameter_types! {
+    pub const MaxPairs: u32 = 2;
+    pub const FeedPeriod: u64 = 2;
+    pub const MaxPriceAge: u64 = 10;
+}
+
+impl pallet_oracle::Config for Test {
+    type Event = Event;
+    type AuthorityId = pallet_oracle::crypto::OracleAuthId;
+    type AssetId = u32;
+    type Feeders = TestFeeders;
+    type UpdateOrigin = EnsureRoot<AccountId>;
+    type MaxPairs = MaxPairs;
+    type FeedPeriod = FeedPeriod;
+    type MaxPriceAge = MaxPriceAge;
+}
+
+pub fn account(seed: u8) -> AccountId {
+    sp_core::sr25519::Public::from_raw([seed; 32])
+}
+
+/// The oracle key the feeder `account(seed)` registers in [`new_test_ext`].
+pub fn key(seed: u8) -> AccountId {
+    account(seed + 100)
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    set_feeders(vec![account(1), account(2), account(3)]);
+    let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| {
+        System::set_block_number(1);
+        for seed in 1..=3 {
+            Oracle::set_key(Origin::signed(account(seed)), key(seed)).unwrap();
+        }
+    });
+    ext
+}
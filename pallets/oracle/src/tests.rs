@@ -0,0 +1,209 @@
+use crate::{mock::*, parse_price, Error, PriceInfo, ENDPOINT_KEY};
+use codec::Decode;
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{OffchainWorker, OnFinalize},
+};
+use sp_core::offchain::{
+	testing::{PendingRequest, TestOffchainExt, TestTransactionPoolExt},
+	OffchainDbExt, OffchainWorkerExt, StorageKind, TransactionPoolExt,
+};
+use sp_keystore::{testing::KeyStore, KeystoreExt, SyncCryptoStore};
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128, RuntimeAppPublic};
+use std::sync::Arc;
+
+fn price(n: u128, d: u128) -> FixedU128 {
+	FixedU128::saturating_from_rational(n, d)
+}
+
+fn with_pair() -> sp_io::TestExternalities {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| assert_ok!(Oracle::add_pair(Origin::root(), 1, 2)));
+	ext
+}
+
+#[test]
+fn parse_price_reads_decimals() {
+	assert_eq!(parse_price(b"1.25"), Some(price(5, 4)));
+	assert_eq!(parse_price(b" 42\n"), Some(price(42, 1)));
+	assert_eq!(parse_price(b".5"), Some(price(1, 2)));
+	assert_eq!(parse_price(b"0.0000000000000000011"), Some(FixedU128::from_inner(1)));
+	assert_eq!(parse_price(b"0"), None);
+	assert_eq!(parse_price(b"."), None);
+	assert_eq!(parse_price(b"-1"), None);
+	assert_eq!(parse_price(b"{\"price\": 1}"), None);
+}
+
+#[test]
+fn pairs_are_managed_by_update_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Oracle::add_pair(Origin::signed(account(1)), 1, 2), DispatchError::BadOrigin);
+		assert_ok!(Oracle::add_pair(Origin::root(), 1, 2));
+		assert_noop!(Oracle::add_pair(Origin::root(), 1, 2), Error::<Test>::PairExists);
+		assert_ok!(Oracle::add_pair(Origin::root(), 2, 1));
+		assert_noop!(Oracle::add_pair(Origin::root(), 1, 3), Error::<Test>::TooManyPairs);
+		assert_eq!(Oracle::pairs(), vec![(1, 2), (2, 1)]);
+
+		assert_noop!(Oracle::remove_pair(Origin::signed(account(1)), 1, 2), DispatchError::BadOrigin);
+		assert_ok!(Oracle::remove_pair(Origin::root(), 1, 2));
+		assert_noop!(Oracle::remove_pair(Origin::root(), 1, 2), Error::<Test>::UnknownPair);
+		assert_eq!(Oracle::pairs(), vec![(2, 1)]);
+	});
+}
+
+#[test]
+fn only_feeders_submit_for_known_pairs() {
+	with_pair().execute_with(|| {
+		assert_noop!(
+			Oracle::submit_prices(Origin::signed(account(9)), vec![((1, 2), price(1, 1))]),
+			Error::<Test>::NotFeeder
+		);
+		// the feeder account itself is not its oracle key.
+		assert_noop!(
+			Oracle::submit_prices(Origin::signed(account(1)), vec![((1, 2), price(1, 1))]),
+			Error::<Test>::NotFeeder
+		);
+		assert_noop!(
+			Oracle::submit_prices(
+				Origin::signed(key(1)),
+				vec![((1, 2), price(1, 1)), ((1, 3), price(1, 1))],
+			),
+			Error::<Test>::UnknownPair
+		);
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(1)), vec![((1, 2), price(1, 1))]));
+	});
+}
+
+#[test]
+fn keys_of_former_feeders_cannot_submit() {
+	with_pair().execute_with(|| {
+		assert_ok!(Oracle::set_key(Origin::signed(account(9)), key(9)));
+		assert_noop!(
+			Oracle::submit_prices(Origin::signed(key(9)), vec![((1, 2), price(1, 1))]),
+			Error::<Test>::NotFeeder
+		);
+		set_feeders(vec![account(2), account(3)]);
+		assert_noop!(
+			Oracle::submit_prices(Origin::signed(key(1)), vec![((1, 2), price(1, 1))]),
+			Error::<Test>::NotFeeder
+		);
+	});
+}
+
+#[test]
+fn feeders_replace_their_oracle_key() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Oracle::keys(account(1)), Some(key(1)));
+		assert_noop!(Oracle::set_key(Origin::signed(account(2)), key(1)), Error::<Test>::KeyInUse);
+
+		assert_ok!(Oracle::set_key(Origin::signed(account(1)), key(9)));
+		assert_eq!(Oracle::keys(account(1)), Some(key(9)));
+		assert_eq!(Oracle::key_owner(key(9)), Some(account(1)));
+		assert_eq!(Oracle::key_owner(key(1)), None);
+		assert!(System::events().iter().any(|record| record.event
+			== Event::pallet_oracle(crate::Event::KeySet(account(1), key(9)))));
+
+		// the old key is free again.
+		assert_ok!(Oracle::set_key(Origin::signed(account(2)), key(1)));
+	});
+}
+
+#[test]
+fn submissions_are_reduced_to_their_median() {
+	with_pair().execute_with(|| {
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(1)), vec![((1, 2), price(3, 1))]));
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(2)), vec![((1, 2), price(100, 1))]));
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(3)), vec![((1, 2), price(2, 1))]));
+		Oracle::on_finalize(1);
+
+		assert_eq!(Oracle::prices((1, 2)), Some(PriceInfo { price: price(3, 1), updated_at: 1 }));
+		assert_eq!(Oracle::submissions((1, 2)), vec![]);
+		assert!(System::events().iter().any(|record| record.event
+			== Event::pallet_oracle(crate::Event::PriceUpdated(1, 2, price(3, 1)))));
+	});
+}
+
+#[test]
+fn even_number_of_submissions_averages_the_middle() {
+	with_pair().execute_with(|| {
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(1)), vec![((1, 2), price(1, 1))]));
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(2)), vec![((1, 2), price(2, 1))]));
+		Oracle::on_finalize(1);
+
+		assert_eq!(Oracle::price(1, 2), Some(price(3, 2)));
+	});
+}
+
+#[test]
+fn resubmitting_replaces_the_earlier_price() {
+	with_pair().execute_with(|| {
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(1)), vec![((1, 2), price(1, 1))]));
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(1)), vec![((1, 2), price(5, 1))]));
+		assert_eq!(Oracle::submissions((1, 2)), vec![(account(1), price(5, 1))]);
+	});
+}
+
+#[test]
+fn stale_prices_are_not_returned() {
+	with_pair().execute_with(|| {
+		assert_ok!(Oracle::submit_prices(Origin::signed(key(1)), vec![((1, 2), price(1, 1))]));
+		Oracle::on_finalize(1);
+
+		System::set_block_number(11);
+		assert_eq!(Oracle::price(1, 2), Some(price(1, 1)));
+		System::set_block_number(12);
+		assert_eq!(Oracle::price(1, 2), None);
+		assert_eq!(Oracle::last_price(1, 2), Some((price(1, 1), 1)));
+	});
+}
+
+#[test]
+fn offchain_worker_fetches_and_submits_prices() {
+	const PHRASE: &str = "news slush supreme milk chapter athlete soap sausage put clutch what kitten";
+
+	let (offchain, state) = TestOffchainExt::new();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	let keystore = KeyStore::new();
+	let oracle_key = SyncCryptoStore::sr25519_generate_new(
+		&keystore,
+		crate::crypto::Public::ID,
+		Some(&format!("{}/feeder1", PHRASE)),
+	).unwrap();
+
+	let mut t = with_pair();
+	t.register_extension(OffchainWorkerExt::new(offchain.clone()));
+	t.register_extension(OffchainDbExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	state.write().expect_request(PendingRequest {
+		method: "GET".into(),
+		uri: "http://localhost:8000/1/2".into(),
+		response: Some(b"1.5".to_vec()),
+		sent: true,
+		..Default::default()
+	});
+
+	t.execute_with(|| {
+		// no endpoint yet: nothing is fetched.
+		Oracle::offchain_worker(2);
+		assert!(pool_state.read().transactions.is_empty());
+
+		sp_io::offchain::local_storage_set(StorageKind::PERSISTENT, ENDPOINT_KEY, b"http://localhost:8000/");
+		// the key in the keystore is no feeder's oracle key yet.
+		Oracle::offchain_worker(2);
+		assert!(pool_state.read().transactions.is_empty());
+
+		assert_ok!(Oracle::set_key(Origin::signed(account(1)), oracle_key));
+		// not a feed period.
+		Oracle::offchain_worker(3);
+		assert!(pool_state.read().transactions.is_empty());
+
+		Oracle::offchain_worker(4);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature.unwrap().0, 0);
+		assert_eq!(tx.call, Call::Oracle(crate::Call::submit_prices(vec![((1, 2), price(3, 2))])));
+	});
+}
@@ -0,0 +1,204 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! # Staking Payout Pallet
+//!
+//! An offchain worker claiming the staking rewards of the validators whose stash key is in the
+//! keystore of the node. Rewards are only paid out when someone calls `payout_stakers`, and are
+//! lost to the validator and its nominators once the era is more than `HistoryDepth` eras old.
+//!
+//! At most every `PayoutPeriod` blocks, the worker looks for the eras of the history in which a
+//! stash of the keystore was an elected validator and whose rewards were not claimed yet, and
+//! submits `payout_stakers` for them, oldest first and signed with the stash key, which pays the
+//! fees. It submits no more than `MaxPayoutsPerRun` payouts in one run.
+//!
+//! The worker is opt-in. It only runs on nodes whose persistent offchain storage holds `true`
+//! under [`ENABLED_KEY`], which the node writes when started with `--staking-payouts=true` or
+//! `--staking-payouts=false`, and leaves alone otherwise. The `offchain_localStorageSet` RPC
+//! (`0x01` to enable, `0x00` to disable) changes it as well.
+
+pub use pallet::*;
+
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer};
+use pallet_staking::EraIndex;
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{
+	offchain::storage::StorageValueRef,
+	traits::{IdentifyAccount, Saturating, Zero},
+	RuntimeAppPublic,
+};
+use sp_std::prelude::*;
+
+/// The key type of the stash keys payouts are signed with.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"stsh");
+
+/// The key in the persistent offchain storage that enables the offchain worker.
+pub const ENABLED_KEY: &[u8] = b"staking-payout::enabled";
+
+/// The key in the persistent offchain storage holding the block of the last run.
+const LAST_RUN_KEY: &[u8] = b"staking-payout::last-run";
+
+/// The crypto of the `stsh` keys.
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_core::sr25519::Signature as Sr25519Signature;
+	use sp_runtime::{
+		app_crypto::{app_crypto, sr25519},
+		traits::Verify,
+		MultiSignature, MultiSigner,
+	};
+
+	app_crypto!(sr25519, KEY_TYPE);
+
+	/// Signs payouts with a `stsh` key.
+	pub struct StashAuthId;
+
+	impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for StashAuthId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+
+	impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature>
+		for StashAuthId
+	{
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config:
+		CreateSignedTransaction<pallet_staking::Call<Self>>
+		+ pallet_staking::Config
+		+ frame_system::Config
+	{
+		/// The stash keys the offchain worker signs payouts with.
+		type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+		/// The minimum number of blocks between two runs of the offchain worker.
+		#[pallet::constant]
+		type PayoutPeriod: Get<Self::BlockNumber>;
+
+		/// The maximum number of payouts submitted in one run.
+		#[pallet::constant]
+		type MaxPayoutsPerRun: Get<u32>;
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		/// Pay out the unclaimed rewards of the stashes in the keystore, if enabled.
+		fn offchain_worker(now: T::BlockNumber) {
+			if let Err(e) = Self::pay_out(now) {
+				log::debug!(target: "runtime::staking-payout", "Not paying out: {}", e);
+			}
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Submit the payouts of the stashes in the keystore, unless disabled or run recently.
+	fn pay_out(now: T::BlockNumber) -> Result<(), &'static str> {
+		if StorageValueRef::persistent(ENABLED_KEY).get::<bool>() != Some(Some(true)) {
+			return Err("disabled");
+		}
+		let stashes = Self::local_stashes();
+		if stashes.is_empty() {
+			return Err("no bonded stash key in the keystore");
+		}
+		Self::record_run(now)?;
+
+		let mut payouts = 0;
+		for (public, stash) in stashes {
+			for era in Self::unclaimed_eras(&stash) {
+				if payouts >= T::MaxPayoutsPerRun::get() {
+					return Ok(());
+				}
+				payouts += 1;
+
+				let call = pallet_staking::Call::payout_stakers(stash.clone(), era);
+				let result = Signer::<T, T::AuthorityId>::any_account()
+					.with_filter(vec![public.clone()])
+					.send_signed_transaction(|_| call.clone());
+				match result {
+					Some((_, Ok(()))) => log::info!(
+						target: "runtime::staking-payout",
+						"Submitted the payout of era {} for {:?}",
+						era,
+						stash,
+					),
+					_ => log::warn!(
+						target: "runtime::staking-payout",
+						"Submitting the payout of era {} for {:?} failed",
+						era,
+						stash,
+					),
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// The bonded stashes whose key is in the keystore.
+	fn local_stashes() -> Vec<(T::Public, T::AccountId)> {
+		<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| {
+				let generic: <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic =
+					key.into();
+				let public: T::Public = generic.into();
+				let stash = public.clone().into_account();
+				(public, stash)
+			})
+			.filter(|(_, stash)| pallet_staking::Pallet::<T>::bonded(stash).is_some())
+			.collect()
+	}
+
+	/// The eras of the history in which `stash` was an elected validator and whose rewards were
+	/// not claimed yet, oldest first.
+	fn unclaimed_eras(stash: &T::AccountId) -> Vec<EraIndex> {
+		let current = match pallet_staking::Pallet::<T>::current_era() {
+			Some(era) => era,
+			None => return Vec::new(),
+		};
+		let claimed = pallet_staking::Pallet::<T>::bonded(stash)
+			.and_then(|controller| pallet_staking::Pallet::<T>::ledger(controller))
+			.map(|ledger| ledger.claimed_rewards)
+			.unwrap_or_default();
+		let oldest = current.saturating_sub(pallet_staking::Pallet::<T>::history_depth());
+
+		(oldest..=current)
+			.filter(|era| !claimed.contains(era))
+			// the reward of an era is known once it ended.
+			.filter(|era| pallet_staking::Pallet::<T>::eras_validator_reward(*era).is_some())
+			.filter(|era| !pallet_staking::Pallet::<T>::eras_stakers(*era, stash).total.is_zero())
+			.collect()
+	}
+
+	/// Record a run at `now`, unless the last one was less than `PayoutPeriod` blocks ago, which
+	/// also keeps a payout from being submitted again before it had time to be included.
+	fn record_run(now: T::BlockNumber) -> Result<(), &'static str> {
+		let last_run = StorageValueRef::persistent(LAST_RUN_KEY);
+		let result = last_run.mutate(|last: Option<Option<T::BlockNumber>>| match last {
+			Some(Some(last)) if now < last.saturating_add(T::PayoutPeriod::get()) => Err(()),
+			_ => Ok(now),
+		});
+		match result {
+			Ok(Ok(_)) => Ok(()),
+			Ok(Err(_)) => Err("another run is in progress"),
+			Err(()) => Err("paid out recently"),
+		}
+	}
+}